@@ -1,8 +1,18 @@
 use tauri::command;
 
+/// 将二进制数据编码为标准Base64（供BLE等模块复用）
+pub fn encode_bytes(data: &[u8]) -> String {
+    base64::encode(data)
+}
+
+/// 解码标准Base64为二进制数据
+pub fn decode_bytes(encoded: &str) -> Result<Vec<u8>, String> {
+    base64::decode(encoded.trim()).map_err(|_| "解码失败：无效的Base64格式".to_string())
+}
+
 #[command]
 pub fn encode_base64_text(text: String) -> Result<String, String> {
-    Ok(base64::encode(text))
+    Ok(encode_bytes(text.as_bytes()))
 }
 
 #[command]
@@ -1,9 +1,599 @@
-use btleplug::api::{Central, Manager as _, Peripheral, ScanFilter, WriteType, Characteristic};
-use btleplug::platform::Manager as BtleplugManager;
+use btleplug::api::bleuuid::uuid_from_u32;
+use btleplug::api::{CharPropFlags, ScanFilter, WriteType, Characteristic};
+use chrono::Utc;
+use futures::stream::StreamExt;
+use regex::Regex;
 use serde::{Serialize, Deserialize};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 use tokio::sync::Mutex;
-use tauri::{command, window, Emitter, Manager};
+use tokio::task::JoinHandle;
+use tauri::{command, window, AppHandle, Emitter, Manager, State};
+use uuid::Uuid;
+
+use crate::ble_backend::{Advertisement, BackendEvent, BleBackend, BtleplugBackend, EventStream, NotificationStream};
+use crate::ble_beacon::{estimate_distance, parse_beacon, Beacon};
+use crate::ble_codec::{decode_value, encode_value, to_hex, ValueEncoding};
+use crate::ble_link::{link_info, throughput_test, LinkInfo, ThroughputReport, ThroughputRequest};
+use crate::ble_dfu::{run_transfer, TransferOptions, TransferReport};
+use crate::ble_decoders::{decode, DecodedValue};
+use crate::ble_error::BleError;
+use crate::ble_schema::{SchemaRegistry, SchemaSummary};
+use crate::ble_session::{
+    read_ndjson, Direction, ExportFormat, SessionLog, SessionSnapshot, TrafficEntry, TrafficKind,
+};
+use crate::ble_sequence::{run_sequence, BleSequence, SequenceReport};
+use crate::ble_sim::{SimScenario, SimulatedBackend};
+use crate::ble_uuid::sig_name;
+
+/// BLE 共享状态，在 `lib.rs` 中通过 `.manage(BleState::from_env())` 注册
+///
+/// 各命令复用同一个后端，扫描到的外设、已连接外设及其发现的服务
+/// 在命令之间保持一致（部分平台上新建 Manager 看不到之前扫描到的设备）。
+pub struct BleState {
+    backend: Arc<dyn BleBackend>,
+    subscriptions: Mutex<HashMap<String, DeviceSubscriptions>>, // 设备ID → 订阅
+    connections: Mutex<HashMap<String, ConnectionEntry>>, // 通过 connect_device 建立的连接
+    event_watcher: Mutex<Option<JoinHandle<()>>>, // 监听适配器事件（断线检测）
+    schemas: SchemaStore, // 用户自定义的特征解析规则
+    session: SessionStore, // 本次会话的操作时间线
+    transfers: std::sync::Mutex<HashMap<String, Arc<AtomicBool>>>, // 进行中的分块传输 → 取消标志
+}
+
+type SchemaStore = Arc<RwLock<SchemaRegistry>>;
+type SessionStore = Arc<std::sync::Mutex<SessionLog>>;
+
+/// 解码特征值：优先使用用户schema，其次是SIG标准解码器
+fn decode_characteristic(
+    schemas: &SchemaStore,
+    characteristic: &Characteristic,
+    data: &[u8],
+) -> Option<DecodedValue> {
+    schemas
+        .read()
+        .unwrap()
+        .decode(&characteristic.service_uuid, &characteristic.uuid, data)
+        .or_else(|| decode(&characteristic.uuid, data))
+}
+
+/// 单个特征的订阅配置
+struct Subscription {
+    characteristic: Characteristic, // 重连后据此重新订阅
+    encoding: ValueEncoding,
+    recorder: Option<Recorder>, // 录制文件，每条通知追加一行JSON
+}
+
+/// 订阅按 (服务UUID, 特征UUID) 区分，不同服务下可以有相同 UUID 的特征
+type SubscriptionKey = (Uuid, Uuid);
+
+fn subscription_key(characteristic: &Characteristic) -> SubscriptionKey {
+    (characteristic.service_uuid, characteristic.uuid)
+}
+
+type SubscriptionMap = Arc<std::sync::Mutex<HashMap<SubscriptionKey, Subscription>>>;
+
+/// 通知录制文件；文件写入在单独的线程中进行，转发任务只把 JSON 行放入通道
+///
+/// 订阅取消后发送端被丢弃，写入线程处理完剩余的行后退出。
+struct Recorder {
+    lines: std::sync::mpsc::Sender<String>,
+}
+
+impl Recorder {
+    fn open(path: &str) -> Result<Self, BleError> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| BleError::io(path, format!("无法打开录制文件: {}", e)))?;
+        let (lines, rx) = std::sync::mpsc::channel::<String>();
+        let path = path.to_string();
+        std::thread::spawn(move || {
+            for line in rx {
+                if let Err(e) = writeln!(file, "{}", line) {
+                    eprintln!("Failed to record notification to {}: {}", path, e);
+                }
+            }
+        });
+        Ok(Recorder { lines })
+    }
+
+    fn record(&self, line: String) {
+        // 写入线程只会在发送端全部丢弃后退出，这里发送不会失败
+        let _ = self.lines.send(line);
+    }
+}
+
+/// 转发任务产生的通知交给谁处理；命令中发出 `ble-notification` 事件
+pub(crate) type NotificationSink = Arc<dyn Fn(BleNotification) + Send + Sync>;
+
+fn notification_events(app: &AppHandle) -> NotificationSink {
+    let app = app.clone();
+    Arc::new(move |event| {
+        if let Err(e) = app.emit("ble-notification", &event) {
+            eprintln!("Failed to emit notification: {}", e);
+        }
+    })
+}
+
+/// 一个设备上的全部订阅，以及转发该设备通知流的后台任务
+///
+/// 断线重连期间 `forwarder` 为 `None`，订阅配置保留，重连成功后恢复。
+struct DeviceSubscriptions {
+    characteristics: SubscriptionMap,
+    forwarder: Option<JoinHandle<()>>,
+}
+
+/// 已跟踪连接的状态
+struct ConnectionEntry {
+    name: Option<String>,
+    state: ConnectionState,
+    connected_since: Option<String>,
+    policy: Option<ReconnectPolicy>,
+}
+
+impl Default for BleState {
+    fn default() -> Self {
+        BleState::new(Arc::new(BtleplugBackend::default()))
+    }
+}
+
+impl BleState {
+    pub fn new(backend: Arc<dyn BleBackend>) -> Self {
+        BleState {
+            backend,
+            subscriptions: Mutex::default(),
+            connections: Mutex::default(),
+            event_watcher: Mutex::default(),
+            schemas: SchemaStore::default(),
+            session: SessionStore::default(),
+            transfers: std::sync::Mutex::default(),
+        }
+    }
+
+    /// 根据环境变量选择后端
+    ///
+    /// `BLE_BACKEND=simulated` 使用模拟后端，场景文件由 `BLE_SIM_SCENARIO` 指定，
+    /// 未指定时使用内置演示场景；其他情况使用 btleplug。
+    pub fn from_env() -> Self {
+        match std::env::var("BLE_BACKEND").as_deref() {
+            Ok("simulated") | Ok("sim") => {
+                let scenario = match std::env::var("BLE_SIM_SCENARIO") {
+                    Ok(path) => SimScenario::from_file(&path).unwrap_or_else(|e| {
+                        eprintln!("{}，改用内置演示场景", e);
+                        SimScenario::demo()
+                    }),
+                    Err(_) => SimScenario::demo(),
+                };
+                match SimulatedBackend::new(&scenario) {
+                    Ok(backend) => {
+                        println!("Using simulated BLE backend");
+                        BleState::new(Arc::new(backend))
+                    }
+                    Err(e) => {
+                        eprintln!("模拟场景无效: {}，改用 btleplug", e);
+                        BleState::default()
+                    }
+                }
+            }
+            _ => BleState::default(),
+        }
+    }
+
+    /// 启动适配器事件监听（只启动一次），用于发现意外断线
+    async fn ensure_event_watcher(&self, app: &AppHandle) -> Result<(), BleError> {
+        let mut watcher = self.event_watcher.lock().await;
+        if watcher.is_some() {
+            return Ok(());
+        }
+
+        let events = self.backend.events().await?;
+        *watcher = Some(tokio::spawn(watch_backend_events(app.clone(), events)));
+        Ok(())
+    }
+
+    /// 为设备启动通知转发任务
+    async fn start_forwarder(
+        &self,
+        sink: NotificationSink,
+        id: &str,
+        characteristics: SubscriptionMap,
+    ) -> Result<JoinHandle<()>, BleError> {
+        let stream = self.backend.notifications(id).await?;
+        Ok(tokio::spawn(forward_notifications(
+            sink,
+            id.to_string(),
+            stream,
+            characteristics,
+            self.schemas.clone(),
+            self.session.clone(),
+        )))
+    }
+
+    /// 停止设备的通知转发；`keep_subscriptions` 为 true 时保留订阅配置以便重连后恢复
+    async fn stop_forwarding(&self, id: &str, keep_subscriptions: bool) {
+        let mut subscriptions = self.subscriptions.lock().await;
+        if keep_subscriptions {
+            if let Some(forwarder) = subscriptions.get_mut(id).and_then(|d| d.forwarder.take()) {
+                forwarder.abort();
+            }
+        } else if let Some(forwarder) = subscriptions.remove(id).and_then(|d| d.forwarder) {
+            forwarder.abort();
+        }
+    }
+
+    /// 重连后重新订阅之前的特征并恢复转发
+    async fn resume_subscriptions(&self, sink: NotificationSink, id: &str) -> Result<(), BleError> {
+        let mut subscriptions = self.subscriptions.lock().await;
+        let Some(device) = subscriptions.get_mut(id) else {
+            return Ok(());
+        };
+
+        let targets: Vec<Characteristic> = device
+            .characteristics
+            .lock()
+            .unwrap()
+            .values()
+            .map(|s| s.characteristic.clone())
+            .collect();
+        if targets.is_empty() {
+            return Ok(());
+        }
+
+        // 上一次失败的重连尝试可能留下了转发任务，先停止，避免同一通知被转发多次
+        if let Some(forwarder) = device.forwarder.take() {
+            forwarder.abort();
+        }
+        // 先打开通知流再订阅，订阅后立即到达的通知不会丢失
+        let forwarder = self
+            .start_forwarder(sink, id, device.characteristics.clone())
+            .await?;
+        for target in &targets {
+            if let Err(e) = self.backend.subscribe(id, target).await {
+                forwarder.abort();
+                return Err(e);
+            }
+        }
+        device.forwarder = Some(forwarder);
+        println!("Restored {} subscriptions on {}", targets.len(), id);
+        Ok(())
+    }
+
+    /// 订阅特征并确保设备的通知转发任务在运行
+    ///
+    /// 先打开通知流、登记订阅配置，再向设备发起订阅，订阅后立即到达的通知不会丢失；
+    /// 订阅失败时撤销登记。
+    async fn subscribe(
+        &self,
+        sink: NotificationSink,
+        id: &str,
+        target: Characteristic,
+        encoding: ValueEncoding,
+        recorder: Option<Recorder>,
+    ) -> Result<(), BleError> {
+        let mut subscriptions = self.subscriptions.lock().await;
+        let device = subscriptions
+            .entry(id.to_string())
+            .or_insert_with(|| DeviceSubscriptions {
+                characteristics: SubscriptionMap::default(),
+                forwarder: None,
+            });
+        if device.forwarder.is_none() {
+            device.forwarder = Some(
+                self.start_forwarder(sink, id, device.characteristics.clone())
+                    .await?,
+            );
+        }
+        let key = subscription_key(&target);
+        let previous = device.characteristics.lock().unwrap().insert(
+            key,
+            Subscription {
+                characteristic: target.clone(),
+                encoding,
+                recorder,
+            },
+        );
+
+        let result = self.backend.subscribe(id, &target).await;
+        self.record(
+            TrafficEntry::new(TrafficKind::Subscribe, Direction::Outgoing, id)
+                .characteristic(&target)
+                .result(&result),
+        );
+        if let Err(e) = result {
+            let mut characteristics = device.characteristics.lock().unwrap();
+            match previous {
+                Some(previous) => characteristics.insert(key, previous),
+                None => characteristics.remove(&key),
+            };
+            if characteristics.is_empty() {
+                drop(characteristics);
+                if let Some(forwarder) = subscriptions.remove(id).and_then(|d| d.forwarder) {
+                    forwarder.abort();
+                }
+            }
+            return Err(e);
+        }
+        println!("Subscribed to {} on {}", target.uuid, id);
+        Ok(())
+    }
+
+    /// 取消订阅并移除订阅配置，设备上最后一个订阅取消后停止转发任务
+    async fn unsubscribe(&self, id: &str, target: &Characteristic) -> Result<(), BleError> {
+        let result = self.backend.unsubscribe(id, target).await;
+        self.record(
+            TrafficEntry::new(TrafficKind::Unsubscribe, Direction::Outgoing, id)
+                .characteristic(target)
+                .result(&result),
+        );
+        result?;
+        println!("Unsubscribed from {} on {}", target.uuid, id);
+
+        let mut subscriptions = self.subscriptions.lock().await;
+        let now_empty = match subscriptions.get(id) {
+            Some(device) => {
+                let mut characteristics = device.characteristics.lock().unwrap();
+                characteristics.remove(&subscription_key(target));
+                characteristics.is_empty()
+            }
+            None => false,
+        };
+
+        if now_empty {
+            if let Some(forwarder) = subscriptions.remove(id).and_then(|d| d.forwarder) {
+                forwarder.abort();
+            }
+        }
+        Ok(())
+    }
+
+    /// 加载自定义特征解析规则文件
+    pub fn load_schema(&self, path: &str) -> Result<SchemaSummary, BleError> {
+        let summary = self.schemas.write().unwrap().load_file(path)?;
+        println!(
+            "Loaded BLE schema {} ({} characteristics)",
+            summary.source,
+            summary.characteristics.len()
+        );
+        Ok(summary)
+    }
+
+    /// 向会话时间线追加一条记录
+    pub(crate) fn record(&self, entry: TrafficEntry) {
+        self.session.lock().unwrap().record(entry);
+    }
+
+    pub(crate) fn backend(&self) -> &dyn BleBackend {
+        self.backend.as_ref()
+    }
+
+    /// 前端是否通过 subscribe_characteristic 订阅了该特征
+    pub(crate) async fn has_subscription(&self, id: &str, characteristic: &Characteristic) -> bool {
+        let key = subscription_key(characteristic);
+        self.subscriptions
+            .lock()
+            .await
+            .get(id)
+            .is_some_and(|d| d.characteristics.lock().unwrap().contains_key(&key))
+    }
+
+    /// 当前后端下满足过滤条件的已发现设备
+    pub async fn discovered_devices(&self, filter: &DeviceFilter) -> Result<Vec<DeviceInfo>, BleError> {
+        let adverts = self.backend.advertisements().await?;
+        Ok(adverts
+            .into_iter()
+            .filter(|advert| filter.matches(advert))
+            .map(|advert| {
+                let beacon = parse_beacon(&advert);
+                DeviceInfo {
+                    estimated_distance_m: estimate_distance(&advert, beacon.as_ref()),
+                    beacon,
+                    id: advert.id,
+                    name: advert.name,
+                    rssi: advert.rssi,
+                    service_count: Some(advert.services.len()),
+                    is_connectable: false, // Field not available, set to default
+                }
+            })
+            .collect())
+    }
+
+    /// 确保设备已连接且完成服务发现
+    pub async fn ensure_connected(&self, id: &str) -> Result<(), BleError> {
+        if !self.backend.is_connected(id).await? {
+            self.backend.connect(id).await?;
+        }
+        self.backend.services(id).await?;
+        Ok(())
+    }
+
+    /// 连接设备并记录到连接表
+    pub async fn connect(&self, id: &str, policy: Option<ReconnectPolicy>) -> Result<(), BleError> {
+        let result = self.ensure_connected(id).await;
+        self.record(TrafficEntry::new(TrafficKind::Connect, Direction::Outgoing, id).result(&result));
+        result?;
+
+        let name = self
+            .backend
+            .advertisements()
+            .await
+            .ok()
+            .and_then(|adverts| adverts.into_iter().find(|a| a.id == id))
+            .and_then(|a| a.name);
+        self.connections.lock().await.insert(
+            id.to_string(),
+            ConnectionEntry {
+                name,
+                state: ConnectionState::Connected,
+                connected_since: Some(now_rfc3339()),
+                policy,
+            },
+        );
+        Ok(())
+    }
+
+    /// 主动断开设备，不触发自动重连
+    pub async fn disconnect(&self, id: &str) -> Result<(), BleError> {
+        // 先移除连接记录，事件监听据此识别为主动断开，不会触发重连
+        self.connections.lock().await.remove(id);
+        self.stop_forwarding(id, false).await;
+        let result = self.backend.disconnect(id).await;
+        self.record(TrafficEntry::new(TrafficKind::Disconnect, Direction::Outgoing, id).result(&result));
+        result
+    }
+
+    /// 按服务UUID + 特征UUID定位特征
+    pub async fn find_characteristic(
+        &self,
+        id: &str,
+        service: &str,
+        characteristic: &str,
+    ) -> Result<Characteristic, BleError> {
+        let service_uuid = parse_uuid(service)?;
+        let characteristic_uuid = parse_uuid(characteristic)?;
+
+        self.backend
+            .services(id)
+            .await?
+            .into_iter()
+            .flat_map(|s| s.characteristics.into_iter())
+            .find(|c| c.service_uuid == service_uuid && c.uuid == characteristic_uuid)
+            .ok_or_else(|| BleError::CharacteristicNotFound {
+                service: service_uuid.to_string(),
+                characteristic: characteristic_uuid.to_string(),
+            })
+    }
+
+    pub async fn gatt_services(&self, id: &str) -> Result<Vec<GattService>, BleError> {
+        self.ensure_connected(id).await?;
+
+        let services = self
+            .backend
+            .services(id)
+            .await?
+            .into_iter()
+            .map(|service| GattService {
+                uuid: service.uuid.to_string(),
+                name: sig_name(&service.uuid).map(String::from),
+                primary: service.primary,
+                characteristics: service
+                    .characteristics
+                    .into_iter()
+                    .map(|characteristic| GattCharacteristic {
+                        uuid: characteristic.uuid.to_string(),
+                        name: sig_name(&characteristic.uuid).map(String::from),
+                        properties: property_names(characteristic.properties),
+                        descriptors: characteristic
+                            .descriptors
+                            .into_iter()
+                            .map(|descriptor| GattDescriptor {
+                                uuid: descriptor.uuid.to_string(),
+                                name: sig_name(&descriptor.uuid).map(String::from),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(services)
+    }
+
+    pub async fn read(
+        &self,
+        id: &str,
+        service: &str,
+        characteristic: &str,
+        encoding: ValueEncoding,
+    ) -> Result<CharacteristicValue, BleError> {
+        let result = self.read_raw(id, service, characteristic).await;
+        let entry = TrafficEntry::new(TrafficKind::Read, Direction::Incoming, id);
+        self.record(match &result {
+            Ok((target, data)) => entry.characteristic(target).data(data),
+            Err(_) => entry.target(service, characteristic).result(&result),
+        });
+
+        let (target, data) = result?;
+        self.characteristic_value(&target, &data, encoding)
+    }
+
+    async fn read_raw(
+        &self,
+        id: &str,
+        service: &str,
+        characteristic: &str,
+    ) -> Result<(Characteristic, Vec<u8>), BleError> {
+        self.ensure_connected(id).await?;
+
+        let target = self.find_characteristic(id, service, characteristic).await?;
+        if !target.properties.contains(CharPropFlags::READ) {
+            return Err(BleError::unsupported(target.uuid, "read"));
+        }
+
+        let data = self.backend.read(id, &target).await?;
+        println!("Read {} bytes from {}", data.len(), target.uuid);
+        Ok((target, data))
+    }
+
+    /// 按指定编码转换特征值，并附带十六进制和结构化解码结果
+    pub fn characteristic_value(
+        &self,
+        target: &Characteristic,
+        data: &[u8],
+        encoding: ValueEncoding,
+    ) -> Result<CharacteristicValue, BleError> {
+        Ok(CharacteristicValue {
+            uuid: target.uuid.to_string(),
+            encoding,
+            value: encode_value(data, encoding).map_err(BleError::InvalidInput)?,
+            hex: to_hex(data),
+            length: data.len(),
+            decoded: decode_characteristic(&self.schemas, target, data),
+        })
+    }
+
+    pub async fn write(
+        &self,
+        id: &str,
+        service: &str,
+        characteristic: &str,
+        data: &[u8],
+        mode: WriteMode,
+    ) -> Result<(), BleError> {
+        let result = self.write_raw(id, service, characteristic, data, mode).await;
+        let entry = TrafficEntry::new(TrafficKind::Write, Direction::Outgoing, id).data(data);
+        self.record(match &result {
+            Ok(target) => entry.characteristic(target),
+            Err(_) => entry.target(service, characteristic).result(&result),
+        });
+        result.map(|_| ())
+    }
+
+    async fn write_raw(
+        &self,
+        id: &str,
+        service: &str,
+        characteristic: &str,
+        data: &[u8],
+        mode: WriteMode,
+    ) -> Result<Characteristic, BleError> {
+        self.ensure_connected(id).await?;
+
+        let target = self.find_characteristic(id, service, characteristic).await?;
+        let (write_type, required, operation) = mode.requirements();
+        if !target.properties.contains(required) {
+            return Err(BleError::unsupported(target.uuid, operation));
+        }
+
+        self.backend.write(id, &target, data, write_type).await?;
+        println!("Wrote {} bytes to {}", data.len(), target.uuid);
+
+        Ok(target)
+    }
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug)] // 添加Debug以便日志输出
 pub struct DeviceInfo {
@@ -12,64 +602,291 @@ pub struct DeviceInfo {
     pub rssi: Option<i16>, // 添加RSSI字段
     pub service_count: Option<usize>, // 添加服务数量字段
     pub is_connectable: bool, // 添加可连接性字段
+    pub beacon: Option<Beacon>, // iBeacon / Eddystone / AltBeacon 解析结果
+    pub estimated_distance_m: Option<f64>, // 按 RSSI 估算的距离
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GattDescriptor {
+    pub uuid: String,
+    pub name: Option<String>, // SIG标准名称
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GattCharacteristic {
+    pub uuid: String,
+    pub name: Option<String>,
+    pub properties: Vec<String>, // 如 ["read", "notify"]
+    pub descriptors: Vec<GattDescriptor>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GattService {
+    pub uuid: String,
+    pub name: Option<String>,
+    pub primary: bool,
+    pub characteristics: Vec<GattCharacteristic>,
+}
+
+/// 写入方式，对应 btleplug 的 `WriteType`
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteMode {
+    #[default]
+    WithResponse,
+    WithoutResponse,
+}
+
+impl WriteMode {
+    /// 对应的写入类型、特征必须具备的属性，以及报错时使用的操作名
+    pub(crate) fn requirements(self) -> (WriteType, CharPropFlags, &'static str) {
+        match self {
+            WriteMode::WithResponse => (WriteType::WithResponse, CharPropFlags::WRITE, "write"),
+            WriteMode::WithoutResponse => (
+                WriteType::WithoutResponse,
+                CharPropFlags::WRITE_WITHOUT_RESPONSE,
+                "write_without_response",
+            ),
+        }
+    }
+}
+
+/// 读取到的特征值
+#[derive(Serialize, Clone, Debug)]
+pub struct CharacteristicValue {
+    pub uuid: String,
+    pub encoding: ValueEncoding,
+    pub value: String, // 按请求的编码转换后的值
+    pub hex: String,   // 始终附带十六进制原始值，便于对照
+    pub length: usize,
+    pub decoded: Option<DecodedValue>, // 已知特征的结构化解析结果
+}
+
+/// `ble-notification` 事件的负载
+#[derive(Serialize, Clone, Debug)]
+pub struct BleNotification {
+    pub device_id: String,
+    pub service: String,
+    pub characteristic: String,
+    pub value: String, // 按订阅时指定的编码转换，转换失败时为十六进制
+    pub hex: String,
+    pub timestamp: String, // RFC 3339，毫秒精度
+    pub timestamp_ms: i64,
+    pub decoded: Option<DecodedValue>,
+}
+
+/// 连接状态
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connected,
+    Disconnected,
+    Reconnecting,
+    ReconnectFailed,
+}
+
+/// `ble-connection-state` 事件的负载
+#[derive(Serialize, Clone, Debug)]
+pub struct ConnectionStateEvent {
+    pub device_id: String,
+    pub state: ConnectionState,
+    pub reason: Option<String>,
+    pub attempt: Option<u32>, // 重连中的第几次尝试
+    pub timestamp: String,
+}
+
+/// 断线自动重连策略，延迟按 `multiplier` 指数退避，不超过 `max_delay_ms`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReconnectPolicy {
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_initial_delay_ms")]
+    pub initial_delay_ms: u64,
+    #[serde(default = "default_max_delay_ms")]
+    pub max_delay_ms: u64,
+    #[serde(default = "default_multiplier", deserialize_with = "backoff_multiplier")]
+    pub multiplier: f64, // 至少为 1，否则延迟会缩短到 0
+}
+
+fn default_max_attempts() -> u32 {
+    5
+}
+
+fn default_initial_delay_ms() -> u64 {
+    500
+}
+
+fn default_max_delay_ms() -> u64 {
+    30_000
+}
+
+fn default_multiplier() -> f64 {
+    2.0
+}
+
+fn backoff_multiplier<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let multiplier = f64::deserialize(deserializer)?;
+    if !multiplier.is_finite() || multiplier < 1.0 {
+        return Err(serde::de::Error::custom(format!(
+            "multiplier 必须是不小于 1 的有限数值，实际为 {}",
+            multiplier
+        )));
+    }
+    Ok(multiplier)
+}
+
+/// `list_connected_devices` 返回的设备
+#[derive(Serialize, Clone, Debug)]
+pub struct ConnectedDevice {
+    pub id: String,
+    pub name: Option<String>,
+    pub state: ConnectionState,
+    pub connected_since: Option<String>,
+    pub auto_reconnect: bool,
+}
+
+/// 前端传入的扫描过滤条件
+///
+/// `services` 直接交给 btleplug 的 `ScanFilter`（由系统蓝牙栈过滤），
+/// 其余条件在 Rust 侧对广播属性逐一判断，全部满足的设备才会上报给前端。
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct ScanFilterSpec {
+    #[serde(default)]
+    pub services: Vec<String>, // 服务UUID，支持完整格式或16位短格式（如 "180d"）
+    pub name_pattern: Option<String>, // 设备名正则
+    pub min_rssi: Option<i16>, // 最低信号强度
+    pub manufacturer_id: Option<u16>, // 厂商ID（广播中的 manufacturer data key）
+}
+
+/// 编译后的 Rust 侧过滤器
+#[derive(Default)]
+pub struct DeviceFilter {
+    name: Option<Regex>,
+    min_rssi: Option<i16>,
+    manufacturer_id: Option<u16>,
+}
+
+impl DeviceFilter {
+    fn matches(&self, props: &Advertisement) -> bool {
+        if let Some(re) = &self.name {
+            match &props.name {
+                Some(name) if re.is_match(name) => {}
+                _ => return false,
+            }
+        }
+        if let Some(min) = self.min_rssi {
+            match props.rssi {
+                Some(rssi) if rssi >= min => {}
+                _ => return false,
+            }
+        }
+        if let Some(id) = self.manufacturer_id {
+            if !props.manufacturer_data.contains_key(&id) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// 解析UUID字符串，支持完整的128位格式以及蓝牙SIG的16位/32位短格式
+pub fn parse_uuid(text: &str) -> Result<Uuid, BleError> {
+    let text = text.trim();
+    let short = text.trim_start_matches("0x").trim_start_matches("0X");
+    if short.len() == 4 || short.len() == 8 {
+        if let Ok(value) = u32::from_str_radix(short, 16) {
+            return Ok(uuid_from_u32(value));
+        }
+    }
+    Uuid::parse_str(text).map_err(|e| BleError::InvalidInput(format!("无效的UUID '{}': {}", text, e)))
+}
+
+impl ScanFilterSpec {
+    pub fn compile(&self) -> Result<(ScanFilter, DeviceFilter), BleError> {
+        let services = self
+            .services
+            .iter()
+            .map(|s| parse_uuid(s))
+            .collect::<Result<Vec<_>, _>>()?;
+        let name = match &self.name_pattern {
+            Some(pattern) if !pattern.is_empty() => {
+                Some(Regex::new(pattern)
+                    .map_err(|e| BleError::InvalidInput(format!("设备名正则无效: {}", e)))?)
+            }
+            _ => None,
+        };
+
+        Ok((
+            ScanFilter { services },
+            DeviceFilter {
+                name,
+                min_rssi: self.min_rssi,
+                manufacturer_id: self.manufacturer_id,
+            },
+        ))
+    }
+}
+
+/// 特征属性标志与前端使用的名称
+pub(crate) const CHAR_PROPERTY_NAMES: &[(CharPropFlags, &str)] = &[
+    (CharPropFlags::BROADCAST, "broadcast"),
+    (CharPropFlags::READ, "read"),
+    (CharPropFlags::WRITE_WITHOUT_RESPONSE, "write_without_response"),
+    (CharPropFlags::WRITE, "write"),
+    (CharPropFlags::NOTIFY, "notify"),
+    (CharPropFlags::INDICATE, "indicate"),
+    (CharPropFlags::AUTHENTICATED_SIGNED_WRITES, "authenticated_signed_writes"),
+    (CharPropFlags::EXTENDED_PROPERTIES, "extended_properties"),
+];
+
+fn property_names(flags: CharPropFlags) -> Vec<String> {
+    CHAR_PROPERTY_NAMES
+        .iter()
+        .filter(|(flag, _)| flags.contains(*flag))
+        .map(|(_, name)| name.to_string())
+        .collect()
 }
 
 #[tauri::command]
-pub async fn scan_devices(window: tauri::Window) -> Result<(), String> {
+pub async fn scan_devices(
+    window: tauri::Window,
+    state: State<'_, BleState>,
+    filter: Option<ScanFilterSpec>,
+) -> Result<(), BleError> {
     println!("Starting BLE scan...");
-    
-    let manager = BtleplugManager::new().await.map_err(|e| e.to_string())?;
-    let adapters = manager.adapters().await.map_err(|e| e.to_string())?;
+    let (scan_filter, device_filter) = filter.unwrap_or_default().compile()?;
 
-    if adapters.is_empty() {
-        println!("No BLE adapters found");
-        window.emit("ble_scan_update", Vec::<DeviceInfo>::new()).map_err(|e| e.to_string())?;
-        return Err("No adapters found".to_string());
+    if let Err(e) = state.backend.start_scan(scan_filter).await {
+        window
+            .emit("ble_scan_update", Vec::<DeviceInfo>::new())
+            .map_err(|e| BleError::Backend(e.to_string()))?;
+        return Err(e);
     }
-
-    let central = adapters[0].clone();
-    central.start_scan(ScanFilter::default()).await.map_err(|e| e.to_string())?;
-    println!("BLE scan started");
+    println!("BLE scan started ({})", state.backend.name());
 
     let seen = Arc::new(Mutex::new(Vec::<String>::new()));
     let window_arc = Arc::new(window); // 使用Arc共享window
 
     for _ in 0..30 {
-        let peripherals = central.peripherals().await.map_err(|e| e.to_string())?;
+        // 不满足条件的设备不记入 seen，信号变强或广播更新后仍有机会上报
+        let devices = state.discovered_devices(&device_filter).await?;
         let mut seen_guard = seen.lock().await;
 
-        for p in peripherals {
-            let id = p.id().to_string();
-            if !seen_guard.contains(&id) {
-                if let Ok(Some(props)) = p.properties().await {
-                    let name = props.local_name.clone();
-                    let rssi = props.rssi;
-                    let service_count = props.services.len();
-                    let is_connectable = false; // Field not available, set to default
-
-                    let device = DeviceInfo { 
-                        id: id.clone(), 
-                        name,
-                        rssi,
-                        service_count: Some(service_count),
-                        is_connectable,
-                    };
-                    
-                    println!("Discovered device: {:?}", device);
-
-                
-                    // 克隆Arc以便在异步任务中使用
-                    let window_clone = window_arc.clone();
-                    
-                    // 使用spawn确保emit操作不阻塞主循环
-                    tokio::spawn(async move {
-                        if let Err(e) = window_clone.emit("ble_scan_update", vec![device]) {
-                            eprintln!("Failed to emit device: {}", e);
-                        }
-                    });
-                    
-                    seen_guard.push(id);
-                }
+        for device in devices {
+            if !seen_guard.contains(&device.id) {
+                println!("Discovered device: {:?}", device);
+                seen_guard.push(device.id.clone());
+                state.record(scan_entry(&device));
+
+                // 克隆Arc以便在异步任务中使用
+                let window_clone = window_arc.clone();
+
+                // 使用spawn确保emit操作不阻塞主循环
+                tokio::spawn(async move {
+                    if let Err(e) = window_clone.emit("ble_scan_update", vec![device]) {
+                        eprintln!("Failed to emit device: {}", e);
+                    }
+                });
             }
         }
 
@@ -80,45 +897,1103 @@ pub async fn scan_devices(window: tauri::Window) -> Result<(), String> {
     Ok(())
 }
 
+pub(crate) fn scan_entry(device: &DeviceInfo) -> TrafficEntry {
+    let entry = TrafficEntry::new(TrafficKind::ScanResult, Direction::Incoming, &device.id)
+        .rssi(device.rssi);
+    match &device.name {
+        Some(name) => entry.detail(name.clone()),
+        None => entry,
+    }
+}
+
+pub(crate) fn now_rfc3339() -> String {
+    Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+fn emit_connection_state(
+    app: &AppHandle,
+    id: &str,
+    state: ConnectionState,
+    reason: Option<&str>,
+    attempt: Option<u32>,
+) {
+    let event = ConnectionStateEvent {
+        device_id: id.to_string(),
+        state,
+        reason: reason.map(String::from),
+        attempt,
+        timestamp: now_rfc3339(),
+    };
+    if let Err(e) = app.emit("ble-connection-state", event) {
+        eprintln!("Failed to emit connection state: {}", e);
+    }
+}
+
+/// 连接设备并开始跟踪其连接状态
+///
+/// 传入 `reconnect` 策略时，设备意外断开后会按退避策略自动重连并恢复订阅。
 #[tauri::command]
-pub async fn connect_device(id: String, data: Option<Vec<u8>>) -> Result<(), String> {
+pub async fn connect_device(
+    app: AppHandle,
+    state: State<'_, BleState>,
+    id: String,
+    reconnect: Option<ReconnectPolicy>,
+) -> Result<(), BleError> {
     println!("Connecting to device: {}", id);
-    
-    let manager = BtleplugManager::new().await.map_err(|e| e.to_string())?;
-    let adapters = manager.adapters().await.map_err(|e| e.to_string())?;
-    if adapters.is_empty() {
-        return Err("No adapters found".to_string());
-    }
-    
-    let central = adapters[0].clone();
-    let peripherals = central.peripherals().await.map_err(|e| e.to_string())?;
-
-    let Some(peripheral) = peripherals.into_iter().find(|p| p.id().to_string() == id) else {
-        return Err("Device not found".to_string());
-    };
 
-    peripheral.connect().await.map_err(|e| e.to_string())?;
-    println!("Connected to device: {}", id);
-    
-    peripheral.discover_services().await.map_err(|e| e.to_string())?;
-    println!("Services discovered");
-
-    // if let Some(data) = data {
-    //     println!("Sending data: {:?}", data);
-    //     // 实际发送数据需要根据设备特性修改
-    //     for service in peripheral.services() {
-    //         for characteristic in &service.characteristics {
-    //             // 这里只是示例 - 实际应用中需要选择正确的特性
-    //             if characteristic.properties.write {
-    //                 peripheral.write(&characteristic, &data, WriteType::WithResponse)
-    //                     .await
-    //                     .map_err(|e| e.to_string())?;
-    //                 println!("Data sent successfully");
-    //                 break;
-    //             }
-    //         }
-    //     }
-    // }
+    state.ensure_event_watcher(&app).await?;
+    state.connect(&id, reconnect).await?;
+    emit_connection_state(&app, &id, ConnectionState::Connected, None, None);
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tauri::command]
+pub async fn disconnect_device(
+    app: AppHandle,
+    state: State<'_, BleState>,
+    id: String,
+) -> Result<(), BleError> {
+    println!("Disconnecting device: {}", id);
+
+    state.disconnect(&id).await?;
+    emit_connection_state(&app, &id, ConnectionState::Disconnected, Some("requested"), None);
+
+    Ok(())
+}
+
+/// 列出当前已连接（以及正在重连）的设备
+#[tauri::command]
+pub async fn list_connected_devices(
+    state: State<'_, BleState>,
+) -> Result<Vec<ConnectedDevice>, BleError> {
+    let adverts = state.backend.advertisements().await?;
+    let connections = state.connections.lock().await;
+
+    // 已发现的设备加上仍在跟踪（可能正在重连）的设备
+    let mut ids: Vec<String> = adverts.iter().map(|a| a.id.clone()).collect();
+    for id in connections.keys() {
+        if !ids.contains(id) {
+            ids.push(id.clone());
+        }
+    }
+
+    let mut devices = Vec::new();
+    for id in ids {
+        let entry = connections.get(&id);
+        let connected = state.backend.is_connected(&id).await.unwrap_or(false);
+        let reconnecting = entry.is_some_and(|e| e.state == ConnectionState::Reconnecting);
+        if !connected && !reconnecting {
+            continue;
+        }
+
+        let name = entry.and_then(|e| e.name.clone()).or_else(|| {
+            adverts
+                .iter()
+                .find(|a| a.id == id)
+                .and_then(|a| a.name.clone())
+        });
+
+        devices.push(ConnectedDevice {
+            id,
+            name,
+            state: if connected {
+                ConnectionState::Connected
+            } else {
+                ConnectionState::Reconnecting
+            },
+            connected_since: entry.and_then(|e| e.connected_since.clone()),
+            auto_reconnect: entry.is_some_and(|e| e.policy.is_some()),
+        });
+    }
+
+    Ok(devices)
+}
+
+/// 监听后端事件，处理已跟踪设备的意外断线
+async fn watch_backend_events(app: AppHandle, mut events: EventStream) {
+    while let Some(event) = events.next().await {
+        match event {
+            BackendEvent::Disconnected(id) => handle_link_lost(&app, id).await,
+        }
+    }
+}
+
+async fn handle_link_lost(app: &AppHandle, id: String) {
+    let state = app.state::<BleState>();
+
+    let policy = {
+        let mut connections = state.connections.lock().await;
+        let Some(entry) = connections.get_mut(&id) else {
+            // 未跟踪或已主动断开
+            return;
+        };
+        match entry.policy.clone() {
+            Some(policy) => {
+                entry.state = ConnectionState::Reconnecting;
+                entry.connected_since = None;
+                Some(policy)
+            }
+            None => {
+                connections.remove(&id);
+                None
+            }
+        }
+    };
+
+    println!("Device {} disconnected unexpectedly", id);
+    state.record(
+        TrafficEntry::new(TrafficKind::ConnectionState, Direction::Internal, &id).detail("link lost"),
+    );
+    state.stop_forwarding(&id, policy.is_some()).await;
+    emit_connection_state(app, &id, ConnectionState::Disconnected, Some("link lost"), None);
+
+    if let Some(policy) = policy {
+        tokio::spawn(reconnect_with_backoff(app.clone(), id, policy));
+    }
+}
+
+async fn reconnect_with_backoff(app: AppHandle, id: String, policy: ReconnectPolicy) {
+    let state = app.state::<BleState>();
+    let mut delay = policy.initial_delay_ms;
+
+    for attempt in 1..=policy.max_attempts {
+        // 重连期间用户主动断开则放弃
+        if !state.connections.lock().await.contains_key(&id) {
+            return;
+        }
+
+        emit_connection_state(&app, &id, ConnectionState::Reconnecting, None, Some(attempt));
+        tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+
+        let result = async {
+            state.ensure_connected(&id).await?;
+            state.resume_subscriptions(notification_events(&app), &id).await
+        }
+        .await;
+
+        state.record(
+            TrafficEntry::new(TrafficKind::ConnectionState, Direction::Internal, &id)
+                .detail(format!("reconnect attempt {}", attempt))
+                .result(&result),
+        );
+
+        match result {
+            Ok(()) => {
+                if let Some(entry) = state.connections.lock().await.get_mut(&id) {
+                    entry.state = ConnectionState::Connected;
+                    entry.connected_since = Some(now_rfc3339());
+                }
+                println!("Reconnected to {} after {} attempt(s)", id, attempt);
+                emit_connection_state(&app, &id, ConnectionState::Connected, Some("reconnected"), Some(attempt));
+                return;
+            }
+            Err(e) => eprintln!("Reconnect attempt {} to {} failed: {}", attempt, id, e),
+        }
+
+        delay = ((delay as f64 * policy.multiplier) as u64).min(policy.max_delay_ms);
+    }
+
+    state.connections.lock().await.remove(&id);
+    state.stop_forwarding(&id, false).await;
+    state.record(
+        TrafficEntry::new(TrafficKind::ConnectionState, Direction::Internal, &id)
+            .detail("reconnect failed"),
+    );
+    emit_connection_state(
+        &app,
+        &id,
+        ConnectionState::ReconnectFailed,
+        Some("max attempts reached"),
+        Some(policy.max_attempts),
+    );
+}
+
+/// 返回设备完整的GATT数据库：服务 → 特征（含属性标志）→ 描述符
+///
+/// 设备未连接时会先连接；服务发现结果缓存在外设对象上，重复调用不会重新发现。
+#[tauri::command]
+pub async fn get_gatt_services(
+    state: State<'_, BleState>,
+    id: String,
+) -> Result<Vec<GattService>, BleError> {
+    state.gatt_services(&id).await
+}
+
+#[tauri::command]
+pub async fn read_characteristic(
+    state: State<'_, BleState>,
+    id: String,
+    service: String,
+    characteristic: String,
+    encoding: Option<ValueEncoding>,
+) -> Result<CharacteristicValue, BleError> {
+    state
+        .read(&id, &service, &characteristic, encoding.unwrap_or_default())
+        .await
+}
+
+#[tauri::command]
+pub async fn write_characteristic(
+    state: State<'_, BleState>,
+    id: String,
+    service: String,
+    characteristic: String,
+    value: String,
+    encoding: Option<ValueEncoding>,
+    mode: Option<WriteMode>,
+) -> Result<(), BleError> {
+    let data = decode_value(&value, encoding.unwrap_or_default()).map_err(BleError::InvalidInput)?;
+    state
+        .write(&id, &service, &characteristic, &data, mode.unwrap_or_default())
+        .await
+}
+
+/// 持续读取设备的通知流，转发已订阅特征的数据为 `ble-notification` 事件
+async fn forward_notifications(
+    sink: NotificationSink,
+    device_id: String,
+    mut stream: NotificationStream,
+    subscriptions: SubscriptionMap,
+    schemas: SchemaStore,
+    session: SessionStore,
+) {
+    while let Some(notification) = stream.next().await {
+        let now = Utc::now();
+
+        // 通知只带特征UUID，多个服务下的同名特征都被订阅时分别转发
+        let events: Vec<BleNotification> = {
+            let subscriptions = subscriptions.lock().unwrap();
+            subscriptions
+                .values()
+                .filter(|subscription| subscription.characteristic.uuid == notification.uuid)
+                .map(|subscription| {
+                    session.lock().unwrap().record(
+                        TrafficEntry::new(TrafficKind::Notification, Direction::Incoming, &device_id)
+                            .characteristic(&subscription.characteristic)
+                            .data(&notification.value),
+                    );
+
+                    let hex = to_hex(&notification.value);
+                    let event = BleNotification {
+                        device_id: device_id.clone(),
+                        service: subscription.characteristic.service_uuid.to_string(),
+                        characteristic: notification.uuid.to_string(),
+                        value: encode_value(&notification.value, subscription.encoding)
+                            .unwrap_or_else(|_| hex.clone()),
+                        hex,
+                        timestamp: now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                        timestamp_ms: now.timestamp_millis(),
+                        decoded: decode_characteristic(
+                            &schemas,
+                            &subscription.characteristic,
+                            &notification.value,
+                        ),
+                    };
+
+                    if let Some(recorder) = &subscription.recorder {
+                        if let Ok(line) = serde_json::to_string(&event) {
+                            recorder.record(line);
+                        }
+                    }
+                    event
+                })
+                .collect()
+        };
+
+        for event in events {
+            sink(event);
+        }
+    }
+
+    println!("Notification stream for {} ended", device_id);
+}
+
+/// 订阅特征的 notify/indicate，数据通过 `ble-notification` 事件推送
+///
+/// `record_path` 不为空时，每条通知会以JSON行的形式追加写入该文件。
+#[tauri::command]
+pub async fn subscribe_characteristic(
+    app: AppHandle,
+    state: State<'_, BleState>,
+    id: String,
+    service: String,
+    characteristic: String,
+    encoding: Option<ValueEncoding>,
+    record_path: Option<String>,
+) -> Result<(), BleError> {
+    state.ensure_connected(&id).await?;
+
+    let target = state.find_characteristic(&id, &service, &characteristic).await?;
+    if !target
+        .properties
+        .intersects(CharPropFlags::NOTIFY | CharPropFlags::INDICATE)
+    {
+        return Err(BleError::unsupported(target.uuid, "notify"));
+    }
+
+    let recorder = match record_path {
+        Some(path) if !path.is_empty() => Some(Recorder::open(&path)?),
+        _ => None,
+    };
+
+    state
+        .subscribe(
+            notification_events(&app),
+            &id,
+            target,
+            encoding.unwrap_or_default(),
+            recorder,
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn unsubscribe_characteristic(
+    state: State<'_, BleState>,
+    id: String,
+    service: String,
+    characteristic: String,
+) -> Result<(), BleError> {
+    let target = state.find_characteristic(&id, &service, &characteristic).await?;
+    state.unsubscribe(&id, &target).await
+}
+
+/// 当前使用的BLE后端名称（"btleplug" 或 "simulated"）
+#[tauri::command]
+pub fn get_ble_backend(state: State<'_, BleState>) -> String {
+    state.backend.name().to_string()
+}
+
+/// 加载自定义特征解析规则（.json 或 .toml），之后的读取和通知会按规则解码
+#[tauri::command]
+pub fn load_ble_schema(state: State<'_, BleState>, path: String) -> Result<SchemaSummary, BleError> {
+    state.load_schema(&path)
+}
+
+#[tauri::command]
+pub fn clear_ble_schemas(state: State<'_, BleState>) {
+    state.schemas.write().unwrap().clear();
+}
+
+/// 执行BLE测试序列，`path` 指向 JSON/YAML 文件，或直接传入 `sequence`
+///
+/// 每个步骤开始和结束时发送 `ble-sequence-step` 事件，全部完成后发送 `ble-sequence-finished`。
+#[tauri::command]
+pub async fn run_ble_sequence(
+    app: AppHandle,
+    state: State<'_, BleState>,
+    path: Option<String>,
+    sequence: Option<BleSequence>,
+) -> Result<SequenceReport, BleError> {
+    let sequence = match (sequence, path) {
+        (Some(sequence), _) => sequence,
+        (None, Some(path)) => BleSequence::from_file(&path)?,
+        (None, None) => return Err(BleError::InvalidInput("需要提供序列文件路径或序列内容".to_string())),
+    };
+
+    state.ensure_event_watcher(&app).await?;
+    println!("Running BLE sequence ({} steps)", sequence.steps.len());
+
+    let report = run_sequence(&state, &sequence, |step| {
+        if let Err(e) = app.emit("ble-sequence-step", step) {
+            eprintln!("Failed to emit sequence step: {}", e);
+        }
+    })
+    .await;
+
+    println!(
+        "BLE sequence {} {} in {} ms",
+        report.sequence,
+        if report.passed { "passed" } else { "failed" },
+        report.duration_ms
+    );
+    if let Err(e) = app.emit("ble-sequence-finished", &report) {
+        eprintln!("Failed to emit sequence result: {}", e);
+    }
+    Ok(report)
+}
+
+/// 当前会话的操作时间线；`since` 为上次拿到的最大序号，用于增量获取
+#[tauri::command]
+pub fn get_ble_session(state: State<'_, BleState>, since: Option<u64>) -> SessionSnapshot {
+    state.session.lock().unwrap().snapshot(since)
+}
+
+/// 清空时间线并开始新会话
+#[tauri::command]
+pub fn clear_ble_session(state: State<'_, BleState>) {
+    *state.session.lock().unwrap() = SessionLog::default();
+}
+
+/// 导出当前会话为 NDJSON（可回放）或 CSV，返回导出的记录数
+#[tauri::command]
+pub fn export_ble_session(
+    state: State<'_, BleState>,
+    path: String,
+    format: Option<ExportFormat>,
+) -> Result<usize, BleError> {
+    let count = state
+        .session
+        .lock()
+        .unwrap()
+        .export(&path, format.unwrap_or_default())?;
+    println!("Exported {} BLE session entries to {}", count, path);
+    Ok(count)
+}
+
+/// 最慢回放倍速，更小的正数会让单条记录的等待时间失去意义
+const MIN_REPLAY_SPEED: f64 = 0.01;
+/// 回放时两条记录之间最长等待时间，录制中的长时间空闲不会原样等待
+const MAX_REPLAY_GAP: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// 按倍速换算两条记录之间的等待时间，不超过 `MAX_REPLAY_GAP`
+fn replay_gap(gap_ms: i64, speed: f64) -> std::time::Duration {
+    let millis = gap_ms.max(0) as f64 / speed;
+    std::time::Duration::from_millis(millis.min(MAX_REPLAY_GAP.as_millis() as f64) as u64)
+}
+
+/// 回放导出的 NDJSON 会话，按原始时间间隔发送 `ble-session-replay` 事件
+///
+/// `speed` 为回放倍速（默认 1.0，最小 0.01），0 表示不等待、立即发送全部记录；
+/// 两条记录之间最多等待 60 秒。
+/// 回放结束后发送 `ble-session-replay-finished`，负载为记录数。
+#[tauri::command]
+pub async fn replay_ble_session(
+    app: AppHandle,
+    path: String,
+    speed: Option<f64>,
+) -> Result<usize, BleError> {
+    let entries = read_ndjson(&path)?;
+    let speed = speed.unwrap_or(1.0);
+    if !speed.is_finite() || (speed != 0.0 && speed < MIN_REPLAY_SPEED) {
+        return Err(BleError::InvalidInput(format!(
+            "无效的回放速度: {}（应为 0 或不小于 {}）",
+            speed, MIN_REPLAY_SPEED
+        )));
+    }
+    println!("Replaying {} BLE session entries from {}", entries.len(), path);
+
+    let mut previous: Option<i64> = None;
+    for entry in &entries {
+        if let (Some(previous), true) = (previous, speed > 0.0) {
+            let gap = entry.timestamp_ms.saturating_sub(previous);
+            tokio::time::sleep(replay_gap(gap, speed)).await;
+        }
+        previous = Some(entry.timestamp_ms);
+
+        if let Err(e) = app.emit("ble-session-replay", entry) {
+            eprintln!("Failed to emit replay entry: {}", e);
+        }
+    }
+
+    if let Err(e) = app.emit("ble-session-replay-finished", entries.len()) {
+        eprintln!("Failed to emit replay result: {}", e);
+    }
+    Ok(entries.len())
+}
+
+/// 已连接设备的 MTU、PHY 和连接参数（平台不提供的字段为 null）
+#[tauri::command]
+pub async fn get_link_info(state: State<'_, BleState>, id: String) -> Result<LinkInfo, BleError> {
+    link_info(&state, &id).await
+}
+
+/// 吞吐测试：写入或读取指定数据量，返回每秒字节数及单次操作延迟分位数
+#[tauri::command]
+pub async fn run_throughput_test(
+    state: State<'_, BleState>,
+    id: String,
+    request: ThroughputRequest,
+) -> Result<ThroughputReport, BleError> {
+    throughput_test(&state, &id, &request).await
+}
+
+/// 将本地文件分块写入设备（通用协议或 Nordic Secure DFU）
+///
+/// 进度通过 `ble-transfer-progress` 事件推送；传入的 `transfer_id` 可用于 `cancel_ble_transfer`，
+/// 未传入时自动生成并包含在进度事件中。
+#[tauri::command]
+pub async fn start_ble_transfer(
+    app: AppHandle,
+    state: State<'_, BleState>,
+    id: String,
+    path: String,
+    options: TransferOptions,
+    transfer_id: Option<String>,
+) -> Result<TransferReport, BleError> {
+    let transfer_id =
+        transfer_id.unwrap_or_else(|| format!("transfer-{}", Utc::now().timestamp_millis()));
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut transfers = state.transfers.lock().unwrap();
+        if transfers.contains_key(&transfer_id) {
+            return Err(BleError::Busy(format!("传输 {} 正在进行", transfer_id)));
+        }
+        transfers.insert(transfer_id.clone(), cancel.clone());
+    }
+    println!("Starting transfer {} of {} to {}", transfer_id, path, id);
+
+    let result = run_transfer(&state, &id, &path, &options, &transfer_id, &cancel, |progress| {
+        if let Err(e) = app.emit("ble-transfer-progress", progress) {
+            eprintln!("Failed to emit transfer progress: {}", e);
+        }
+    })
+    .await;
+
+    state.transfers.lock().unwrap().remove(&transfer_id);
+    result
+}
+
+/// 取消进行中的传输，当前数据块写完后停止
+#[tauri::command]
+pub fn cancel_ble_transfer(state: State<'_, BleState>, transfer_id: String) -> Result<(), BleError> {
+    match state.transfers.lock().unwrap().get(&transfer_id) {
+        Some(cancel) => {
+            cancel.store(true, Ordering::Relaxed);
+            Ok(())
+        }
+        None => Err(BleError::InvalidInput(format!("没有进行中的传输 {}", transfer_id))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ble_link::ThroughputDirection;
+    use crate::ble_sim::{SimCharacteristic, SimPeripheral, SimResponse, SimService};
+    use std::collections::HashSet;
+
+    const COMMAND_UUID: &str = "6e400002-b5a3-f393-e0a9-e50e24dcca9e";
+
+    fn sim_state() -> (SimulatedBackend, BleState) {
+        let scenario = SimScenario {
+            peripherals: vec![
+                SimPeripheral {
+                    id: "dev-1".to_string(),
+                    name: Some("Thermo-01".to_string()),
+                    rssi: Some(-40),
+                    advertised_services: vec!["181a".to_string()],
+                    manufacturer_data: HashMap::from([(0x0059, "01".to_string())]),
+                    services: vec![SimService {
+                        uuid: "181a".to_string(),
+                        primary: true,
+                        characteristics: vec![
+                            SimCharacteristic {
+                                uuid: "2a6e".to_string(),
+                                properties: vec!["read".to_string(), "notify".to_string()],
+                                value: "e803".to_string(),
+                                ..Default::default()
+                            },
+                            SimCharacteristic {
+                                uuid: "2a1f".to_string(),
+                                properties: vec!["notify".to_string()],
+                                value: "e803".to_string(),
+                                notify_interval_ms: Some(20),
+                                ..Default::default()
+                            },
+                            SimCharacteristic {
+                                uuid: COMMAND_UUID.to_string(),
+                                properties: vec!["write_without_response".to_string()],
+                                on_write: vec![SimResponse {
+                                    when: Some("01".to_string()),
+                                    notify: "2a6e".to_string(),
+                                    value: "0a00".to_string(),
+                                }],
+                                ..Default::default()
+                            },
+                        ],
+                    }],
+                    ..Default::default()
+                },
+                SimPeripheral {
+                    id: "dev-2".to_string(),
+                    name: Some("Other".to_string()),
+                    rssi: Some(-90),
+                    ..Default::default()
+                },
+            ],
+        };
+        let backend = SimulatedBackend::new(&scenario).unwrap();
+        let state = BleState::new(Arc::new(backend.clone()));
+        (backend, state)
+    }
+
+    async fn scan(state: &BleState, spec: ScanFilterSpec) -> Vec<String> {
+        let (scan_filter, device_filter) = spec.compile().unwrap();
+        state.backend.start_scan(scan_filter).await.unwrap();
+        let mut ids: Vec<String> = state
+            .discovered_devices(&device_filter)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|d| d.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn scan_applies_rust_side_filters() {
+        let (_, state) = sim_state();
+
+        assert_eq!(scan(&state, ScanFilterSpec::default()).await, ["dev-1", "dev-2"]);
+        let by_name = ScanFilterSpec {
+            name_pattern: Some("^Thermo".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(scan(&state, by_name).await, ["dev-1"]);
+        let by_rssi = ScanFilterSpec {
+            min_rssi: Some(-60),
+            ..Default::default()
+        };
+        assert_eq!(scan(&state, by_rssi).await, ["dev-1"]);
+        let by_manufacturer = ScanFilterSpec {
+            manufacturer_id: Some(0x0059),
+            ..Default::default()
+        };
+        assert_eq!(scan(&state, by_manufacturer).await, ["dev-1"]);
+    }
+
+    #[tokio::test]
+    async fn scan_passes_service_uuids_to_backend() {
+        let (_, state) = sim_state();
+        let spec = ScanFilterSpec {
+            services: vec!["0x181A".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(scan(&state, spec).await, ["dev-1"]);
+    }
+
+    #[tokio::test]
+    async fn connect_exposes_gatt_tree_with_sig_names() {
+        let (_, state) = sim_state();
+        state.connect("dev-1", None).await.unwrap();
+
+        let services = state.gatt_services("dev-1").await.unwrap();
+        assert_eq!(services.len(), 1);
+        assert_eq!(services[0].name.as_deref(), Some("Environmental Sensing"));
+
+        let temperature = services[0]
+            .characteristics
+            .iter()
+            .find(|c| c.uuid == parse_uuid("2a6e").unwrap().to_string())
+            .unwrap();
+        assert_eq!(temperature.name.as_deref(), Some("Temperature"));
+        assert_eq!(temperature.properties, ["read", "notify"]);
+    }
+
+    #[tokio::test]
+    async fn read_and_write_characteristics() {
+        let (backend, state) = sim_state();
+        state.connect("dev-1", None).await.unwrap();
+
+        let value = state
+            .read("dev-1", "181a", "2a6e", ValueEncoding::I16le)
+            .await
+            .unwrap();
+        assert_eq!(value.value, "1000");
+        assert_eq!(value.hex, "e8 03");
+        let decoded = value.decoded.unwrap();
+        assert_eq!(decoded.name, "Temperature");
+        assert_eq!(decoded.fields, Some(serde_json::json!({ "celsius": 10.0 })));
+
+        state
+            .write("dev-1", "181a", COMMAND_UUID, &[0x02], WriteMode::WithoutResponse)
+            .await
+            .unwrap();
+        assert_eq!(
+            backend.writes("dev-1").unwrap(),
+            vec![(parse_uuid(COMMAND_UUID).unwrap(), vec![0x02])]
+        );
+
+        // 属性不支持的操作在到达后端之前被拒绝
+        assert!(state
+            .write("dev-1", "181a", COMMAND_UUID, &[0x02], WriteMode::WithResponse)
+            .await
+            .is_err());
+        assert!(state
+            .read("dev-1", "181a", COMMAND_UUID, ValueEncoding::Hex)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn loaded_schema_takes_priority_over_sig_decoder() {
+        let (_, state) = sim_state();
+        state.connect("dev-1", None).await.unwrap();
+
+        let path = std::env::temp_dir().join(format!("ble-schema-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+name = "thermo"
+
+[[characteristics]]
+service = "181a"
+characteristic = "2a6e"
+name = "Raw Temperature"
+
+[[characteristics.fields]]
+name = "tenths"
+type = "i16"
+scale = 0.1
+unit = "°C"
+"#,
+        )
+        .unwrap();
+        let summary = state
+            .schemas
+            .write()
+            .unwrap()
+            .load_file(path.to_str().unwrap())
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(summary.characteristics, ["Raw Temperature"]);
+
+        let value = state.read("dev-1", "181a", "2a6e", ValueEncoding::Hex).await.unwrap();
+        let decoded = value.decoded.unwrap();
+        assert_eq!(decoded.source, "thermo");
+        assert_eq!(decoded.fields, Some(serde_json::json!({ "tenths": 100.0 })));
+        assert_eq!(decoded.units.unwrap()["tenths"], "°C");
+
+        state.schemas.write().unwrap().clear();
+        let value = state.read("dev-1", "181a", "2a6e", ValueEncoding::Hex).await.unwrap();
+        assert_eq!(value.decoded.unwrap().source, "sig");
+    }
+
+    #[tokio::test]
+    async fn session_log_records_and_exports_traffic() {
+        let (_, state) = sim_state();
+        state.connect("dev-1", None).await.unwrap();
+        state.read("dev-1", "181a", "2a6e", ValueEncoding::Hex).await.unwrap();
+        state
+            .write("dev-1", "181a", COMMAND_UUID, &[0x01], WriteMode::WithoutResponse)
+            .await
+            .unwrap();
+        assert!(state.read("dev-1", "181a", COMMAND_UUID, ValueEncoding::Hex).await.is_err());
+        state.disconnect("dev-1").await.unwrap();
+
+        let snapshot = state.session.lock().unwrap().snapshot(None);
+        let timeline: Vec<_> = snapshot
+            .entries
+            .iter()
+            .map(|e| (e.kind, e.direction, e.error.is_some()))
+            .collect();
+        assert_eq!(
+            timeline,
+            [
+                (TrafficKind::Connect, Direction::Outgoing, false),
+                (TrafficKind::Read, Direction::Incoming, false),
+                (TrafficKind::Write, Direction::Outgoing, false),
+                (TrafficKind::Read, Direction::Incoming, true),
+                (TrafficKind::Disconnect, Direction::Outgoing, false),
+            ]
+        );
+        assert_eq!(snapshot.entries[1].hex.as_deref(), Some("e8 03"));
+        assert_eq!(state.session.lock().unwrap().snapshot(Some(3)).entries.len(), 2);
+
+        let dir = std::env::temp_dir();
+        let ndjson = dir.join(format!("ble-session-{}.ndjson", std::process::id()));
+        let csv = dir.join(format!("ble-session-{}.csv", std::process::id()));
+        let session = state.session.lock().unwrap();
+        assert_eq!(session.export(ndjson.to_str().unwrap(), ExportFormat::Ndjson).unwrap(), 5);
+        assert_eq!(session.export(csv.to_str().unwrap(), ExportFormat::Csv).unwrap(), 5);
+
+        let replayed = read_ndjson(ndjson.to_str().unwrap()).unwrap();
+        assert_eq!(replayed.len(), 5);
+        assert_eq!(replayed[2].characteristic.as_deref(), Some(COMMAND_UUID));
+        let csv_text = std::fs::read_to_string(&csv).unwrap();
+        assert_eq!(csv_text.lines().count(), 6);
+        assert!(csv_text.lines().nth(3).unwrap().contains(",write,outgoing,dev-1,"));
+
+        std::fs::remove_file(ndjson).unwrap();
+        std::fs::remove_file(csv).unwrap();
+    }
+
+    #[tokio::test]
+    async fn link_info_and_throughput() {
+        let (_, state) = sim_state();
+        state.connect("dev-1", None).await.unwrap();
+
+        // 场景未配置链路参数时如实报告为不可用
+        let info = link_info(&state, "dev-1").await.unwrap();
+        assert_eq!(info.parameters.mtu, None);
+        assert_eq!(info.att_payload, None);
+        assert_eq!(info.unavailable.len(), 5);
+
+        let request: ThroughputRequest = serde_json::from_value(serde_json::json!({
+            "service": "181a",
+            "characteristic": COMMAND_UUID,
+            "total_bytes": 50,
+            "mode": "without_response",
+        }))
+        .unwrap();
+        let report = throughput_test(&state, "dev-1", &request).await.unwrap();
+        assert_eq!(report.chunk_size, Some(20));
+        assert_eq!(report.operations, 3);
+        assert_eq!(report.total_bytes, 50);
+        assert!(report.latency_ms.min <= report.latency_ms.p50);
+        assert!(report.latency_ms.p50 <= report.latency_ms.max);
+
+        let request = ThroughputRequest {
+            characteristic: "2a6e".to_string(),
+            direction: ThroughputDirection::Read,
+            ..request
+        };
+        let report = throughput_test(&state, "dev-1", &request).await.unwrap();
+        assert_eq!(report.operations, 25);
+        assert_eq!(report.chunk_size, None);
+    }
+
+    #[tokio::test]
+    async fn write_triggers_scripted_notification() {
+        let (_, state) = sim_state();
+        state.connect("dev-1", None).await.unwrap();
+
+        let target = state.find_characteristic("dev-1", "181a", "2a6e").await.unwrap();
+        state.backend.subscribe("dev-1", &target).await.unwrap();
+        let mut notifications = state.backend.notifications("dev-1").await.unwrap();
+
+        state
+            .write("dev-1", "181a", COMMAND_UUID, &[0x01], WriteMode::WithoutResponse)
+            .await
+            .unwrap();
+
+        let notification =
+            tokio::time::timeout(std::time::Duration::from_secs(1), notifications.next())
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(notification.uuid, target.uuid);
+        assert_eq!(notification.value, vec![0x0a, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn subscription_receives_periodic_notifications() {
+        let (_, state) = sim_state();
+        state.connect("dev-1", None).await.unwrap();
+        let target = state.find_characteristic("dev-1", "181a", "2a1f").await.unwrap();
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let sink: NotificationSink = Arc::new(move |event| {
+            let _ = tx.send(event);
+        });
+        state
+            .subscribe(sink, "dev-1", target, ValueEncoding::Hex, None)
+            .await
+            .unwrap();
+
+        // 第一次推送在订阅后立即发生，之后按周期推送
+        for _ in 0..2 {
+            let event = tokio::time::timeout(std::time::Duration::from_secs(1), rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(event.hex, "e8 03");
+        }
+
+        // 订阅时还没有接收端，周期推送也不会因此停止
+        state.disconnect("dev-1").await.unwrap();
+        state.connect("dev-1", None).await.unwrap();
+        let target = state.find_characteristic("dev-1", "181a", "2a1f").await.unwrap();
+        state.backend.subscribe("dev-1", &target).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let mut notifications = state.backend.notifications("dev-1").await.unwrap();
+        let late = tokio::time::timeout(std::time::Duration::from_secs(1), notifications.next())
+            .await
+            .unwrap();
+        assert_eq!(late.map(|n| n.uuid), Some(target.uuid));
+    }
+
+    #[tokio::test]
+    async fn failed_reconnect_attempt_does_not_leak_forwarder() {
+        let (backend, state) = sim_state();
+        state.connect("dev-1", None).await.unwrap();
+        let target = state
+            .find_characteristic("dev-1", "181a", "2a6e")
+            .await
+            .unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let sink: NotificationSink = Arc::new(move |event| {
+            let _ = tx.send(event);
+        });
+        state
+            .subscribe(
+                sink.clone(),
+                "dev-1",
+                target.clone(),
+                ValueEncoding::Hex,
+                None,
+            )
+            .await
+            .unwrap();
+
+        // 掉线后第一次重连在订阅时失败，第二次成功
+        backend.drop_connection("dev-1").unwrap();
+        backend.fail_subscribes("dev-1", 1).unwrap();
+        for expect_ok in [false, true] {
+            state.ensure_connected("dev-1").await.unwrap();
+            let result = state.resume_subscriptions(sink.clone(), "dev-1").await;
+            assert_eq!(result.is_ok(), expect_ok);
+        }
+
+        for value in [vec![0x01, 0x00], vec![0x02, 0x00]] {
+            assert!(backend.notify("dev-1", &target, value).unwrap());
+        }
+        let mut received = Vec::new();
+        while let Ok(Some(event)) =
+            tokio::time::timeout(std::time::Duration::from_millis(100), rx.recv()).await
+        {
+            received.push(event.hex);
+        }
+        assert_eq!(received, ["01 00", "02 00"]);
+    }
+
+    /// 两个服务下各有一个UUID相同、周期推送的特征
+    fn two_service_state(interval_ms: u64) -> (SimulatedBackend, BleState) {
+        let service = |uuid: &str| SimService {
+            uuid: uuid.to_string(),
+            primary: true,
+            characteristics: vec![SimCharacteristic {
+                uuid: "2a1f".to_string(),
+                properties: vec!["notify".to_string()],
+                value: "e803".to_string(),
+                notify_interval_ms: Some(interval_ms),
+                ..Default::default()
+            }],
+        };
+        let scenario = SimScenario {
+            peripherals: vec![SimPeripheral {
+                id: "dev-1".to_string(),
+                services: vec![service("181a"), service("1809")],
+                ..Default::default()
+            }],
+        };
+        let backend = SimulatedBackend::new(&scenario).unwrap();
+        let state = BleState::new(Arc::new(backend.clone()));
+        (backend, state)
+    }
+
+    #[tokio::test]
+    async fn repeated_subscribe_keeps_one_periodic_notifier() {
+        let (backend, state) = two_service_state(1000);
+        state.connect("dev-1", None).await.unwrap();
+        let target = state
+            .find_characteristic("dev-1", "181a", "2a1f")
+            .await
+            .unwrap();
+        let mut notifications = backend.notifications("dev-1").await.unwrap();
+        for _ in 0..2 {
+            backend.subscribe("dev-1", &target).await.unwrap();
+        }
+
+        // 周期任务启动时立即推送一次，重复订阅不会再启动一个
+        let mut count = 0;
+        while let Ok(Some(_)) =
+            tokio::time::timeout(std::time::Duration::from_millis(200), notifications.next()).await
+        {
+            count += 1;
+        }
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn subscriptions_are_keyed_by_service() {
+        let (backend, state) = two_service_state(20);
+        state.connect("dev-1", None).await.unwrap();
+        let first = state
+            .find_characteristic("dev-1", "181a", "2a1f")
+            .await
+            .unwrap();
+        let second = state
+            .find_characteristic("dev-1", "1809", "2a1f")
+            .await
+            .unwrap();
+
+        let record_path =
+            std::env::temp_dir().join(format!("ble-record-{}.ndjson", std::process::id()));
+        let _ = std::fs::remove_file(&record_path);
+        let recorder = Recorder::open(record_path.to_str().unwrap()).unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let sink: NotificationSink = Arc::new(move |event| {
+            let _ = tx.send(event);
+        });
+        for (target, recorder) in [(first.clone(), Some(recorder)), (second.clone(), None)] {
+            state
+                .subscribe(sink.clone(), "dev-1", target, ValueEncoding::Hex, recorder)
+                .await
+                .unwrap();
+        }
+        assert!(state.has_subscription("dev-1", &first).await);
+        assert!(state.has_subscription("dev-1", &second).await);
+
+        // 两个服务下的同名特征各自收到通知
+        let mut services = HashSet::new();
+        while services.len() < 2 {
+            let event = tokio::time::timeout(std::time::Duration::from_secs(1), rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            services.insert(event.service);
+        }
+
+        // 取消一个服务下的订阅不影响另一个
+        state.unsubscribe("dev-1", &first).await.unwrap();
+        assert!(!state.has_subscription("dev-1", &first).await);
+        assert!(state.has_subscription("dev-1", &second).await);
+        assert!(!backend.notify("dev-1", &first, vec![0x01]).unwrap());
+        assert!(backend.notify("dev-1", &second, vec![0x01]).unwrap());
+
+        // 录制在后台线程写入，订阅取消后写完剩余内容
+        let mut recorded = String::new();
+        for _ in 0..50 {
+            recorded = std::fs::read_to_string(&record_path).unwrap_or_default();
+            if !recorded.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let line: serde_json::Value =
+            serde_json::from_str(recorded.lines().next().unwrap()).unwrap();
+        assert_eq!(line["service"], first.service_uuid.to_string());
+        std::fs::remove_file(&record_path).unwrap();
+    }
+
+    #[test]
+    fn replay_gap_is_scaled_and_capped() {
+        assert_eq!(replay_gap(1_000, 2.0).as_millis(), 500);
+        assert_eq!(replay_gap(-5, 1.0).as_millis(), 0);
+        assert_eq!(replay_gap(i64::MAX, MIN_REPLAY_SPEED), MAX_REPLAY_GAP);
+        assert_eq!(replay_gap(10 * 60_000, 1.0), MAX_REPLAY_GAP);
+    }
+
+    #[test]
+    fn reconnect_policy_rejects_shrinking_multiplier() {
+        let policy = |value: serde_json::Value| serde_json::from_value::<ReconnectPolicy>(value);
+        assert_eq!(policy(serde_json::json!({})).unwrap().multiplier, 2.0);
+        assert_eq!(policy(serde_json::json!({ "multiplier": 1 })).unwrap().multiplier, 1.0);
+        for multiplier in [0.0, 0.5, -2.0] {
+            assert!(policy(serde_json::json!({ "multiplier": multiplier })).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn link_loss_and_disconnect() {
+        let (backend, state) = sim_state();
+        let mut events = state.backend.events().await.unwrap();
+        state.connect("dev-1", None).await.unwrap();
+
+        backend.drop_connection("dev-1").unwrap();
+        assert_eq!(
+            events.next().await,
+            Some(BackendEvent::Disconnected("dev-1".to_string()))
+        );
+        assert!(!state.backend.is_connected("dev-1").await.unwrap());
+
+        state.connect("dev-1", None).await.unwrap();
+        state.disconnect("dev-1").await.unwrap();
+        assert!(state.connections.lock().await.is_empty());
+        assert!(!state.backend.is_connected("dev-1").await.unwrap());
+    }
+}
@@ -0,0 +1,248 @@
+use async_trait::async_trait;
+use btleplug::api::{
+    Central, CentralEvent, Characteristic, Manager as _, Peripheral as _, ScanFilter, Service,
+    ValueNotification, WriteType,
+};
+use btleplug::platform::{Adapter, Manager as BtleplugManager, Peripheral};
+use futures::stream::{BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::ble_error::BleError;
+
+pub type NotificationStream = BoxStream<'static, ValueNotification>;
+pub type EventStream = BoxStream<'static, BackendEvent>;
+
+/// 一个外设最近一次的广播数据
+#[derive(Clone, Debug, Default)]
+pub struct Advertisement {
+    pub id: String,
+    pub name: Option<String>,
+    pub rssi: Option<i16>,
+    pub tx_power: Option<i16>,
+    pub services: Vec<Uuid>,
+    pub manufacturer_data: HashMap<u16, Vec<u8>>,
+    pub service_data: HashMap<Uuid, Vec<u8>>,
+}
+
+/// 连接的链路参数，平台未提供的字段为 `None`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct LinkParameters {
+    pub mtu: Option<u16>,
+    pub phy: Option<String>, // "1m" / "2m" / "coded"
+    pub connection_interval_ms: Option<f64>,
+    pub peripheral_latency: Option<u16>,
+    pub supervision_timeout_ms: Option<u32>,
+}
+
+/// 后端上报的适配器事件
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BackendEvent {
+    Disconnected(String),
+}
+
+/// BLE 后端抽象，`ble.rs` 中的命令只通过该 trait 访问蓝牙
+///
+/// 外设以字符串ID标识；GATT 数据沿用 btleplug 的 `Service` / `Characteristic` 类型。
+#[async_trait]
+pub trait BleBackend: Send + Sync {
+    /// 后端名称，用于日志和前端展示
+    fn name(&self) -> &'static str;
+
+    async fn start_scan(&self, filter: ScanFilter) -> Result<(), BleError>;
+
+    async fn stop_scan(&self) -> Result<(), BleError>;
+
+    /// 当前已发现外设的广播快照
+    async fn advertisements(&self) -> Result<Vec<Advertisement>, BleError>;
+
+    async fn events(&self) -> Result<EventStream, BleError>;
+
+    async fn connect(&self, id: &str) -> Result<(), BleError>;
+
+    async fn disconnect(&self, id: &str) -> Result<(), BleError>;
+
+    async fn is_connected(&self, id: &str) -> Result<bool, BleError>;
+
+    /// 已连接外设的链路参数
+    async fn link_parameters(&self, id: &str) -> Result<LinkParameters, BleError>;
+
+    /// 返回GATT服务列表，尚未发现时先执行服务发现
+    async fn services(&self, id: &str) -> Result<Vec<Service>, BleError>;
+
+    async fn read(&self, id: &str, characteristic: &Characteristic) -> Result<Vec<u8>, BleError>;
+
+    async fn write(
+        &self,
+        id: &str,
+        characteristic: &Characteristic,
+        data: &[u8],
+        write_type: WriteType,
+    ) -> Result<(), BleError>;
+
+    async fn subscribe(&self, id: &str, characteristic: &Characteristic) -> Result<(), BleError>;
+
+    async fn unsubscribe(&self, id: &str, characteristic: &Characteristic) -> Result<(), BleError>;
+
+    /// 设备所有已订阅特征的通知流
+    async fn notifications(&self, id: &str) -> Result<NotificationStream, BleError>;
+}
+
+/// 基于 btleplug 的真实蓝牙后端，使用第一个适配器
+#[derive(Default)]
+pub struct BtleplugBackend {
+    central: Mutex<Option<Adapter>>,
+}
+
+impl BtleplugBackend {
+    /// 获取第一个蓝牙适配器，首次调用时初始化
+    async fn central(&self) -> Result<Adapter, BleError> {
+        let mut guard = self.central.lock().await;
+        if let Some(central) = guard.as_ref() {
+            return Ok(central.clone());
+        }
+
+        let unavailable = |e: btleplug::Error| BleError::AdapterUnavailable(e.to_string());
+        let manager = BtleplugManager::new().await.map_err(unavailable)?;
+        let adapters = manager.adapters().await.map_err(unavailable)?;
+        let Some(central) = adapters.into_iter().next() else {
+            println!("No BLE adapters found");
+            return Err(BleError::AdapterUnavailable("No adapters found".to_string()));
+        };
+
+        *guard = Some(central.clone());
+        Ok(central)
+    }
+
+    /// 按ID查找已扫描到的外设
+    async fn peripheral(&self, id: &str) -> Result<Peripheral, BleError> {
+        let central = self.central().await?;
+        let peripherals = central.peripherals().await?;
+        peripherals
+            .into_iter()
+            .find(|p| p.id().to_string() == id)
+            .ok_or_else(|| BleError::DeviceNotFound(id.to_string()))
+    }
+}
+
+#[async_trait]
+impl BleBackend for BtleplugBackend {
+    fn name(&self) -> &'static str {
+        "btleplug"
+    }
+
+    async fn start_scan(&self, filter: ScanFilter) -> Result<(), BleError> {
+        let central = self.central().await?;
+        Ok(central.start_scan(filter).await?)
+    }
+
+    async fn stop_scan(&self) -> Result<(), BleError> {
+        let central = self.central().await?;
+        Ok(central.stop_scan().await?)
+    }
+
+    async fn advertisements(&self) -> Result<Vec<Advertisement>, BleError> {
+        let central = self.central().await?;
+        let peripherals = central.peripherals().await?;
+        let mut adverts = Vec::with_capacity(peripherals.len());
+
+        for p in peripherals {
+            if let Ok(Some(props)) = p.properties().await {
+                adverts.push(Advertisement {
+                    id: p.id().to_string(),
+                    name: props.local_name,
+                    rssi: props.rssi,
+                    tx_power: props.tx_power_level,
+                    services: props.services,
+                    manufacturer_data: props.manufacturer_data,
+                    service_data: props.service_data,
+                });
+            }
+        }
+
+        Ok(adverts)
+    }
+
+    async fn events(&self) -> Result<EventStream, BleError> {
+        let central = self.central().await?;
+        let events = central.events().await?;
+        Ok(events
+            .filter_map(|event| async move {
+                match event {
+                    CentralEvent::DeviceDisconnected(id) => {
+                        Some(BackendEvent::Disconnected(id.to_string()))
+                    }
+                    _ => None,
+                }
+            })
+            .boxed())
+    }
+
+    async fn connect(&self, id: &str) -> Result<(), BleError> {
+        let peripheral = self.peripheral(id).await?;
+        peripheral.connect().await.map_err(|e| BleError::from(e).for_device(id))?;
+        println!("Connected to device: {}", id);
+        Ok(())
+    }
+
+    async fn disconnect(&self, id: &str) -> Result<(), BleError> {
+        let peripheral = self.peripheral(id).await?;
+        peripheral.disconnect().await.map_err(|e| BleError::from(e).for_device(id))
+    }
+
+    async fn is_connected(&self, id: &str) -> Result<bool, BleError> {
+        let peripheral = self.peripheral(id).await?;
+        peripheral.is_connected().await.map_err(|e| BleError::from(e).for_device(id))
+    }
+
+    async fn link_parameters(&self, id: &str) -> Result<LinkParameters, BleError> {
+        // btleplug 0.11 没有暴露 MTU、PHY 和连接参数
+        self.peripheral(id).await?;
+        Ok(LinkParameters::default())
+    }
+
+    async fn services(&self, id: &str) -> Result<Vec<Service>, BleError> {
+        let peripheral = self.peripheral(id).await?;
+        if peripheral.services().is_empty() {
+            peripheral.discover_services().await.map_err(|e| BleError::from(e).for_device(id))?;
+            println!("Services discovered");
+        }
+        Ok(peripheral.services().into_iter().collect())
+    }
+
+    async fn read(&self, id: &str, characteristic: &Characteristic) -> Result<Vec<u8>, BleError> {
+        let peripheral = self.peripheral(id).await?;
+        peripheral.read(characteristic).await.map_err(|e| BleError::from(e).for_device(id))
+    }
+
+    async fn write(
+        &self,
+        id: &str,
+        characteristic: &Characteristic,
+        data: &[u8],
+        write_type: WriteType,
+    ) -> Result<(), BleError> {
+        let peripheral = self.peripheral(id).await?;
+        peripheral
+            .write(characteristic, data, write_type)
+            .await
+            .map_err(|e| BleError::from(e).for_device(id))
+    }
+
+    async fn subscribe(&self, id: &str, characteristic: &Characteristic) -> Result<(), BleError> {
+        let peripheral = self.peripheral(id).await?;
+        peripheral.subscribe(characteristic).await.map_err(|e| BleError::from(e).for_device(id))
+    }
+
+    async fn unsubscribe(&self, id: &str, characteristic: &Characteristic) -> Result<(), BleError> {
+        let peripheral = self.peripheral(id).await?;
+        peripheral.unsubscribe(characteristic).await.map_err(|e| BleError::from(e).for_device(id))
+    }
+
+    async fn notifications(&self, id: &str) -> Result<NotificationStream, BleError> {
+        let peripheral = self.peripheral(id).await?;
+        peripheral.notifications().await.map_err(|e| BleError::from(e).for_device(id))
+    }
+}
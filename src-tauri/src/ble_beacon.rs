@@ -0,0 +1,313 @@
+use btleplug::api::bleuuid::uuid_from_u16;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::ble_backend::Advertisement;
+use crate::ble_codec::to_hex;
+
+/// Apple 的厂商ID（iBeacon）
+const APPLE_COMPANY_ID: u16 = 0x004C;
+/// Eddystone 服务UUID（0xFEAA）
+const EDDYSTONE_SERVICE: u16 = 0xFEAA;
+/// Eddystone 的校准功率是 0 米处的值，换算到 1 米约减去 41 dBm
+const EDDYSTONE_0M_TO_1M: i16 = 41;
+/// 路径损耗指数，2.0 对应空旷环境
+const PATH_LOSS_EXPONENT: f64 = 2.0;
+
+/// 从广播中识别出的信标帧
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Beacon {
+    Ibeacon {
+        uuid: String,
+        major: u16,
+        minor: u16,
+        measured_power: i8, // 1 米处的 RSSI
+    },
+    EddystoneUid {
+        namespace: String, // 10 字节十六进制
+        instance: String,  // 6 字节十六进制
+        tx_power: i8,      // 0 米处的 RSSI
+    },
+    EddystoneUrl {
+        url: String,
+        tx_power: i8,
+    },
+    EddystoneTlm {
+        version: u8,
+        encrypted: bool, // 版本 1 为加密TLM，只保留原始数据
+        battery_mv: Option<u16>,
+        temperature_c: Option<f64>,
+        adv_count: Option<u32>,
+        uptime_s: Option<f64>,
+        raw: Option<String>,
+    },
+    EddystoneEid {
+        eid: String, // 8 字节临时ID
+        tx_power: i8,
+    },
+    AltBeacon {
+        manufacturer_id: u16,
+        beacon_id: String, // 20 字节，通常为 UUID + major + minor
+        reference_rssi: i8, // 1 米处的 RSSI
+        reserved: u8,
+    },
+}
+
+impl Beacon {
+    /// 1 米处的参考 RSSI；TLM 帧不携带功率信息
+    fn power_at_1m(&self) -> Option<i16> {
+        match self {
+            Beacon::Ibeacon { measured_power, .. } => Some(*measured_power as i16),
+            Beacon::AltBeacon { reference_rssi, .. } => Some(*reference_rssi as i16),
+            Beacon::EddystoneUid { tx_power, .. }
+            | Beacon::EddystoneUrl { tx_power, .. }
+            | Beacon::EddystoneEid { tx_power, .. } => Some(*tx_power as i16 - EDDYSTONE_0M_TO_1M),
+            Beacon::EddystoneTlm { .. } => None,
+        }
+    }
+}
+
+/// 识别广播中的 iBeacon / AltBeacon / Eddystone 帧，数据不完整时返回 `None`
+pub fn parse_beacon(advert: &Advertisement) -> Option<Beacon> {
+    let mut companies: Vec<_> = advert.manufacturer_data.iter().collect();
+    companies.sort_by_key(|(id, _)| **id);
+    for (company, data) in companies {
+        if *company == APPLE_COMPANY_ID {
+            if let Some(beacon) = parse_ibeacon(data) {
+                return Some(beacon);
+            }
+        }
+        if let Some(beacon) = parse_altbeacon(*company, data) {
+            return Some(beacon);
+        }
+    }
+
+    let eddystone: Uuid = uuid_from_u16(EDDYSTONE_SERVICE);
+    advert
+        .service_data
+        .get(&eddystone)
+        .and_then(|data| parse_eddystone(data))
+}
+
+/// 由 RSSI 和信标（或广播 TX Power）估算距离，单位米
+///
+/// 使用对数距离路径损耗模型，仅供参考，实际误差受环境影响很大。
+pub fn estimate_distance(advert: &Advertisement, beacon: Option<&Beacon>) -> Option<f64> {
+    let rssi = advert.rssi?;
+    let power_at_1m = beacon
+        .and_then(Beacon::power_at_1m)
+        .or_else(|| advert.tx_power.map(|tx| tx - EDDYSTONE_0M_TO_1M))?;
+
+    let distance = 10f64.powf((power_at_1m - rssi) as f64 / (10.0 * PATH_LOSS_EXPONENT));
+    Some((distance * 100.0).round() / 100.0)
+}
+
+/// iBeacon：02 15 | UUID(16) | major(2, BE) | minor(2, BE) | measured power(1)
+fn parse_ibeacon(data: &[u8]) -> Option<Beacon> {
+    if data.len() != 23 || data[0] != 0x02 || data[1] != 0x15 {
+        return None;
+    }
+    Some(Beacon::Ibeacon {
+        uuid: Uuid::from_slice(&data[2..18]).ok()?.to_string(),
+        major: u16::from_be_bytes([data[18], data[19]]),
+        minor: u16::from_be_bytes([data[20], data[21]]),
+        measured_power: data[22] as i8,
+    })
+}
+
+/// AltBeacon：BE AC | beacon ID(20) | reference RSSI(1) | reserved(1)
+fn parse_altbeacon(company: u16, data: &[u8]) -> Option<Beacon> {
+    if data.len() != 24 || data[0] != 0xBE || data[1] != 0xAC {
+        return None;
+    }
+    Some(Beacon::AltBeacon {
+        manufacturer_id: company,
+        beacon_id: to_hex(&data[2..22]),
+        reference_rssi: data[22] as i8,
+        reserved: data[23],
+    })
+}
+
+fn parse_eddystone(data: &[u8]) -> Option<Beacon> {
+    match *data.first()? {
+        0x00 if data.len() >= 18 => Some(Beacon::EddystoneUid {
+            tx_power: data[1] as i8,
+            namespace: to_hex(&data[2..12]),
+            instance: to_hex(&data[12..18]),
+        }),
+        0x10 if data.len() >= 3 => Some(Beacon::EddystoneUrl {
+            tx_power: data[1] as i8,
+            url: decode_eddystone_url(data[2], &data[3..])?,
+        }),
+        0x20 => parse_eddystone_tlm(data),
+        0x30 if data.len() >= 10 => Some(Beacon::EddystoneEid {
+            tx_power: data[1] as i8,
+            eid: to_hex(&data[2..10]),
+        }),
+        _ => None,
+    }
+}
+
+/// TLM：20 | 版本 | 电压(mV, BE) | 温度(8.8 定点, BE) | 广播计数(BE) | 上电时间(0.1s, BE)
+fn parse_eddystone_tlm(data: &[u8]) -> Option<Beacon> {
+    let version = *data.get(1)?;
+    if version == 0x01 {
+        return Some(Beacon::EddystoneTlm {
+            version,
+            encrypted: true,
+            battery_mv: None,
+            temperature_c: None,
+            adv_count: None,
+            uptime_s: None,
+            raw: Some(to_hex(&data[2..])),
+        });
+    }
+    if version != 0x00 || data.len() < 14 {
+        return None;
+    }
+
+    let battery = u16::from_be_bytes([data[2], data[3]]);
+    let temperature = i16::from_be_bytes([data[4], data[5]]);
+    let adv_count = u32::from_be_bytes([data[6], data[7], data[8], data[9]]);
+    let uptime = u32::from_be_bytes([data[10], data[11], data[12], data[13]]);
+    Some(Beacon::EddystoneTlm {
+        version,
+        encrypted: false,
+        // 0 和 0x8000 表示不支持
+        battery_mv: (battery != 0).then_some(battery),
+        temperature_c: (temperature != i16::MIN).then_some(temperature as f64 / 256.0),
+        adv_count: Some(adv_count),
+        uptime_s: Some(uptime as f64 / 10.0),
+        raw: None,
+    })
+}
+
+/// 展开 Eddystone-URL 的前缀和缩写编码
+fn decode_eddystone_url(scheme: u8, encoded: &[u8]) -> Option<String> {
+    const SCHEMES: [&str; 4] = ["http://www.", "https://www.", "http://", "https://"];
+    const EXPANSIONS: [&str; 14] = [
+        ".com/", ".org/", ".edu/", ".net/", ".info/", ".biz/", ".gov/", ".com", ".org", ".edu",
+        ".net", ".info", ".biz", ".gov",
+    ];
+
+    let mut url = SCHEMES.get(scheme as usize)?.to_string();
+    for &b in encoded {
+        match b {
+            0x00..=0x0D => url.push_str(EXPANSIONS[b as usize]),
+            0x21..=0x7E => url.push(b as char),
+            _ => return None,
+        }
+    }
+    Some(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ble_codec::from_hex;
+    use std::collections::HashMap;
+
+    fn advert(rssi: i16) -> Advertisement {
+        Advertisement {
+            id: "beacon".to_string(),
+            rssi: Some(rssi),
+            ..Default::default()
+        }
+    }
+
+    fn eddystone(frame: &str) -> Advertisement {
+        Advertisement {
+            service_data: HashMap::from([(uuid_from_u16(EDDYSTONE_SERVICE), from_hex(frame).unwrap())]),
+            ..advert(-70)
+        }
+    }
+
+    #[test]
+    fn ibeacon_and_distance() {
+        let advert = Advertisement {
+            manufacturer_data: HashMap::from([(
+                APPLE_COMPANY_ID,
+                from_hex("0215f7826da64fa24e988024bc5b71e0893e00010002c5").unwrap(),
+            )]),
+            ..advert(-79)
+        };
+
+        let beacon = parse_beacon(&advert).unwrap();
+        assert_eq!(
+            beacon,
+            Beacon::Ibeacon {
+                uuid: "f7826da6-4fa2-4e98-8024-bc5b71e0893e".to_string(),
+                major: 1,
+                minor: 2,
+                measured_power: -59,
+            }
+        );
+        // 比 1 米处弱 20 dB，n = 2 时约 10 米
+        assert_eq!(estimate_distance(&advert, Some(&beacon)), Some(10.0));
+    }
+
+    #[test]
+    fn altbeacon() {
+        let advert = Advertisement {
+            manufacturer_data: HashMap::from([(
+                0x0118,
+                from_hex("beac 0102030405060708090a0b0c0d0e0f10 0001 0002 c5 00").unwrap(),
+            )]),
+            ..advert(-59)
+        };
+
+        let beacon = parse_beacon(&advert).unwrap();
+        assert!(matches!(
+            beacon,
+            Beacon::AltBeacon { manufacturer_id: 0x0118, reference_rssi: -59, .. }
+        ));
+        assert_eq!(estimate_distance(&advert, Some(&beacon)), Some(1.0));
+    }
+
+    #[test]
+    fn eddystone_frames() {
+        assert_eq!(
+            parse_beacon(&eddystone("00 ee 00010203040506070809 0a0b0c0d0e0f 0000")),
+            Some(Beacon::EddystoneUid {
+                namespace: "00 01 02 03 04 05 06 07 08 09".to_string(),
+                instance: "0a 0b 0c 0d 0e 0f".to_string(),
+                tx_power: -18,
+            })
+        );
+        assert_eq!(
+            parse_beacon(&eddystone("10 ee 03 676f6f676c65 07")),
+            Some(Beacon::EddystoneUrl {
+                url: "https://google.com".to_string(),
+                tx_power: -18,
+            })
+        );
+        assert_eq!(
+            parse_beacon(&eddystone("20 00 0bb8 1780 00000064 00000e10")),
+            Some(Beacon::EddystoneTlm {
+                version: 0,
+                encrypted: false,
+                battery_mv: Some(3000),
+                temperature_c: Some(23.5),
+                adv_count: Some(100),
+                uptime_s: Some(360.0),
+                raw: None,
+            })
+        );
+        assert!(matches!(
+            parse_beacon(&eddystone("30 ee 0102030405060708")),
+            Some(Beacon::EddystoneEid { tx_power: -18, .. })
+        ));
+        assert_eq!(parse_beacon(&eddystone("10 ee 09 6162")), None);
+    }
+
+    #[test]
+    fn distance_falls_back_to_tx_power_level() {
+        let advert = Advertisement {
+            tx_power: Some(0),
+            ..advert(-47)
+        };
+        assert_eq!(estimate_distance(&advert, None), Some(2.0));
+        assert_eq!(estimate_distance(&Advertisement::default(), None), None);
+    }
+}
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+
+use crate::base64::{decode_bytes, encode_bytes};
+
+/// 特征值在前端与字节之间的转换方式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueEncoding {
+    #[default]
+    Hex,
+    Utf8,
+    Base64,
+    U8,
+    U16le,
+    U16be,
+    U32le,
+    U32be,
+    U64le,
+    U64be,
+    I8,
+    I16le,
+    I16be,
+    I32le,
+    I32be,
+    I64le,
+    I64be,
+}
+
+impl ValueEncoding {
+    /// 整数编码的布局：(字节宽度, 是否有符号, 是否小端)
+    fn int_layout(self) -> Option<(usize, bool, bool)> {
+        use ValueEncoding::*;
+        match self {
+            U8 => Some((1, false, true)),
+            U16le => Some((2, false, true)),
+            U16be => Some((2, false, false)),
+            U32le => Some((4, false, true)),
+            U32be => Some((4, false, false)),
+            U64le => Some((8, false, true)),
+            U64be => Some((8, false, false)),
+            I8 => Some((1, true, true)),
+            I16le => Some((2, true, true)),
+            I16be => Some((2, true, false)),
+            I32le => Some((4, true, true)),
+            I32be => Some((4, true, false)),
+            I64le => Some((8, true, true)),
+            I64be => Some((8, true, false)),
+            Hex | Utf8 | Base64 => None,
+        }
+    }
+}
+
+/// 字节格式化为十六进制，如 "0a ff 10"
+pub fn to_hex(data: &[u8]) -> String {
+    data.iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 解析十六进制字符串，允许空格、冒号、连字符分隔及 "0x" 前缀
+pub fn from_hex(text: &str) -> Result<Vec<u8>, String> {
+    let digits: String = text
+        .trim()
+        .trim_start_matches("0x")
+        .trim_start_matches("0X")
+        .chars()
+        .filter(|c| !matches!(c, ' ' | ':' | '-' | '\n' | '\r' | '\t'))
+        .collect();
+
+    // 先逐字符检查，之后才能按字节切分（非 ASCII 字符会落在字符边界之外）
+    if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(format!("无效的十六进制字符: {}", c));
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err("十六进制长度必须为偶数".to_string());
+    }
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| format!("无效的十六进制: {}", &digits[i..i + 2]))
+        })
+        .collect()
+}
+
+/// 字节 → 指定编码的文本
+pub fn encode_value(data: &[u8], encoding: ValueEncoding) -> Result<String, String> {
+    match encoding {
+        ValueEncoding::Hex => Ok(to_hex(data)),
+        ValueEncoding::Utf8 => String::from_utf8(data.to_vec())
+            .map_err(|_| "特征值不是有效的UTF-8序列".to_string()),
+        ValueEncoding::Base64 => Ok(encode_bytes(data)),
+        _ => {
+            let (width, signed, little) = encoding.int_layout().unwrap_or_default();
+            if data.len() != width {
+                return Err(format!(
+                    "特征值长度为 {} 字节，与 {:?} 需要的 {} 字节不符",
+                    data.len(),
+                    encoding,
+                    width
+                ));
+            }
+
+            let mut buf = [0u8; 8];
+            if little {
+                buf[..width].copy_from_slice(data);
+            } else {
+                for (i, b) in data.iter().rev().enumerate() {
+                    buf[i] = *b;
+                }
+            }
+            let raw = u64::from_le_bytes(buf);
+
+            if signed {
+                // 符号扩展到64位
+                let shift = 64 - width * 8;
+                Ok((((raw << shift) as i64) >> shift).to_string())
+            } else {
+                Ok(raw.to_string())
+            }
+        }
+    }
+}
+
+/// 指定编码的文本 → 字节
+pub fn decode_value(text: &str, encoding: ValueEncoding) -> Result<Vec<u8>, String> {
+    match encoding {
+        ValueEncoding::Hex => from_hex(text),
+        ValueEncoding::Utf8 => Ok(text.as_bytes().to_vec()),
+        ValueEncoding::Base64 => decode_bytes(text),
+        _ => {
+            let (width, signed, little) = encoding.int_layout().unwrap_or_default();
+            let bits = width as u32 * 8;
+            let text = text.trim();
+
+            let raw = if signed {
+                let value: i64 = text
+                    .parse()
+                    .map_err(|_| format!("无效的整数: {}", text))?;
+                let min = i64::MIN >> (64 - bits);
+                let max = i64::MAX >> (64 - bits);
+                if value < min || value > max {
+                    return Err(format!("{} 超出 {:?} 的取值范围", value, encoding));
+                }
+                value as u64
+            } else {
+                let value: u64 = text
+                    .parse()
+                    .map_err(|_| format!("无效的无符号整数: {}", text))?;
+                if bits < 64 && value >> bits != 0 {
+                    return Err(format!("{} 超出 {:?} 的取值范围", value, encoding));
+                }
+                value
+            };
+
+            let bytes = raw.to_le_bytes()[..width].to_vec();
+            Ok(if little {
+                bytes
+            } else {
+                bytes.into_iter().rev().collect()
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_with_separators() {
+        assert_eq!(from_hex("0x0A ff:10-7f").unwrap(), vec![0x0a, 0xff, 0x10, 0x7f]);
+        assert_eq!(from_hex("").unwrap(), Vec::<u8>::new());
+        assert_eq!(to_hex(&from_hex("e803").unwrap()), "e8 03");
+    }
+
+    #[test]
+    fn rejects_invalid_hex() {
+        for text in ["aé", "é", "0g", "+f", "abc"] {
+            assert!(from_hex(text).is_err(), "{}", text);
+        }
+        assert_eq!(from_hex("aé").unwrap_err(), "无效的十六进制字符: é");
+    }
+}
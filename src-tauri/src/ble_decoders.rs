@@ -0,0 +1,418 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::ble_uuid::{short_uuid, sig_name};
+
+/// 特征值的结构化解码结果
+#[derive(Serialize, Clone, Debug)]
+pub struct DecodedValue {
+    pub source: String, // "sig" 或自定义schema来源
+    pub name: String,
+    pub fields: Option<Value>,
+    pub error: Option<String>, // 解码器匹配但数据不合法时的原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub units: Option<BTreeMap<String, String>>, // 字段名 → 单位（自定义schema）
+}
+
+type DecodeFn = fn(&[u8]) -> Result<Value, String>;
+
+/// 已注册的SIG标准特征解码器
+const DECODERS: &[(u16, DecodeFn)] = &[
+    (0x2A19, decode_battery_level),
+    (0x2A37, decode_heart_rate_measurement),
+    (0x2A38, decode_body_sensor_location),
+    (0x2A1C, decode_temperature_measurement),
+    (0x2A1E, decode_temperature_measurement),
+    (0x2A2B, decode_current_time),
+    (0x2A5B, decode_csc_measurement),
+    (0x2A6E, decode_temperature),
+    (0x2A6F, decode_humidity),
+    (0x2A23, decode_system_id),
+    (0x2A50, decode_pnp_id),
+    (0x2A24, decode_string),
+    (0x2A25, decode_string),
+    (0x2A26, decode_string),
+    (0x2A27, decode_string),
+    (0x2A28, decode_string),
+    (0x2A29, decode_string),
+    (0x2A00, decode_string),
+];
+
+/// 按特征UUID查找解码器并解码，没有对应解码器时返回 `None`
+pub fn decode(uuid: &Uuid, data: &[u8]) -> Option<DecodedValue> {
+    let short = short_uuid(uuid)?;
+    let (_, decoder) = DECODERS.iter().find(|(value, _)| *value == short)?;
+    let name = sig_name(uuid).unwrap_or("Unknown").to_string();
+
+    Some(match decoder(data) {
+        Ok(fields) => DecodedValue {
+            source: "sig".to_string(),
+            name,
+            fields: Some(fields),
+            error: None,
+            units: None,
+        },
+        Err(e) => DecodedValue {
+            source: "sig".to_string(),
+            name,
+            fields: None,
+            error: Some(e),
+            units: None,
+        },
+    })
+}
+
+/// 小端顺序读取字段，越界时返回错误
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos + len;
+        if end > self.data.len() {
+            return Err(format!(
+                "数据长度不足：需要至少 {} 字节，实际 {} 字节",
+                end,
+                self.data.len()
+            ));
+        }
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+}
+
+/// IEEE 11073-20601 32位 FLOAT：高8位有符号指数，低24位有符号尾数
+fn ieee11073_float(raw: u32) -> Option<f64> {
+    let exponent = (raw >> 24) as i8;
+    match raw & 0x00FF_FFFF {
+        0x007F_FFFF | 0x0080_0000 | 0x007F_FFFE | 0x0080_0002 => None, // NaN、NRes、±INFINITY
+        _ => {
+            let mantissa = ((raw << 8) as i32) >> 8;
+            Some(mantissa as f64 * 10f64.powi(exponent as i32))
+        }
+    }
+}
+
+/// Date Time（0x2A08）格式：年(u16) 月 日 时 分 秒
+fn date_time(reader: &mut Reader) -> Result<String, String> {
+    let year = reader.u16()?;
+    let month = reader.u8()?;
+    let day = reader.u8()?;
+    let hours = reader.u8()?;
+    let minutes = reader.u8()?;
+    let seconds = reader.u8()?;
+    Ok(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year, month, day, hours, minutes, seconds
+    ))
+}
+
+fn decode_battery_level(data: &[u8]) -> Result<Value, String> {
+    let level = Reader::new(data).u8()?;
+    if level > 100 {
+        return Err(format!("电量 {} 超出 0-100 范围", level));
+    }
+    Ok(json!({ "level_percent": level }))
+}
+
+fn decode_heart_rate_measurement(data: &[u8]) -> Result<Value, String> {
+    let mut reader = Reader::new(data);
+    let flags = reader.u8()?;
+
+    let heart_rate = if flags & 0x01 != 0 {
+        reader.u16()?
+    } else {
+        reader.u8()? as u16
+    };
+    let sensor_contact = match (flags >> 1) & 0x03 {
+        0b10 => "not_detected",
+        0b11 => "detected",
+        _ => "not_supported",
+    };
+    let energy_expended = if flags & 0x08 != 0 {
+        Some(reader.u16()?)
+    } else {
+        None
+    };
+
+    let mut rr_intervals = Vec::new();
+    if flags & 0x10 != 0 {
+        while reader.remaining() >= 2 {
+            // 单位 1/1024 秒
+            let rr = reader.u16()? as f64 * 1000.0 / 1024.0;
+            rr_intervals.push((rr * 10.0).round() / 10.0);
+        }
+    }
+
+    Ok(json!({
+        "heart_rate_bpm": heart_rate,
+        "sensor_contact": sensor_contact,
+        "energy_expended_kj": energy_expended,
+        "rr_intervals_ms": rr_intervals,
+    }))
+}
+
+fn decode_body_sensor_location(data: &[u8]) -> Result<Value, String> {
+    let location = match Reader::new(data).u8()? {
+        0 => "other",
+        1 => "chest",
+        2 => "wrist",
+        3 => "finger",
+        4 => "hand",
+        5 => "ear_lobe",
+        6 => "foot",
+        _ => "reserved",
+    };
+    Ok(json!({ "location": location }))
+}
+
+fn decode_temperature_measurement(data: &[u8]) -> Result<Value, String> {
+    let mut reader = Reader::new(data);
+    let flags = reader.u8()?;
+    let value = ieee11073_float(reader.u32()?);
+    let unit = if flags & 0x01 != 0 { "fahrenheit" } else { "celsius" };
+    let timestamp = if flags & 0x02 != 0 {
+        Some(date_time(&mut reader)?)
+    } else {
+        None
+    };
+    let temperature_type = if flags & 0x04 != 0 {
+        Some(match reader.u8()? {
+            1 => "armpit",
+            2 => "body",
+            3 => "ear",
+            4 => "finger",
+            5 => "gastro_intestinal_tract",
+            6 => "mouth",
+            7 => "rectum",
+            8 => "toe",
+            9 => "tympanum",
+            _ => "reserved",
+        })
+    } else {
+        None
+    };
+
+    Ok(json!({
+        "temperature": value,
+        "unit": unit,
+        "timestamp": timestamp,
+        "temperature_type": temperature_type,
+    }))
+}
+
+fn decode_current_time(data: &[u8]) -> Result<Value, String> {
+    const DAYS: [&str; 8] = [
+        "unknown", "monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday",
+    ];
+    const ADJUST_REASONS: [&str; 4] = [
+        "manual_time_update",
+        "external_reference_time_update",
+        "change_of_time_zone",
+        "change_of_dst",
+    ];
+
+    let mut reader = Reader::new(data);
+    let datetime = date_time(&mut reader)?;
+    let day_of_week = DAYS.get(reader.u8()? as usize).copied().unwrap_or("reserved");
+    let fractions256 = reader.u8()?;
+    let adjust = reader.u8()?;
+    let adjust_reason: Vec<&str> = ADJUST_REASONS
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| adjust & (1 << bit) != 0)
+        .map(|(_, name)| *name)
+        .collect();
+
+    Ok(json!({
+        "datetime": datetime,
+        "day_of_week": day_of_week,
+        "fractions256": fractions256,
+        "milliseconds": (fractions256 as u32 * 1000) / 256,
+        "adjust_reason": adjust_reason,
+    }))
+}
+
+fn decode_csc_measurement(data: &[u8]) -> Result<Value, String> {
+    let mut reader = Reader::new(data);
+    let flags = reader.u8()?;
+    let mut fields = serde_json::Map::new();
+
+    if flags & 0x01 != 0 {
+        fields.insert("cumulative_wheel_revolutions".into(), json!(reader.u32()?));
+        // 单位 1/1024 秒
+        fields.insert("last_wheel_event_time".into(), json!(reader.u16()? as f64 / 1024.0));
+    }
+    if flags & 0x02 != 0 {
+        fields.insert("cumulative_crank_revolutions".into(), json!(reader.u16()?));
+        fields.insert("last_crank_event_time".into(), json!(reader.u16()? as f64 / 1024.0));
+    }
+
+    Ok(Value::Object(fields))
+}
+
+fn decode_temperature(data: &[u8]) -> Result<Value, String> {
+    // 单位 0.01 °C
+    let raw = Reader::new(data).i16()?;
+    if raw == i16::MIN {
+        return Ok(json!({ "celsius": null }));
+    }
+    Ok(json!({ "celsius": raw as f64 / 100.0 }))
+}
+
+fn decode_humidity(data: &[u8]) -> Result<Value, String> {
+    // 单位 0.01 %
+    let raw = Reader::new(data).u16()?;
+    if raw == 0xFFFF {
+        return Ok(json!({ "percent": null }));
+    }
+    Ok(json!({ "percent": raw as f64 / 100.0 }))
+}
+
+fn decode_system_id(data: &[u8]) -> Result<Value, String> {
+    let bytes = Reader::new(data).take(8)?;
+    let manufacturer = bytes[..5]
+        .iter()
+        .rev()
+        .fold(0u64, |acc, b| (acc << 8) | *b as u64);
+    let oui = bytes[5..]
+        .iter()
+        .rev()
+        .fold(0u32, |acc, b| (acc << 8) | *b as u32);
+    Ok(json!({
+        "manufacturer_identifier": manufacturer,
+        "organizationally_unique_identifier": format!("{:06X}", oui),
+    }))
+}
+
+fn decode_pnp_id(data: &[u8]) -> Result<Value, String> {
+    let mut reader = Reader::new(data);
+    let source = match reader.u8()? {
+        1 => "bluetooth_sig",
+        2 => "usb_implementers_forum",
+        _ => "reserved",
+    };
+    let vendor_id = reader.u16()?;
+    let product_id = reader.u16()?;
+    let version = reader.u16()?;
+    Ok(json!({
+        "vendor_id_source": source,
+        "vendor_id": vendor_id,
+        "product_id": product_id,
+        "product_version": format!("{}.{}.{}", version >> 8, (version >> 4) & 0x0F, version & 0x0F),
+    }))
+}
+
+fn decode_string(data: &[u8]) -> Result<Value, String> {
+    let text = String::from_utf8_lossy(data);
+    Ok(json!({ "text": text.trim_end_matches('\0') }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use btleplug::api::bleuuid::uuid_from_u16;
+
+    fn fields(short: u16, data: &[u8]) -> Value {
+        let decoded = decode(&uuid_from_u16(short), data).unwrap();
+        assert_eq!(decoded.error, None, "{:04x} {:02x?}", short, data);
+        decoded.fields.unwrap()
+    }
+
+    #[test]
+    fn decodes_spec_vectors() {
+        let cases: &[(u16, &[u8], Value)] = &[
+            // Heart Rate Measurement：标志位决定心率宽度、接触状态、能量和 RR 间期
+            (0x2A37, &[0x00, 0x48], json!({ "heart_rate_bpm": 72, "sensor_contact": "not_supported", "energy_expended_kj": null, "rr_intervals_ms": [] })),
+            (0x2A37, &[0x04, 0x48], json!({ "heart_rate_bpm": 72, "sensor_contact": "not_detected", "energy_expended_kj": null, "rr_intervals_ms": [] })),
+            (0x2A37, &[0x06, 0x48], json!({ "heart_rate_bpm": 72, "sensor_contact": "detected", "energy_expended_kj": null, "rr_intervals_ms": [] })),
+            (0x2A37, &[0x01, 0x2c, 0x01], json!({ "heart_rate_bpm": 300, "sensor_contact": "not_supported", "energy_expended_kj": null, "rr_intervals_ms": [] })),
+            (0x2A37, &[0x18, 0x48, 0x10, 0x00, 0x00, 0x04, 0x00, 0x02], json!({ "heart_rate_bpm": 72, "sensor_contact": "not_supported", "energy_expended_kj": 16, "rr_intervals_ms": [1000.0, 500.0] })),
+            // CSC Measurement：车轮和曲柄数据分别由 bit0、bit1 标志
+            (0x2A5B, &[0x00], json!({})),
+            (0x2A5B, &[0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x04], json!({ "cumulative_wheel_revolutions": 1, "last_wheel_event_time": 1.0 })),
+            (0x2A5B, &[0x02, 0x02, 0x00, 0x00, 0x08], json!({ "cumulative_crank_revolutions": 2, "last_crank_event_time": 2.0 })),
+            (0x2A5B, &[0x03, 0x01, 0x00, 0x00, 0x00, 0x00, 0x04, 0x02, 0x00, 0x00, 0x08], json!({ "cumulative_wheel_revolutions": 1, "last_wheel_event_time": 1.0, "cumulative_crank_revolutions": 2, "last_crank_event_time": 2.0 })),
+            // Temperature Measurement：IEEE-11073 FLOAT，0xFF 指数即 ×10⁻¹
+            (0x2A1C, &[0x00, 0x72, 0x01, 0x00, 0xff], json!({ "temperature": 37.0, "unit": "celsius", "timestamp": null, "temperature_type": null })),
+            (0x2A1C, &[0x01, 0xff, 0xff, 0xff, 0x00], json!({ "temperature": -1.0, "unit": "fahrenheit", "timestamp": null, "temperature_type": null })),
+            (0x2A1C, &[0x00, 0xff, 0xff, 0x7f, 0x00], json!({ "temperature": null, "unit": "celsius", "timestamp": null, "temperature_type": null })),
+            (0x2A1C, &[0x06, 0x72, 0x01, 0x00, 0xff, 0xe4, 0x07, 0x01, 0x02, 0x03, 0x04, 0x05, 0x02], json!({ "temperature": 37.0, "unit": "celsius", "timestamp": "2020-01-02T03:04:05", "temperature_type": "body" })),
+            // Current Time：Date Time + 星期 + 1/256 秒 + 调整原因位
+            (0x2A2B, &[0xe8, 0x07, 0x0a, 0x13, 0x0c, 0x1e, 0x00, 0x01, 0x80, 0x05], json!({ "datetime": "2024-10-19T12:30:00", "day_of_week": "monday", "fractions256": 128, "milliseconds": 500, "adjust_reason": ["manual_time_update", "change_of_time_zone"] })),
+            (0x2A2B, &[0xe8, 0x07, 0x0a, 0x13, 0x0c, 0x1e, 0x00, 0x09, 0x00, 0x00], json!({ "datetime": "2024-10-19T12:30:00", "day_of_week": "reserved", "fractions256": 0, "milliseconds": 0, "adjust_reason": [] })),
+            // PnP ID：来源、厂商、产品和 BCD 版本号 JJ.M.N
+            (0x2A50, &[0x01, 0x59, 0x00, 0x01, 0x00, 0x12, 0x01], json!({ "vendor_id_source": "bluetooth_sig", "vendor_id": 89, "product_id": 1, "product_version": "1.1.2" })),
+            (0x2A50, &[0x02, 0x6b, 0x1d, 0x46, 0x02, 0x00, 0x02], json!({ "vendor_id_source": "usb_implementers_forum", "vendor_id": 0x1d6b, "product_id": 0x0246, "product_version": "2.0.0" })),
+            // System ID：40 位厂商标识 + 24 位 OUI，均为小端
+            (0x2A23, &[0x01, 0x02, 0x03, 0x04, 0x05, 0xaa, 0xbb, 0xcc], json!({ "manufacturer_identifier": 0x05_0403_0201u64, "organizationally_unique_identifier": "CCBBAA" })),
+        ];
+        for (short, data, expected) in cases {
+            assert_eq!(&fields(*short, data), expected, "{:04x} {:02x?}", short, data);
+        }
+    }
+
+    #[test]
+    fn truncated_input_reports_error() {
+        let cases: &[(u16, &[u8])] = &[
+            (0x2A37, &[]),
+            (0x2A37, &[0x01, 0x2c]),
+            (0x2A37, &[0x08, 0x48, 0x10]),
+            (0x2A5B, &[0x03, 0x01, 0x00, 0x00, 0x00, 0x00, 0x04, 0x02, 0x00]),
+            (0x2A1C, &[0x00, 0x72, 0x01, 0x00]),
+            (0x2A1C, &[0x02, 0x72, 0x01, 0x00, 0xff, 0xe4, 0x07]),
+            (0x2A1C, &[0x04, 0x72, 0x01, 0x00, 0xff]),
+            (0x2A2B, &[0xe8, 0x07, 0x0a, 0x13, 0x0c, 0x1e, 0x00, 0x01, 0x80]),
+            (0x2A50, &[0x01, 0x59, 0x00, 0x01, 0x00, 0x12]),
+            (0x2A23, &[0x01, 0x02, 0x03, 0x04, 0x05, 0xaa, 0xbb]),
+        ];
+        for (short, data) in cases {
+            let decoded = decode(&uuid_from_u16(*short), data).unwrap();
+            assert!(decoded.fields.is_none(), "{:04x} {:02x?}", short, data);
+            assert!(
+                decoded.error.is_some_and(|e| e.starts_with("数据长度不足")),
+                "{:04x} {:02x?}",
+                short,
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_characteristic_has_no_decoder() {
+        assert!(decode(&uuid_from_u16(0x2A99), &[0x01]).is_none());
+        let battery = decode(&uuid_from_u16(0x2A19), &[0x65]).unwrap();
+        assert!(battery.error.is_some_and(|e| e.contains("超出")));
+    }
+}
@@ -0,0 +1,896 @@
+use btleplug::api::{CharPropFlags, Characteristic, WriteType};
+use futures::stream::StreamExt;
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::ble::{BleState, WriteMode};
+use crate::ble_backend::NotificationStream;
+use crate::ble_codec::{from_hex, to_hex};
+use crate::ble_error::BleError;
+use crate::ble_link::att_payload;
+
+/// Nordic Secure DFU 服务及特征
+const NORDIC_DFU_SERVICE: &str = "fe59";
+const NORDIC_CONTROL_POINT: &str = "8ec90001-f315-4f60-9fb8-838830daea50";
+const NORDIC_PACKET: &str = "8ec90002-f315-4f60-9fb8-838830daea50";
+
+/// 传输方式，`profile` 字段区分
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "profile", rename_all = "snake_case")]
+pub enum TransferOptions {
+    Generic(ChunkedProtocol),
+    NordicDfu(NordicDfuOptions),
+}
+
+fn default_ack_every() -> usize {
+    1
+}
+
+fn default_ack_timeout() -> u64 {
+    2_000
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+/// 通用分块传输：数据写入 `characteristic`，可选地在 `ack_characteristic` 上等待确认通知
+#[derive(Deserialize, Clone, Debug)]
+pub struct ChunkedProtocol {
+    pub service: String,
+    pub characteristic: String,
+    #[serde(default)]
+    pub mode: WriteMode,
+    pub chunk_size: Option<usize>,   // 缺省为 MTU - 3，MTU 未知时为 20
+    pub ack_service: Option<String>, // 缺省与 service 相同
+    pub ack_characteristic: Option<String>, // 为空时不等待确认
+    #[serde(default = "default_ack_every")]
+    pub ack_every: usize, // 每发送多少块等待一次确认
+    pub ack_value: Option<String>,   // 确认通知需以该十六进制值开头，否则视为 NACK
+    #[serde(default = "default_ack_timeout")]
+    pub ack_timeout_ms: u64,
+    #[serde(default = "default_retries")]
+    pub retries: u32, // 超时或 NACK 后重发当前窗口的次数
+}
+
+fn default_prn() -> u16 {
+    12
+}
+
+fn default_nordic_timeout() -> u64 {
+    5_000
+}
+
+/// Nordic Secure DFU（设备需已处于 bootloader 模式）
+///
+/// 传入解压后的 DFU 包：`init_packet` 为 .dat 文件，传输文件为 .bin 固件。
+#[derive(Deserialize, Clone, Debug)]
+pub struct NordicDfuOptions {
+    pub init_packet: String,
+    #[serde(default = "default_prn")]
+    pub prn: u16, // 每发送多少个包要求设备回报一次 CRC，0 表示关闭
+    pub chunk_size: Option<usize>,
+    #[serde(default = "default_nordic_timeout")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_retries")]
+    pub retries: u32, // 单个对象 CRC 校验失败后的重发次数
+}
+
+/// `ble-transfer-progress` 事件的负载
+#[derive(Serialize, Clone, Debug)]
+pub struct TransferProgress {
+    pub transfer_id: String,
+    pub device_id: String,
+    pub phase: String, // "data"，Nordic DFU 为 "init_packet" / "firmware"
+    pub sent_bytes: usize,
+    pub total_bytes: usize,
+    pub percent: f64,
+    pub retries: u32,
+    pub bytes_per_sec: f64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TransferReport {
+    pub transfer_id: String,
+    pub device_id: String,
+    pub total_bytes: usize,
+    pub elapsed_ms: f64,
+    pub bytes_per_sec: f64,
+    pub retries: u32,
+    pub crc32: String, // 传输文件的 CRC32，便于与设备端核对
+}
+
+/// 执行一次传输，`cancel` 置位后在下一个数据块之前中止
+pub async fn run_transfer<F>(
+    state: &BleState,
+    id: &str,
+    path: &str,
+    options: &TransferOptions,
+    transfer_id: &str,
+    cancel: &AtomicBool,
+    on_progress: F,
+) -> Result<TransferReport, BleError>
+where
+    F: FnMut(&TransferProgress) + Send,
+{
+    let data =
+        std::fs::read(path).map_err(|e| BleError::io(path, format!("无法读取文件: {}", e)))?;
+    if data.is_empty() {
+        return Err(BleError::InvalidInput(format!("文件 {} 为空", path)));
+    }
+    state.ensure_connected(id).await?;
+
+    let mut transfer = Transfer {
+        state,
+        id,
+        transfer_id,
+        cancel,
+        on_progress,
+        started: Instant::now(),
+        retries: 0,
+    };
+    match options {
+        TransferOptions::Generic(protocol) => transfer.generic(&data, protocol).await?,
+        TransferOptions::NordicDfu(options) => {
+            let init = std::fs::read(&options.init_packet)
+                .map_err(|e| BleError::io(&options.init_packet, format!("无法读取 init packet: {}", e)))?;
+            transfer.nordic_dfu(&init, &data, options).await?
+        }
+    }
+
+    let elapsed = transfer.started.elapsed();
+    println!(
+        "Transfer {} to {} finished: {} bytes in {:?}",
+        transfer_id,
+        id,
+        data.len(),
+        elapsed
+    );
+    Ok(TransferReport {
+        transfer_id: transfer_id.to_string(),
+        device_id: id.to_string(),
+        total_bytes: data.len(),
+        elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+        bytes_per_sec: data.len() as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        retries: transfer.retries,
+        crc32: format!("{:08x}", crc32(&data)),
+    })
+}
+
+struct Transfer<'a, F> {
+    state: &'a BleState,
+    id: &'a str,
+    transfer_id: &'a str,
+    cancel: &'a AtomicBool,
+    on_progress: F,
+    started: Instant,
+    retries: u32,
+}
+
+impl<F> Transfer<'_, F>
+where
+    F: FnMut(&TransferProgress) + Send,
+{
+    fn check_cancelled(&self) -> Result<(), BleError> {
+        if self.cancel.load(Ordering::Relaxed) {
+            println!("Transfer {} cancelled", self.transfer_id);
+            return Err(BleError::Cancelled);
+        }
+        Ok(())
+    }
+
+    fn progress(&mut self, phase: &str, sent: usize, total: usize) {
+        let elapsed = self.started.elapsed().as_secs_f64().max(f64::EPSILON);
+        (self.on_progress)(&TransferProgress {
+            transfer_id: self.transfer_id.to_string(),
+            device_id: self.id.to_string(),
+            phase: phase.to_string(),
+            sent_bytes: sent,
+            total_bytes: total,
+            percent: (sent as f64 * 1000.0 / total as f64).round() / 10.0,
+            retries: self.retries,
+            bytes_per_sec: sent as f64 / elapsed,
+        });
+    }
+
+    /// 未指定时按 MTU 计算每包字节数
+    async fn chunk_size(&self, requested: Option<usize>) -> Result<usize, BleError> {
+        match requested {
+            Some(0) => Err(BleError::InvalidInput("chunk_size 不能为 0".to_string())),
+            Some(size) => Ok(size),
+            None => Ok(att_payload(
+                self.state.backend().link_parameters(self.id).await?.mtu,
+            )),
+        }
+    }
+
+    async fn generic(&mut self, data: &[u8], protocol: &ChunkedProtocol) -> Result<(), BleError> {
+        let backend = self.state.backend();
+        let target = self
+            .state
+            .find_characteristic(self.id, &protocol.service, &protocol.characteristic)
+            .await?;
+        let write_type = writable(&target, protocol.mode)?;
+        let chunk_size = self.chunk_size(protocol.chunk_size).await?;
+        let expected_ack = protocol
+            .ack_value
+            .as_deref()
+            .map(from_hex)
+            .transpose()
+            .map_err(BleError::InvalidInput)?;
+
+        let mut ack = match &protocol.ack_characteristic {
+            Some(characteristic) => {
+                let service = protocol.ack_service.as_deref().unwrap_or(&protocol.service);
+                Some(AckChannel::open(self.state, self.id, service, characteristic).await?)
+            }
+            None => None,
+        };
+
+        let chunks: Vec<&[u8]> = data.chunks(chunk_size).collect();
+        let window = protocol.ack_every.max(1);
+        let timeout = Duration::from_millis(protocol.ack_timeout_ms);
+        let mut index = 0;
+        let mut sent = 0;
+        let mut last_percent = None;
+
+        let result = async {
+            while index < chunks.len() {
+                self.check_cancelled()?;
+                let end = (index + window).min(chunks.len());
+                let mut attempt = 0;
+
+                loop {
+                    let result = async {
+                        for chunk in &chunks[index..end] {
+                            backend.write(self.id, &target, chunk, write_type).await?;
+                        }
+                        if let Some(ack) = ack.as_mut() {
+                            let value = ack.next(timeout).await?;
+                            if let Some(expected) = &expected_ack {
+                                if !value.starts_with(expected) {
+                                    return Err(BleError::Protocol(format!(
+                                        "收到 NACK: {}",
+                                        to_hex(&value)
+                                    )));
+                                }
+                            }
+                        }
+                        Ok(())
+                    }
+                    .await;
+
+                    match result {
+                        Ok(()) => break,
+                        Err(e) if attempt < protocol.retries => {
+                            attempt += 1;
+                            self.retries += 1;
+                            eprintln!(
+                                "Chunk {} failed ({}), retry {}/{}",
+                                index, e, attempt, protocol.retries
+                            );
+                            if let Some(ack) = ack.as_mut() {
+                                ack.drain();
+                            }
+                            self.check_cancelled()?;
+                        }
+                        Err(e) => {
+                            eprintln!("Chunk {} failed after {} retries: {}", index, attempt, e);
+                            return Err(e);
+                        }
+                    }
+                }
+
+                sent += chunks[index..end].iter().map(|c| c.len()).sum::<usize>();
+                index = end;
+                // 无确认时每块都会走到这里，按百分比节流
+                let percent = sent * 100 / data.len();
+                if last_percent != Some(percent) || index == chunks.len() {
+                    last_percent = Some(percent);
+                    self.progress("data", sent, data.len());
+                }
+            }
+            Ok(())
+        }
+        .await;
+
+        if let Some(ack) = ack {
+            ack.close(self.state).await;
+        }
+        result
+    }
+
+    async fn nordic_dfu(
+        &mut self,
+        init: &[u8],
+        firmware: &[u8],
+        options: &NordicDfuOptions,
+    ) -> Result<(), BleError> {
+        let packet = self
+            .state
+            .find_characteristic(self.id, NORDIC_DFU_SERVICE, NORDIC_PACKET)
+            .await?;
+        let mut control = AckChannel::open(
+            self.state,
+            self.id,
+            NORDIC_DFU_SERVICE,
+            NORDIC_CONTROL_POINT,
+        )
+        .await?;
+        let chunk_size = self.chunk_size(options.chunk_size).await?;
+
+        let result = async {
+            let prn = options.prn.to_le_bytes();
+            nordic_request(
+                self.state,
+                self.id,
+                &mut control,
+                &[0x02, prn[0], prn[1]],
+                options.timeout_ms,
+            )
+            .await?;
+            self.nordic_object(
+                &mut control,
+                &packet,
+                0x01,
+                "init_packet",
+                init,
+                chunk_size,
+                options,
+            )
+            .await?;
+            self.nordic_object(
+                &mut control,
+                &packet,
+                0x02,
+                "firmware",
+                firmware,
+                chunk_size,
+                options,
+            )
+            .await
+        }
+        .await;
+
+        control.close(self.state).await;
+        result
+    }
+
+    /// 发送一类 DFU 对象（0x01 init packet，0x02 固件），按设备给出的最大对象大小拆分
+    #[allow(clippy::too_many_arguments)]
+    async fn nordic_object(
+        &mut self,
+        control: &mut AckChannel,
+        packet: &Characteristic,
+        kind: u8,
+        phase: &str,
+        data: &[u8],
+        chunk_size: usize,
+        options: &NordicDfuOptions,
+    ) -> Result<(), BleError> {
+        let backend = self.state.backend();
+        let select = nordic_request(
+            self.state,
+            self.id,
+            control,
+            &[0x06, kind],
+            options.timeout_ms,
+        )
+        .await?;
+        let max_size = read_u32(&select, 0)? as usize;
+        if max_size == 0 {
+            return Err(BleError::Protocol("设备返回的最大对象大小为 0".to_string()));
+        }
+
+        let mut crc = Crc32::default();
+        let mut offset = 0;
+        for object in data.chunks(max_size) {
+            let mut attempt = 0;
+            loop {
+                self.check_cancelled()?;
+                let (crc_before, offset_before) = (crc, offset);
+
+                let result = async {
+                    let size = (object.len() as u32).to_le_bytes();
+                    nordic_request(
+                        self.state,
+                        self.id,
+                        control,
+                        &[0x01, kind, size[0], size[1], size[2], size[3]],
+                        options.timeout_ms,
+                    )
+                    .await?;
+
+                    for (i, chunk) in object.chunks(chunk_size).enumerate() {
+                        backend
+                            .write(self.id, packet, chunk, WriteType::WithoutResponse)
+                            .await?;
+                        crc.update(chunk);
+                        offset += chunk.len();
+                        if options.prn > 0 && (i + 1) % options.prn as usize == 0 {
+                            // PRN 回报与 0x03 请求的响应格式相同
+                            let response =
+                                nordic_response(control, 0x03, options.timeout_ms).await?;
+                            verify_checksum(&response, offset, crc.value())?;
+                        }
+                    }
+
+                    let checksum =
+                        nordic_request(self.state, self.id, control, &[0x03], options.timeout_ms)
+                            .await?;
+                    verify_checksum(&checksum, offset, crc.value())?;
+                    nordic_request(self.state, self.id, control, &[0x04], options.timeout_ms)
+                        .await?;
+                    Ok::<_, BleError>(())
+                }
+                .await;
+
+                match result {
+                    Ok(()) => break,
+                    Err(e @ BleError::ChecksumMismatch(_)) if attempt < options.retries => {
+                        attempt += 1;
+                        self.retries += 1;
+                        eprintln!(
+                            "DFU object at {} failed ({}), retry {}/{}",
+                            offset_before, e, attempt, options.retries
+                        );
+                        crc = crc_before;
+                        offset = offset_before;
+                        control.drain();
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            self.progress(phase, offset, data.len());
+        }
+        Ok(())
+    }
+}
+
+/// 写入方式对应的 `WriteType`，特征不支持时返回错误
+fn writable(target: &Characteristic, mode: WriteMode) -> Result<WriteType, BleError> {
+    let (write_type, required, operation) = mode.requirements();
+    if !target.properties.contains(required) {
+        return Err(BleError::unsupported(target.uuid, operation));
+    }
+    Ok(write_type)
+}
+
+/// 写入 Control Point 并等待对应的响应（60 <op> <result> ...），返回结果码之后的数据
+async fn nordic_request(
+    state: &BleState,
+    id: &str,
+    control: &mut AckChannel,
+    request: &[u8],
+    timeout_ms: u64,
+) -> Result<Vec<u8>, BleError> {
+    state
+        .backend()
+        .write(
+            id,
+            &control.characteristic,
+            request,
+            WriteType::WithResponse,
+        )
+        .await?;
+    nordic_response(control, request[0], timeout_ms).await
+}
+
+/// 等待操作码 `opcode` 的响应并检查结果码，返回结果码之后的数据
+async fn nordic_response(
+    control: &mut AckChannel,
+    opcode: u8,
+    timeout_ms: u64,
+) -> Result<Vec<u8>, BleError> {
+    let response = control
+        .next_matching(&[0x60, opcode], Duration::from_millis(timeout_ms))
+        .await?;
+
+    match response.get(2) {
+        Some(0x01) => Ok(response.get(3..).unwrap_or_default().to_vec()),
+        Some(code) => Err(BleError::Protocol(format!(
+            "DFU 操作 0x{:02x} 失败: {}",
+            opcode,
+            nordic_error(*code, response.get(3).copied())
+        ))),
+        None => Err(BleError::Protocol(format!(
+            "DFU 响应格式错误: {}",
+            to_hex(&response)
+        ))),
+    }
+}
+
+fn nordic_error(code: u8, extended: Option<u8>) -> String {
+    match code {
+        0x00 => "invalid opcode".to_string(),
+        0x02 => "opcode not supported".to_string(),
+        0x03 => "invalid parameter".to_string(),
+        0x04 => "insufficient resources".to_string(),
+        0x05 => "invalid object".to_string(),
+        0x07 => "unsupported type".to_string(),
+        0x08 => "operation not permitted".to_string(),
+        0x0A => "operation failed".to_string(),
+        0x0B => format!("extended error 0x{:02x}", extended.unwrap_or(0)),
+        _ => format!("unknown result 0x{:02x}", code),
+    }
+}
+
+/// 校验设备回报的 offset(u32 LE) + CRC32(u32 LE)
+fn verify_checksum(response: &[u8], offset: usize, crc: u32) -> Result<(), BleError> {
+    let remote_offset = read_u32(response, 0)? as usize;
+    let remote_crc = read_u32(response, 4)?;
+    if remote_offset != offset || remote_crc != crc {
+        return Err(BleError::ChecksumMismatch(format!(
+            "CRC 校验失败：本地 offset {} crc {:08x}，设备 offset {} crc {:08x}",
+            offset, crc, remote_offset, remote_crc
+        )));
+    }
+    Ok(())
+}
+
+fn read_u32(data: &[u8], at: usize) -> Result<u32, BleError> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| BleError::Protocol(format!("DFU 响应长度不足: {}", to_hex(data))))
+}
+
+/// 确认/响应通道：订阅特征并持有通知流
+struct AckChannel {
+    device_id: String,
+    characteristic: Characteristic,
+    stream: NotificationStream,
+}
+
+impl AckChannel {
+    async fn open(
+        state: &BleState,
+        id: &str,
+        service: &str,
+        characteristic: &str,
+    ) -> Result<Self, BleError> {
+        let target = state
+            .find_characteristic(id, service, characteristic)
+            .await?;
+        if !target
+            .properties
+            .intersects(CharPropFlags::NOTIFY | CharPropFlags::INDICATE)
+        {
+            return Err(BleError::unsupported(target.uuid, "notify"));
+        }
+
+        // 先打开通知流再订阅，避免错过第一条确认
+        let stream = state.backend().notifications(id).await?;
+        state.backend().subscribe(id, &target).await?;
+        Ok(AckChannel {
+            device_id: id.to_string(),
+            characteristic: target,
+            stream,
+        })
+    }
+
+    async fn next(&mut self, timeout: Duration) -> Result<Vec<u8>, BleError> {
+        self.next_matching(&[], timeout).await
+    }
+
+    /// 等待本特征上以 `prefix` 开头的下一条通知，其他通知丢弃
+    async fn next_matching(&mut self, prefix: &[u8], timeout: Duration) -> Result<Vec<u8>, BleError> {
+        let uuid = self.characteristic.uuid;
+        let wait = async {
+            while let Some(notification) = self.stream.next().await {
+                if notification.uuid == uuid && notification.value.starts_with(prefix) {
+                    return Ok(notification.value);
+                }
+            }
+            Err(BleError::NotConnected(self.device_id.clone()))
+        };
+        tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| {
+                BleError::Timeout(format!("{} ms 内未收到 {} 的确认", timeout.as_millis(), uuid))
+            })?
+    }
+
+    /// 丢弃已到达但尚未处理的通知（重试前清掉迟到的确认）
+    fn drain(&mut self) {
+        while let Some(Some(_)) = self.stream.next().now_or_never() {}
+    }
+
+    /// 取消订阅；前端也订阅了该特征时保留
+    async fn close(self, state: &BleState) {
+        if state
+            .has_subscription(&self.device_id, &self.characteristic)
+            .await
+        {
+            return;
+        }
+        if let Err(e) = state
+            .backend()
+            .unsubscribe(&self.device_id, &self.characteristic)
+            .await
+        {
+            eprintln!("Failed to unsubscribe {}: {}", self.characteristic.uuid, e);
+        }
+    }
+}
+
+/// CRC-32（IEEE 802.3，与 Nordic DFU 和 zlib 一致）
+#[derive(Clone, Copy)]
+struct Crc32(u32);
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Crc32(0xFFFF_FFFF)
+    }
+}
+
+impl Crc32 {
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.0 ^= byte as u32;
+            for _ in 0..8 {
+                let mask = (self.0 & 1).wrapping_neg();
+                self.0 = (self.0 >> 1) ^ (0xEDB8_8320 & mask);
+            }
+        }
+    }
+
+    fn value(&self) -> u32 {
+        !self.0
+    }
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc32::default();
+    crc.update(data);
+    crc.value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ble_sim::{
+        SimCharacteristic, SimPeripheral, SimResponse, SimScenario, SimService, SimulatedBackend,
+    };
+    use std::sync::Arc;
+
+    fn dfu_state() -> (SimulatedBackend, BleState) {
+        let scenario = SimScenario {
+            peripherals: vec![SimPeripheral {
+                id: "dfu-1".to_string(),
+                services: vec![SimService {
+                    uuid: "fff0".to_string(),
+                    primary: true,
+                    characteristics: vec![
+                        SimCharacteristic {
+                            uuid: "fff1".to_string(),
+                            properties: vec!["write_without_response".to_string()],
+                            on_write: vec![SimResponse {
+                                when: None,
+                                notify: "fff2".to_string(),
+                                value: "01".to_string(),
+                            }],
+                            ..Default::default()
+                        },
+                        SimCharacteristic {
+                            uuid: "fff2".to_string(),
+                            properties: vec!["notify".to_string()],
+                            ..Default::default()
+                        },
+                    ],
+                }],
+                ..Default::default()
+            }],
+        };
+        let backend = SimulatedBackend::new(&scenario).unwrap();
+        (backend.clone(), BleState::new(Arc::new(backend)))
+    }
+
+    fn protocol(ack_value: &str) -> TransferOptions {
+        serde_json::from_value(serde_json::json!({
+            "profile": "generic",
+            "service": "fff0",
+            "characteristic": "fff1",
+            "mode": "without_response",
+            "chunk_size": 16,
+            "ack_characteristic": "fff2",
+            "ack_value": ack_value,
+            "ack_timeout_ms": 100,
+            "retries": 1,
+        }))
+        .unwrap()
+    }
+
+    fn firmware_file(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}.bin", name, std::process::id()));
+        std::fs::write(&path, (0..100u8).collect::<Vec<_>>()).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    /// Nordic DFU 模拟设备：init packet 和固件使用相同的数据，每写入两包回报一次 CRC
+    ///
+    /// `prn_report` 为第二包写入后设备回报的完整通知（十六进制）。
+    fn nordic_state(data: &[u8], prn_report: String) -> (SimulatedBackend, BleState) {
+        let checksum = |len: usize| {
+            let mut report = (len as u32).to_le_bytes().to_vec();
+            report.extend(crc32(&data[..len]).to_le_bytes());
+            to_hex(&report)
+        };
+        let control = |when: &str, value: String| SimResponse {
+            when: Some(when.to_string()),
+            notify: NORDIC_CONTROL_POINT.to_string(),
+            value,
+        };
+        let scenario = SimScenario {
+            peripherals: vec![SimPeripheral {
+                id: "dfu-1".to_string(),
+                services: vec![SimService {
+                    uuid: NORDIC_DFU_SERVICE.to_string(),
+                    primary: true,
+                    characteristics: vec![
+                        SimCharacteristic {
+                            uuid: NORDIC_CONTROL_POINT.to_string(),
+                            properties: vec!["write".to_string(), "notify".to_string()],
+                            on_write: vec![
+                                control("02 02 00", "60 02 01".to_string()),
+                                control("06 01", format!("60 06 01 00 01 00 00 {}", checksum(0))),
+                                control("06 02", format!("60 06 01 00 01 00 00 {}", checksum(0))),
+                                control("01 01 0c 00 00 00", "60 01 01".to_string()),
+                                control("01 02 0c 00 00 00", "60 01 01".to_string()),
+                                control("03", format!("60 03 01 {}", checksum(data.len()))),
+                                control("04", "60 04 01".to_string()),
+                            ],
+                            ..Default::default()
+                        },
+                        SimCharacteristic {
+                            uuid: NORDIC_PACKET.to_string(),
+                            properties: vec!["write_without_response".to_string()],
+                            on_write: vec![SimResponse {
+                                when: Some(to_hex(&data[4..8])),
+                                notify: NORDIC_CONTROL_POINT.to_string(),
+                                value: prn_report,
+                            }],
+                            ..Default::default()
+                        },
+                    ],
+                }],
+                ..Default::default()
+            }],
+        };
+        let backend = SimulatedBackend::new(&scenario).unwrap();
+        (backend.clone(), BleState::new(Arc::new(backend)))
+    }
+
+    async fn nordic_transfer(state: &BleState, data: &[u8]) -> Result<TransferReport, BleError> {
+        let path = std::env::temp_dir().join(format!("dfu-nordic-{}.bin", std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        let path = path.to_string_lossy().to_string();
+        let options: TransferOptions = serde_json::from_value(serde_json::json!({
+            "profile": "nordic_dfu",
+            "init_packet": path,
+            "prn": 2,
+            "chunk_size": 4,
+            "timeout_ms": 200,
+            "retries": 0,
+        }))
+        .unwrap();
+        let cancel = AtomicBool::new(false);
+        let result = run_transfer(state, "dfu-1", &path, &options, "t4", &cancel, |_| {}).await;
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[tokio::test]
+    async fn chunked_transfer_waits_for_acks() {
+        let (backend, state) = dfu_state();
+        let path = firmware_file("dfu-ok");
+        let cancel = AtomicBool::new(false);
+        let mut progress = Vec::new();
+
+        let report = run_transfer(
+            &state,
+            "dfu-1",
+            &path,
+            &protocol("01"),
+            "t1",
+            &cancel,
+            |p| progress.push(p.percent),
+        )
+        .await
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(report.total_bytes, 100);
+        assert_eq!(report.retries, 0);
+        assert_eq!(
+            report.crc32,
+            format!("{:08x}", crc32(&(0..100u8).collect::<Vec<_>>()))
+        );
+        let writes = backend.writes("dfu-1").unwrap();
+        assert_eq!(writes.len(), 7);
+        assert_eq!(writes[6].1, (96..100u8).collect::<Vec<_>>());
+        assert_eq!(progress.last(), Some(&100.0));
+    }
+
+    #[tokio::test]
+    async fn nack_is_retried_then_fails() {
+        let (backend, state) = dfu_state();
+        let path = firmware_file("dfu-nack");
+        let cancel = AtomicBool::new(false);
+
+        let error = run_transfer(
+            &state,
+            "dfu-1",
+            &path,
+            &protocol("02"),
+            "t2",
+            &cancel,
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(
+            matches!(&error, BleError::Protocol(message) if message.contains("NACK")),
+            "{}",
+            error
+        );
+        // 第一块发送一次，重试一次
+        assert_eq!(backend.writes("dfu-1").unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn cancelled_transfer_stops_before_next_chunk() {
+        let (backend, state) = dfu_state();
+        let path = firmware_file("dfu-cancel");
+        let cancel = AtomicBool::new(false);
+
+        let error = run_transfer(
+            &state,
+            "dfu-1",
+            &path,
+            &protocol("01"),
+            "t3",
+            &cancel,
+            |p| {
+                if p.sent_bytes >= 32 {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            },
+        )
+        .await
+        .unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(error, BleError::Cancelled);
+        assert_eq!(backend.writes("dfu-1").unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn nordic_transfer_checks_prn_reports() {
+        let data: Vec<u8> = (1..=12).collect();
+        let mut report = 8u32.to_le_bytes().to_vec();
+        report.extend(crc32(&data[..8]).to_le_bytes());
+        let prn_report = format!("60 03 01 {}", to_hex(&report));
+
+        let (backend, state) = nordic_state(&data, prn_report);
+        let report = nordic_transfer(&state, &data).await.unwrap();
+        assert_eq!(report.total_bytes, 12);
+        // 每个对象：创建、3 个数据包、计算校验、执行；另有 PRN 设置和两次选择
+        assert_eq!(backend.writes("dfu-1").unwrap().len(), 1 + 2 * 7);
+
+        // 过短的 PRN 回报和失败的结果码都返回错误，而不是越界
+        for prn_report in ["60 03", "60 03 01 08", "60 03 0a"] {
+            let (_, state) = nordic_state(&data, prn_report.to_string());
+            let error = nordic_transfer(&state, &data).await.unwrap_err();
+            assert!(matches!(error, BleError::Protocol(_)), "{}: {}", prn_report, error);
+        }
+    }
+}
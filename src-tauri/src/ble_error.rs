@@ -0,0 +1,162 @@
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::error::{serialize_error, ErrorCode};
+
+/// BLE 命令的错误类型，序列化为 `{ code, message, details }`
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum BleError {
+    /// 没有可用的蓝牙适配器（蓝牙关闭、无硬件或系统服务不可用）
+    #[error("蓝牙适配器不可用: {0}")]
+    AdapterUnavailable(String),
+    #[error("没有蓝牙访问权限")]
+    PermissionDenied,
+    #[error("未找到设备: {0}")]
+    DeviceNotFound(String),
+    #[error("设备未连接: {0}")]
+    NotConnected(String),
+    #[error("服务 {service} 中未找到特征 {characteristic}")]
+    CharacteristicNotFound {
+        service: String,
+        characteristic: String,
+    },
+    /// 特征属性不支持该操作（读、写、通知）
+    #[error("特征 {characteristic} 不支持 {operation} 操作")]
+    UnsupportedOperation {
+        characteristic: String,
+        operation: String,
+    },
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("{0}")]
+    Timeout(String),
+    /// 测试序列中的断言不成立
+    #[error("{0}")]
+    AssertionFailed(String),
+    #[error("操作已取消")]
+    Cancelled,
+    #[error("{message}: {path}")]
+    Io { path: String, message: String },
+    /// 设备返回了不符合协议的数据（NACK、错误的响应等）
+    #[error("{0}")]
+    Protocol(String),
+    /// 设备回报的 offset / CRC 与本地不一致，可重传
+    #[error("{0}")]
+    ChecksumMismatch(String),
+    #[error("{0}")]
+    Busy(String),
+    #[error("{0}")]
+    Backend(String),
+}
+
+impl BleError {
+    pub fn io(path: &str, message: impl std::fmt::Display) -> Self {
+        BleError::Io {
+            path: path.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    /// btleplug 的错误不带设备ID，由调用方补上
+    pub fn for_device(self, id: &str) -> Self {
+        match self {
+            BleError::DeviceNotFound(d) if d.is_empty() => BleError::DeviceNotFound(id.to_string()),
+            BleError::NotConnected(d) if d.is_empty() => BleError::NotConnected(id.to_string()),
+            other => other,
+        }
+    }
+
+    pub fn unsupported(characteristic: impl std::fmt::Display, operation: &str) -> Self {
+        BleError::UnsupportedOperation {
+            characteristic: characteristic.to_string(),
+            operation: operation.to_string(),
+        }
+    }
+}
+
+impl ErrorCode for BleError {
+    fn code(&self) -> &'static str {
+        match self {
+            BleError::AdapterUnavailable(_) => "adapter_unavailable",
+            BleError::PermissionDenied => "permission_denied",
+            BleError::DeviceNotFound(_) => "device_not_found",
+            BleError::NotConnected(_) => "not_connected",
+            BleError::CharacteristicNotFound { .. } => "characteristic_not_found",
+            BleError::UnsupportedOperation { .. } => "unsupported_operation",
+            BleError::InvalidInput(_) => "invalid_input",
+            BleError::Timeout(_) => "timeout",
+            BleError::AssertionFailed(_) => "assertion_failed",
+            BleError::Cancelled => "cancelled",
+            BleError::Io { .. } => "io_error",
+            BleError::Protocol(_) => "protocol_error",
+            BleError::ChecksumMismatch(_) => "checksum_mismatch",
+            BleError::Busy(_) => "busy",
+            BleError::Backend(_) => "backend_error",
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            BleError::DeviceNotFound(id) | BleError::NotConnected(id) => {
+                Some(json!({ "device_id": id }))
+            }
+            BleError::CharacteristicNotFound {
+                service,
+                characteristic,
+            } => Some(json!({ "service": service, "characteristic": characteristic })),
+            BleError::UnsupportedOperation {
+                characteristic,
+                operation,
+            } => Some(json!({ "characteristic": characteristic, "operation": operation })),
+            BleError::Io { path, .. } => Some(json!({ "path": path })),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for BleError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_error(self, serializer)
+    }
+}
+
+impl From<btleplug::Error> for BleError {
+    fn from(e: btleplug::Error) -> Self {
+        match e {
+            btleplug::Error::PermissionDenied => BleError::PermissionDenied,
+            btleplug::Error::DeviceNotFound => BleError::DeviceNotFound(String::new()),
+            btleplug::Error::NotConnected => BleError::NotConnected(String::new()),
+            btleplug::Error::NotSupported(operation) => BleError::UnsupportedOperation {
+                characteristic: String::new(),
+                operation,
+            },
+            btleplug::Error::TimedOut(duration) => {
+                BleError::Timeout(format!("操作超时（{:?}）", duration))
+            }
+            other => BleError::Backend(other.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_code_message_and_details() {
+        let error = BleError::from(btleplug::Error::DeviceNotFound).for_device("dev-1");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "code": "device_not_found",
+                "message": "未找到设备: dev-1",
+                "details": { "device_id": "dev-1" },
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(BleError::Cancelled).unwrap(),
+            json!({ "code": "cancelled", "message": "操作已取消", "details": null })
+        );
+    }
+}
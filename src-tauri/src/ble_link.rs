@@ -0,0 +1,227 @@
+use btleplug::api::CharPropFlags;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::ble::{BleState, WriteMode};
+use crate::ble_backend::LinkParameters;
+use crate::ble_error::BleError;
+
+/// ATT 头部占用的字节数，单个包可携带的数据为 MTU - 3
+const ATT_HEADER: u16 = 3;
+/// MTU 未知时使用的默认包大小（最小 MTU 23 - 3）
+const DEFAULT_CHUNK: usize = 20;
+/// 单次吞吐测试的数据量上限
+const MAX_TEST_BYTES: usize = 10 * 1024 * 1024;
+
+/// 按 MTU 计算单个包可携带的字节数，MTU 未知或过小时使用默认值
+pub(crate) fn att_payload(mtu: Option<u16>) -> usize {
+    mtu.map(|mtu| mtu.saturating_sub(ATT_HEADER) as usize)
+        .filter(|size| *size > 0)
+        .unwrap_or(DEFAULT_CHUNK)
+}
+
+/// `get_link_info` 的返回值
+#[derive(Serialize, Clone, Debug)]
+pub struct LinkInfo {
+    pub device_id: String,
+    pub backend: String,
+    pub rssi: Option<i16>, // 最近一次广播的信号强度
+    #[serde(flatten)]
+    pub parameters: LinkParameters,
+    pub att_payload: Option<u16>, // 单个包可携带的最大字节数（MTU - 3）
+    pub unavailable: Vec<String>, // 当前后端/平台无法提供的参数
+}
+
+/// 吞吐测试方向
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ThroughputDirection {
+    #[default]
+    Write,
+    Read,
+}
+
+fn default_total_bytes() -> usize {
+    4096
+}
+
+/// 吞吐测试参数
+#[derive(Deserialize, Clone, Debug)]
+pub struct ThroughputRequest {
+    pub service: String,
+    pub characteristic: String,
+    #[serde(default)]
+    pub direction: ThroughputDirection,
+    #[serde(default = "default_total_bytes")]
+    pub total_bytes: usize,
+    pub chunk_size: Option<usize>, // 每次写入的字节数，缺省为 MTU - 3
+    #[serde(default)]
+    pub mode: WriteMode,
+}
+
+/// 单次操作耗时的统计，单位毫秒
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct LatencyStats {
+    pub min: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ThroughputReport {
+    pub direction: ThroughputDirection,
+    pub chunk_size: Option<usize>, // 读取测试时为 None
+    pub operations: usize,
+    pub total_bytes: usize,
+    pub elapsed_ms: f64,
+    pub bytes_per_sec: f64,
+    pub latency_ms: LatencyStats,
+}
+
+/// 汇总链路参数，平台不提供的字段列在 `unavailable` 中
+pub async fn link_info(state: &BleState, id: &str) -> Result<LinkInfo, BleError> {
+    state.ensure_connected(id).await?;
+    let backend = state.backend();
+    let parameters = backend.link_parameters(id).await?;
+    let rssi = backend
+        .advertisements()
+        .await?
+        .into_iter()
+        .find(|a| a.id == id)
+        .and_then(|a| a.rssi);
+
+    let mut unavailable = Vec::new();
+    for (name, missing) in [
+        ("mtu", parameters.mtu.is_none()),
+        ("phy", parameters.phy.is_none()),
+        ("connection_interval_ms", parameters.connection_interval_ms.is_none()),
+        ("peripheral_latency", parameters.peripheral_latency.is_none()),
+        ("supervision_timeout_ms", parameters.supervision_timeout_ms.is_none()),
+    ] {
+        if missing {
+            unavailable.push(name.to_string());
+        }
+    }
+
+    Ok(LinkInfo {
+        device_id: id.to_string(),
+        backend: backend.name().to_string(),
+        rssi,
+        att_payload: parameters.mtu.map(|mtu| mtu.saturating_sub(ATT_HEADER)),
+        parameters,
+        unavailable,
+    })
+}
+
+/// 反复写入或读取特征，统计吞吐量和单次操作耗时
+///
+/// 直接调用后端以免计时受会话记录等额外开销影响，测试中的读写不计入会话时间线。
+pub async fn throughput_test(
+    state: &BleState,
+    id: &str,
+    request: &ThroughputRequest,
+) -> Result<ThroughputReport, BleError> {
+    if request.total_bytes == 0 || request.total_bytes > MAX_TEST_BYTES {
+        return Err(BleError::InvalidInput(format!(
+            "测试数据量需在 1 到 {} 字节之间",
+            MAX_TEST_BYTES
+        )));
+    }
+
+    state.ensure_connected(id).await?;
+    let target = state
+        .find_characteristic(id, &request.service, &request.characteristic)
+        .await?;
+    let backend = state.backend();
+
+    let mut latencies = Vec::new();
+    let mut transferred = 0;
+    let mut chunk_size = None;
+    let started = Instant::now();
+
+    match request.direction {
+        ThroughputDirection::Write => {
+            let (write_type, required, operation) = request.mode.requirements();
+            if !target.properties.contains(required) {
+                return Err(BleError::unsupported(target.uuid, operation));
+            }
+
+            let size = match request.chunk_size {
+                Some(0) => return Err(BleError::InvalidInput("chunk_size 不能为 0".to_string())),
+                Some(size) => size,
+                None => att_payload(backend.link_parameters(id).await?.mtu),
+            };
+            chunk_size = Some(size);
+
+            // 递增字节序列，便于在设备端校验数据是否完整
+            let payload: Vec<u8> = (0..request.total_bytes).map(|i| i as u8).collect();
+            for chunk in payload.chunks(size) {
+                let op = Instant::now();
+                backend.write(id, &target, chunk, write_type).await?;
+                latencies.push(op.elapsed());
+                transferred += chunk.len();
+            }
+        }
+        ThroughputDirection::Read => {
+            if !target.properties.contains(CharPropFlags::READ) {
+                return Err(BleError::unsupported(target.uuid, "read"));
+            }
+            while transferred < request.total_bytes {
+                let op = Instant::now();
+                let data = backend.read(id, &target).await?;
+                latencies.push(op.elapsed());
+                if data.is_empty() {
+                    return Err(BleError::Protocol("特征值为空，无法统计读取吞吐量".to_string()));
+                }
+                transferred += data.len();
+            }
+        }
+    }
+
+    let elapsed = started.elapsed();
+    let report = ThroughputReport {
+        direction: request.direction,
+        chunk_size,
+        operations: latencies.len(),
+        total_bytes: transferred,
+        elapsed_ms: millis(elapsed),
+        bytes_per_sec: transferred as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        latency_ms: latency_stats(&mut latencies),
+    };
+    println!(
+        "Throughput test on {}: {} bytes in {:.1} ms ({:.0} B/s)",
+        id, report.total_bytes, report.elapsed_ms, report.bytes_per_sec
+    );
+    Ok(report)
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// 最小/平均/最大值及最近秩法（nearest-rank）百分位数
+fn latency_stats(samples: &mut [Duration]) -> LatencyStats {
+    if samples.is_empty() {
+        return LatencyStats::default();
+    }
+    samples.sort();
+    let percentile = |p: f64| {
+        let rank = ((p / 100.0) * samples.len() as f64).ceil() as usize;
+        millis(samples[rank.clamp(1, samples.len()) - 1])
+    };
+    let total: Duration = samples.iter().sum();
+
+    LatencyStats {
+        min: millis(samples[0]),
+        mean: millis(total) / samples.len() as f64,
+        p50: percentile(50.0),
+        p90: percentile(90.0),
+        p95: percentile(95.0),
+        p99: percentile(99.0),
+        max: millis(samples[samples.len() - 1]),
+    }
+}
@@ -0,0 +1,504 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::ble::parse_uuid;
+use crate::ble_codec::to_hex;
+use crate::ble_decoders::DecodedValue;
+use crate::ble_error::BleError;
+
+/// 用户自定义的特征解析规则文件（JSON 或 TOML）
+///
+/// ```toml
+/// name = "acme"
+///
+/// [[characteristics]]
+/// service = "6e400001-b5a3-f393-e0a9-e50e24dcca9e"   # 可选
+/// characteristic = "6e400003-b5a3-f393-e0a9-e50e24dcca9e"
+/// name = "Sensor Packet"
+///
+/// [[characteristics.fields]]
+/// name = "temperature"
+/// type = "i16"
+/// scale = 0.01
+/// unit = "°C"
+///
+/// [[characteristics.fields]]
+/// name = "status"
+/// type = "u8"
+/// flags = { "0" = "low_battery", "3" = "charging" }
+/// ```
+#[derive(Deserialize, Clone, Debug)]
+pub struct SchemaFile {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub characteristics: Vec<CharacteristicSchema>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CharacteristicSchema {
+    pub service: Option<String>,
+    pub characteristic: String,
+    pub name: String,
+    #[serde(default)]
+    pub fields: Vec<FieldSpec>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    U8,
+    U16,
+    U24,
+    U32,
+    U64,
+    I8,
+    I16,
+    I24,
+    I32,
+    I64,
+    F32,
+    F64,
+    String,
+    Bytes,
+}
+
+impl FieldType {
+    /// 固定宽度类型的字节数，字符串/字节数组返回 `None`
+    fn width(self) -> Option<usize> {
+        use FieldType::*;
+        match self {
+            U8 | I8 => Some(1),
+            U16 | I16 => Some(2),
+            U24 | I24 => Some(3),
+            U32 | I32 | F32 => Some(4),
+            U64 | I64 | F64 => Some(8),
+            String | Bytes => None,
+        }
+    }
+
+    fn is_signed(self) -> bool {
+        matches!(self, FieldType::I8 | FieldType::I16 | FieldType::I24 | FieldType::I32 | FieldType::I64)
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct FieldSpec {
+    pub name: String,
+    pub offset: Option<usize>, // 缺省时紧接上一个字段
+    #[serde(rename = "type")]
+    pub kind: FieldType,
+    #[serde(default)]
+    pub endian: Endian,
+    pub length: Option<usize>, // string/bytes 的长度，缺省时取到末尾
+    pub scale: Option<f64>,
+    pub bias: Option<f64>, // 缩放后再加上的偏移量
+    pub unit: Option<String>,
+    #[serde(default, rename = "enum")]
+    pub enum_values: HashMap<String, String>, // 原始值 → 名称
+    #[serde(default)]
+    pub flags: HashMap<String, String>, // 位序号 → 名称
+}
+
+/// 已校验、可直接用于解码的规则
+struct CompiledSchema {
+    source: String,
+    service: Option<Uuid>,
+    characteristic: Uuid,
+    name: String,
+    fields: Vec<FieldSpec>,
+    enums: Vec<HashMap<i128, String>>,
+    flags: Vec<BTreeMap<u32, String>>,
+}
+
+/// `load_ble_schema` 返回的摘要
+#[derive(Serialize, Clone, Debug)]
+pub struct SchemaSummary {
+    pub source: String,
+    pub path: String,
+    pub characteristics: Vec<String>,
+}
+
+/// 已加载的全部自定义规则，按文件路径分组，重复加载同一文件会替换旧规则
+#[derive(Default)]
+pub struct SchemaRegistry {
+    files: Vec<(String, Vec<CompiledSchema>)>,
+}
+
+impl SchemaRegistry {
+    /// 按扩展名解析 JSON / TOML 文件并加入注册表
+    pub fn load_file(&mut self, path: &str) -> Result<SchemaSummary, BleError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| BleError::io(path, format!("无法读取schema文件: {}", e)))?;
+        let is_toml = Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+        let file: SchemaFile = if is_toml {
+            toml::from_str(&text)
+                .map_err(|e| BleError::InvalidInput(format!("TOML schema格式错误: {}", e)))?
+        } else {
+            serde_json::from_str(&text)
+                .map_err(|e| BleError::InvalidInput(format!("JSON schema格式错误: {}", e)))?
+        };
+
+        let source = file.name.clone().unwrap_or_else(|| {
+            Path::new(path)
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.to_string())
+        });
+        let compiled = file
+            .characteristics
+            .into_iter()
+            .map(|c| compile(&source, c))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let summary = SchemaSummary {
+            source,
+            path: path.to_string(),
+            characteristics: compiled.iter().map(|c| c.name.clone()).collect(),
+        };
+        self.files.retain(|(p, _)| p != path);
+        self.files.push((path.to_string(), compiled));
+        Ok(summary)
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+
+    /// 查找匹配的规则并解码；后加载的文件优先
+    pub fn decode(&self, service: &Uuid, characteristic: &Uuid, data: &[u8]) -> Option<DecodedValue> {
+        let schema = self
+            .files
+            .iter()
+            .rev()
+            .flat_map(|(_, schemas)| schemas.iter())
+            .find(|s| {
+                s.characteristic == *characteristic && s.service.is_none_or(|uuid| uuid == *service)
+            })?;
+
+        let mut units = BTreeMap::new();
+        for field in &schema.fields {
+            if let Some(unit) = &field.unit {
+                units.insert(field.name.clone(), unit.clone());
+            }
+        }
+
+        let (fields, error) = match schema.decode(data) {
+            Ok(fields) => (Some(Value::Object(fields)), None),
+            Err(e) => (None, Some(e)),
+        };
+        Some(DecodedValue {
+            source: schema.source.clone(),
+            name: schema.name.clone(),
+            fields,
+            error,
+            units: (!units.is_empty()).then_some(units),
+        })
+    }
+}
+
+fn compile(source: &str, schema: CharacteristicSchema) -> Result<CompiledSchema, BleError> {
+    let mut enums = Vec::with_capacity(schema.fields.len());
+    let mut flags = Vec::with_capacity(schema.fields.len());
+
+    for field in &schema.fields {
+        let context = format!("{}.{}", schema.name, field.name);
+        let numeric = field.kind.width().is_some();
+        if !numeric && (!field.enum_values.is_empty() || !field.flags.is_empty()) {
+            return Err(BleError::InvalidInput(format!(
+                "{}: enum/flags 只能用于数值字段",
+                context
+            )));
+        }
+
+        let values = field
+            .enum_values
+            .iter()
+            .map(|(k, v)| {
+                k.trim()
+                    .parse::<i128>()
+                    .map(|k| (k, v.clone()))
+                    .map_err(|_| {
+                        BleError::InvalidInput(format!("{}: enum 键 '{}' 不是整数", context, k))
+                    })
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        let bits = field.kind.width().unwrap_or(0) as u32 * 8;
+        let bit_names = field
+            .flags
+            .iter()
+            .map(|(k, v)| match k.trim().parse::<u32>() {
+                Ok(bit) if bit < bits => Ok((bit, v.clone())),
+                _ => Err(BleError::InvalidInput(format!(
+                    "{}: flags 键 '{}' 不是有效的位序号",
+                    context, k
+                ))),
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+
+        enums.push(values);
+        flags.push(bit_names);
+    }
+
+    Ok(CompiledSchema {
+        source: source.to_string(),
+        service: schema.service.as_deref().map(parse_uuid).transpose()?,
+        characteristic: parse_uuid(&schema.characteristic)?,
+        name: schema.name,
+        fields: schema.fields,
+        enums,
+        flags,
+    })
+}
+
+impl CompiledSchema {
+    fn decode(&self, data: &[u8]) -> Result<Map<String, Value>, String> {
+        let mut fields = Map::new();
+        let mut cursor = 0;
+
+        for (index, field) in self.fields.iter().enumerate() {
+            let start = field.offset.unwrap_or(cursor);
+            let len = match field.kind.width() {
+                Some(width) => width,
+                None => field.length.unwrap_or(data.len().saturating_sub(start)),
+            };
+            // offset/length 来自规则文件，可能大到相加溢出
+            let end = match start.checked_add(len) {
+                Some(end) if end <= data.len() => end,
+                _ => {
+                    return Err(format!(
+                        "字段 {} 需要从 {} 开始的 {} 字节，数据只有 {} 字节",
+                        field.name,
+                        start,
+                        len,
+                        data.len()
+                    ));
+                }
+            };
+            let bytes = &data[start..end];
+            cursor = end;
+
+            let value = match field.kind {
+                FieldType::String => {
+                    Value::String(String::from_utf8_lossy(bytes).trim_end_matches('\0').to_string())
+                }
+                FieldType::Bytes => Value::String(to_hex(bytes)),
+                FieldType::F32 | FieldType::F64 => {
+                    let raw = read_uint(bytes, field.endian);
+                    let float = if field.kind == FieldType::F32 {
+                        f32::from_bits(raw as u32) as f64
+                    } else {
+                        f64::from_bits(raw)
+                    };
+                    scaled(field, float)
+                }
+                _ => {
+                    let raw = read_int(bytes, field.endian, field.kind.is_signed());
+                    if !self.flags[index].is_empty() {
+                        Value::Array(
+                            self.flags[index]
+                                .iter()
+                                .filter(|(bit, _)| (raw as u64) & (1u64 << **bit) != 0)
+                                .map(|(_, name)| Value::String(name.clone()))
+                                .collect(),
+                        )
+                    } else if let Some(name) = self.enums[index].get(&raw) {
+                        Value::String(name.clone())
+                    } else if field.scale.is_some() || field.bias.is_some() {
+                        scaled(field, raw as f64)
+                    } else if raw < 0 {
+                        Value::from(raw as i64)
+                    } else {
+                        Value::from(raw as u64)
+                    }
+                }
+            };
+            fields.insert(field.name.clone(), value);
+        }
+
+        Ok(fields)
+    }
+}
+
+fn scaled(field: &FieldSpec, value: f64) -> Value {
+    let value = value * field.scale.unwrap_or(1.0) + field.bias.unwrap_or(0.0);
+    serde_json::Number::from_f64(value)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+fn read_uint(bytes: &[u8], endian: Endian) -> u64 {
+    let fold = |acc: u64, b: &u8| (acc << 8) | *b as u64;
+    match endian {
+        Endian::Little => bytes.iter().rev().fold(0, fold),
+        Endian::Big => bytes.iter().fold(0, fold),
+    }
+}
+
+fn read_int(bytes: &[u8], endian: Endian, signed: bool) -> i128 {
+    let raw = read_uint(bytes, endian);
+    if signed {
+        let shift = 64 - bytes.len() as u32 * 8;
+        (((raw << shift) as i64) >> shift) as i128
+    } else {
+        raw as i128
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CHARACTERISTIC: &str = "6e400003-b5a3-f393-e0a9-e50e24dcca9e";
+
+    fn schema_file(name: &str, ext: &str, text: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}.{}", name, std::process::id(), ext));
+        std::fs::write(&path, text).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn load(name: &str, fields: Value) -> SchemaRegistry {
+        let text = json!({
+            "name": "acme",
+            "characteristics": [{
+                "characteristic": CHARACTERISTIC,
+                "name": "Sensor Packet",
+                "fields": fields,
+            }],
+        });
+        let path = schema_file(name, "json", &text.to_string());
+        let mut registry = SchemaRegistry::default();
+        registry.load_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        registry
+    }
+
+    fn decode(registry: &SchemaRegistry, data: &[u8]) -> DecodedValue {
+        let characteristic = parse_uuid(CHARACTERISTIC).unwrap();
+        registry
+            .decode(&Uuid::nil(), &characteristic, data)
+            .unwrap()
+    }
+
+    #[test]
+    fn decodes_field_types() {
+        let registry = load(
+            "schema-fields",
+            json!([
+                { "name": "mode", "type": "u8", "enum": { "1": "idle", "2": "active" } },
+                { "name": "status", "type": "u8", "flags": { "0": "low_battery", "3": "charging" } },
+                { "name": "pressure", "type": "u24", "endian": "big" },
+                { "name": "temperature", "type": "i16", "scale": 0.01, "unit": "°C" },
+                { "name": "label", "type": "string", "length": 4 },
+                { "name": "checksum", "type": "u8", "offset": 0 },
+            ]),
+        );
+        let decoded = decode(
+            &registry,
+            &[0x02, 0x09, 0x01, 0x02, 0x03, 0x18, 0xfc, b'a', b'b', 0, 0],
+        );
+        assert_eq!(decoded.source, "acme");
+        assert_eq!(decoded.error, None);
+        assert_eq!(
+            decoded.fields.unwrap(),
+            json!({
+                "mode": "active",
+                "status": ["low_battery", "charging"],
+                "pressure": 0x010203,
+                "temperature": -10.0,
+                "label": "ab",
+                "checksum": 2,
+            })
+        );
+        assert_eq!(decoded.units.unwrap()["temperature"], "°C");
+
+        // 枚举中没有的值按原始数值输出
+        let decoded = decode(
+            &registry,
+            &[0x07, 0x00, 0, 0, 0, 0, 0, b'x', b'y', b'z', b'w'],
+        );
+        assert_eq!(decoded.fields.unwrap()["mode"], 7);
+    }
+
+    #[test]
+    fn reports_short_data_and_huge_offsets() {
+        let registry = load(
+            "schema-offset",
+            json!([{ "name": "far", "type": "u32", "offset": usize::MAX }]),
+        );
+        let decoded = decode(&registry, &[0x01, 0x02]);
+        assert!(decoded.fields.is_none());
+        assert!(decoded.error.unwrap().starts_with("字段 far 需要"));
+
+        let registry = load("schema-short", json!([{ "name": "value", "type": "u16" }]));
+        assert!(decode(&registry, &[0x01]).error.is_some());
+    }
+
+    #[test]
+    fn loads_toml_and_rejects_malformed_files() {
+        let toml = format!(
+            "name = \"acme\"\n[[characteristics]]\ncharacteristic = \"{}\"\nname = \"Sensor\"\n\
+             [[characteristics.fields]]\nname = \"level\"\ntype = \"u8\"\n",
+            CHARACTERISTIC
+        );
+        let path = schema_file("schema-toml", "toml", &toml);
+        let mut registry = SchemaRegistry::default();
+        let summary = registry.load_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(summary.characteristics, ["Sensor"]);
+        assert_eq!(
+            decode(&registry, &[0x2a]).fields.unwrap(),
+            json!({ "level": 42 })
+        );
+
+        let field = |spec: Value| {
+            json!({ "characteristics": [{ "characteristic": CHARACTERISTIC, "name": "x", "fields": [spec] }] })
+                .to_string()
+        };
+        let malformed = [
+            ("json", "{".to_string()),
+            ("toml", "characteristics = 1".to_string()),
+            ("json", field(json!({ "name": "f", "type": "u128" }))),
+            (
+                "json",
+                field(json!({ "name": "f", "type": "string", "enum": { "1": "a" } })),
+            ),
+            (
+                "json",
+                field(json!({ "name": "f", "type": "u8", "enum": { "one": "a" } })),
+            ),
+            (
+                "json",
+                field(json!({ "name": "f", "type": "u8", "flags": { "8": "a" } })),
+            ),
+            (
+                "json",
+                json!({ "characteristics": [{ "characteristic": "xyz", "name": "x" }] })
+                    .to_string(),
+            ),
+        ];
+        for (index, (ext, text)) in malformed.iter().enumerate() {
+            let path = schema_file(&format!("schema-bad{}", index), ext, text);
+            let result = SchemaRegistry::default().load_file(&path);
+            std::fs::remove_file(&path).unwrap();
+            assert!(result.is_err(), "{}", text);
+        }
+        assert!(SchemaRegistry::default()
+            .load_file("/nonexistent/schema.json")
+            .is_err());
+    }
+}
@@ -0,0 +1,690 @@
+use btleplug::api::{CharPropFlags, Characteristic, ValueNotification};
+use futures::stream::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::ble::{scan_entry, BleState, CharacteristicValue, ScanFilterSpec, WriteMode};
+use crate::ble_backend::NotificationStream;
+use crate::ble_session::{Direction, TrafficEntry, TrafficKind};
+use crate::ble_codec::{decode_value, from_hex, ValueEncoding};
+use crate::ble_error::BleError;
+
+/// BLE 测试序列（JSON 或 YAML）
+///
+/// ```yaml
+/// name: hrm-smoke
+/// steps:
+///   - action: scan_for
+///     name_pattern: "^Sim Heart"
+///   - action: connect
+///   - action: subscribe
+///     service: "180d"
+///     characteristic: "2a37"
+///   - action: wait_for_notification
+///     service: "180d"
+///     characteristic: "2a37"
+///     timeout_ms: 3000
+///     expect: { field: heart_rate_bpm, min: 40, max: 200 }
+///   - action: disconnect
+/// ```
+#[derive(Deserialize, Clone, Debug)]
+pub struct BleSequence {
+    pub name: Option<String>,
+    pub device: Option<String>, // 默认设备ID，scan_for 找到设备后会替换
+    #[serde(default = "default_stop_on_failure")]
+    pub stop_on_failure: bool, // 失败后跳过剩余步骤（disconnect 仍会执行）
+    pub steps: Vec<SequenceStep>,
+}
+
+fn default_stop_on_failure() -> bool {
+    true
+}
+
+fn default_scan_timeout() -> u64 {
+    10_000
+}
+
+fn default_notification_timeout() -> u64 {
+    5_000
+}
+
+/// 序列中的单个步骤，`device` 缺省时使用序列的当前设备
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SequenceStep {
+    /// 扫描直到发现满足条件的设备，并将其设为当前设备
+    ScanFor {
+        #[serde(flatten)]
+        filter: ScanFilterSpec,
+        id: Option<String>,
+        #[serde(default = "default_scan_timeout")]
+        timeout_ms: u64,
+    },
+    Connect {
+        device: Option<String>,
+    },
+    Disconnect {
+        device: Option<String>,
+    },
+    Write {
+        device: Option<String>,
+        service: String,
+        characteristic: String,
+        value: String,
+        #[serde(default)]
+        encoding: ValueEncoding,
+        #[serde(default)]
+        mode: WriteMode,
+    },
+    Read {
+        device: Option<String>,
+        service: String,
+        characteristic: String,
+        #[serde(default)]
+        encoding: ValueEncoding,
+        expect: Option<Expectation>,
+    },
+    /// 开启通知；需要在触发通知的写入之前执行，否则可能错过通知
+    Subscribe {
+        device: Option<String>,
+        service: String,
+        characteristic: String,
+    },
+    WaitForNotification {
+        device: Option<String>,
+        service: String,
+        characteristic: String,
+        #[serde(default = "default_notification_timeout")]
+        timeout_ms: u64,
+        #[serde(default)]
+        encoding: ValueEncoding,
+        expect: Option<Expectation>,
+    },
+    /// 对上一次读取或通知的值做断言
+    #[serde(alias = "assert")]
+    Expect {
+        #[serde(flatten)]
+        expect: Expectation,
+    },
+    Delay {
+        ms: u64,
+    },
+}
+
+impl SequenceStep {
+    fn action(&self) -> &'static str {
+        match self {
+            SequenceStep::ScanFor { .. } => "scan_for",
+            SequenceStep::Connect { .. } => "connect",
+            SequenceStep::Disconnect { .. } => "disconnect",
+            SequenceStep::Write { .. } => "write",
+            SequenceStep::Read { .. } => "read",
+            SequenceStep::Subscribe { .. } => "subscribe",
+            SequenceStep::WaitForNotification { .. } => "wait_for_notification",
+            SequenceStep::Expect { .. } => "expect",
+            SequenceStep::Delay { .. } => "delay",
+        }
+    }
+}
+
+/// 对特征值的断言，所有给出的条件都需满足
+///
+/// `field` 指定 `decoded.fields` 中的字段（用 "." 分隔嵌套字段），
+/// 未指定时 `equals` / `min` / `max` 作用于按编码转换后的值。
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct Expectation {
+    pub value: Option<String>, // 与编码后的值逐字比较
+    pub hex: Option<String>,   // 与原始字节比较
+    pub field: Option<String>,
+    pub equals: Option<Value>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Expectation {
+    fn check(&self, actual: &CharacteristicValue) -> Result<(), String> {
+        if let Some(expected) = &self.value {
+            if *expected != actual.value {
+                return Err(format!("期望值 '{}'，实际为 '{}'", expected, actual.value));
+            }
+        }
+        if let Some(expected) = &self.hex {
+            let expected = from_hex(expected)?;
+            if from_hex(&actual.hex)? != expected {
+                return Err(format!(
+                    "期望字节 '{}'，实际为 '{}'",
+                    crate::ble_codec::to_hex(&expected),
+                    actual.hex
+                ));
+            }
+        }
+
+        let target = match &self.field {
+            Some(path) => actual
+                .decoded
+                .as_ref()
+                .and_then(|d| d.fields.as_ref())
+                .and_then(|fields| fields.pointer(&format!("/{}", path.replace('.', "/"))))
+                .cloned()
+                .ok_or_else(|| format!("解码结果中没有字段 '{}'", path))?,
+            None => actual
+                .value
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .unwrap_or_else(|| Value::String(actual.value.clone())),
+        };
+        let label = self.field.as_deref().unwrap_or("value");
+
+        if let Some(expected) = &self.equals {
+            let same = match (expected.as_f64(), target.as_f64()) {
+                (Some(a), Some(b)) => a == b,
+                _ => *expected == target,
+            };
+            if !same {
+                return Err(format!("{} 期望为 {}，实际为 {}", label, expected, target));
+            }
+        }
+        if self.min.is_some() || self.max.is_some() {
+            let number = target
+                .as_f64()
+                .ok_or_else(|| format!("{} 不是数值: {}", label, target))?;
+            if let Some(min) = self.min {
+                if number < min {
+                    return Err(format!("{} = {} 小于下限 {}", label, number, min));
+                }
+            }
+            if let Some(max) = self.max {
+                if number > max {
+                    return Err(format!("{} = {} 大于上限 {}", label, number, max));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BleSequence {
+    /// 按扩展名解析 YAML（.yaml / .yml）或 JSON 文件
+    pub fn from_file(path: &str) -> Result<Self, BleError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| BleError::io(path, format!("无法读取序列文件: {}", e)))?;
+        let is_yaml = Path::new(path).extension().is_some_and(|ext| {
+            ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml")
+        });
+        if is_yaml {
+            serde_yaml::from_str(&text)
+                .map_err(|e| BleError::InvalidInput(format!("YAML 序列格式错误: {}", e)))
+        } else {
+            serde_json::from_str(&text)
+                .map_err(|e| BleError::InvalidInput(format!("JSON 序列格式错误: {}", e)))
+        }
+    }
+}
+
+/// 步骤状态
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Running,
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// `ble-sequence-step` 事件的负载，每个步骤开始和结束时各发送一次
+#[derive(Serialize, Clone, Debug)]
+pub struct StepReport {
+    pub sequence: String,
+    pub index: usize,
+    pub action: String,
+    pub status: StepStatus,
+    pub device_id: Option<String>,
+    pub message: Option<String>, // 失败原因或步骤说明
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<BleError>, // 失败时的结构化错误，与命令返回的错误格式相同
+    pub value: Option<CharacteristicValue>, // read / wait_for_notification 得到的值
+    pub started_at: String,
+    pub duration_ms: u64,
+}
+
+/// 整个序列的执行结果，同时作为 `ble-sequence-finished` 事件的负载
+#[derive(Serialize, Clone, Debug)]
+pub struct SequenceReport {
+    pub sequence: String,
+    pub passed: bool,
+    pub steps: Vec<StepReport>,
+    pub duration_ms: u64,
+}
+
+/// 执行序列时的上下文
+struct Runner<'a> {
+    state: &'a BleState,
+    device: Option<String>,
+    streams: HashMap<String, NotificationStream>, // 设备ID → 通知流
+    pending: Vec<(String, ValueNotification)>, // 等待其他特征时收到的通知
+    subscribed: Vec<(String, Characteristic)>, // 序列开启的订阅，结束时取消
+    last: Option<CharacteristicValue>,
+    observed: Option<CharacteristicValue>, // 当前步骤得到的值
+}
+
+/// 依次执行序列中的步骤，每个步骤开始和结束时调用 `on_step`
+pub async fn run_sequence<F>(state: &BleState, sequence: &BleSequence, mut on_step: F) -> SequenceReport
+where
+    F: FnMut(&StepReport) + Send,
+{
+    let name = sequence.name.clone().unwrap_or_else(|| "sequence".to_string());
+    let started = Instant::now();
+    let mut runner = Runner {
+        state,
+        device: sequence.device.clone(),
+        streams: HashMap::new(),
+        pending: Vec::new(),
+        subscribed: Vec::new(),
+        last: None,
+        observed: None,
+    };
+    let mut reports = Vec::with_capacity(sequence.steps.len());
+    let mut failed = false;
+
+    for (index, step) in sequence.steps.iter().enumerate() {
+        let mut report = StepReport {
+            sequence: name.clone(),
+            index,
+            action: step.action().to_string(),
+            status: StepStatus::Running,
+            device_id: runner.step_device(step),
+            message: None,
+            error: None,
+            value: None,
+            started_at: crate::ble::now_rfc3339(),
+            duration_ms: 0,
+        };
+
+        // 失败后只执行断开步骤，避免设备停留在连接状态
+        let skip = failed
+            && sequence.stop_on_failure
+            && !matches!(step, SequenceStep::Disconnect { .. });
+        if skip {
+            report.status = StepStatus::Skipped;
+            on_step(&report);
+            reports.push(report);
+            continue;
+        }
+
+        on_step(&report);
+        runner.observed = None;
+        let step_started = Instant::now();
+        match runner.execute(step).await {
+            Ok((message, value)) => {
+                report.status = StepStatus::Passed;
+                report.message = message;
+                report.value = value;
+            }
+            Err(e) => {
+                println!("Sequence {} step {} ({}) failed: {}", name, index, report.action, e);
+                report.status = StepStatus::Failed;
+                report.message = Some(e.to_string());
+                report.error = Some(e);
+                report.value = runner.observed.take();
+                failed = true;
+            }
+        }
+        report.duration_ms = step_started.elapsed().as_millis() as u64;
+        report.device_id = report.device_id.or_else(|| runner.device.clone());
+        on_step(&report);
+        reports.push(report);
+    }
+
+    runner.cleanup().await;
+
+    SequenceReport {
+        sequence: name,
+        passed: !failed,
+        steps: reports,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+impl SequenceStep {
+    fn device(&self) -> Option<&String> {
+        match self {
+            SequenceStep::Connect { device }
+            | SequenceStep::Disconnect { device }
+            | SequenceStep::Write { device, .. }
+            | SequenceStep::Read { device, .. }
+            | SequenceStep::Subscribe { device, .. }
+            | SequenceStep::WaitForNotification { device, .. } => device.as_ref(),
+            SequenceStep::ScanFor { .. } | SequenceStep::Expect { .. } | SequenceStep::Delay { .. } => None,
+        }
+    }
+}
+
+type StepOutcome = (Option<String>, Option<CharacteristicValue>);
+
+impl Runner<'_> {
+    fn step_device(&self, step: &SequenceStep) -> Option<String> {
+        match step {
+            SequenceStep::ScanFor { .. } | SequenceStep::Expect { .. } | SequenceStep::Delay { .. } => None,
+            _ => step.device().or(self.device.as_ref()).cloned(),
+        }
+    }
+
+    fn resolve(&self, device: &Option<String>) -> Result<String, BleError> {
+        device.clone().or_else(|| self.device.clone()).ok_or_else(|| {
+            BleError::InvalidInput("未指定设备：请设置 device 或先执行 scan_for".to_string())
+        })
+    }
+
+    async fn execute(&mut self, step: &SequenceStep) -> Result<StepOutcome, BleError> {
+        match step {
+            SequenceStep::ScanFor { filter, id, timeout_ms } => {
+                let found = self.scan_for(filter, id.as_deref(), *timeout_ms).await?;
+                let message = format!("发现设备 {}", found);
+                self.device = Some(found);
+                Ok((Some(message), None))
+            }
+            SequenceStep::Connect { device } => {
+                let id = self.resolve(device)?;
+                self.state.connect(&id, None).await?;
+                self.device = Some(id);
+                Ok((None, None))
+            }
+            SequenceStep::Disconnect { device } => {
+                let id = self.resolve(device)?;
+                self.streams.remove(&id);
+                self.subscribed.retain(|(device, _)| *device != id);
+                self.state.disconnect(&id).await?;
+                Ok((None, None))
+            }
+            SequenceStep::Write { device, service, characteristic, value, encoding, mode } => {
+                let id = self.resolve(device)?;
+                let data = decode_value(value, *encoding).map_err(BleError::InvalidInput)?;
+                self.state.write(&id, service, characteristic, &data, *mode).await?;
+                Ok((Some(format!("写入 {} 字节", data.len())), None))
+            }
+            SequenceStep::Read { device, service, characteristic, encoding, expect } => {
+                let id = self.resolve(device)?;
+                let value = self.state.read(&id, service, characteristic, *encoding).await?;
+                self.observe(value, expect.as_ref())
+            }
+            SequenceStep::Subscribe { device, service, characteristic } => {
+                let id = self.resolve(device)?;
+                self.subscribe(&id, service, characteristic).await?;
+                Ok((None, None))
+            }
+            SequenceStep::WaitForNotification {
+                device,
+                service,
+                characteristic,
+                timeout_ms,
+                encoding,
+                expect,
+            } => {
+                let id = self.resolve(device)?;
+                let target = self.subscribe(&id, service, characteristic).await?;
+                let data = self.next_notification(&id, &target, *timeout_ms).await?;
+                let value = self.state.characteristic_value(&target, &data, *encoding)?;
+                self.observe(value, expect.as_ref())
+            }
+            SequenceStep::Expect { expect } => {
+                let last = self
+                    .last
+                    .clone()
+                    .ok_or_else(|| {
+                        BleError::InvalidInput(
+                            "没有可断言的值：需要先执行 read 或 wait_for_notification".to_string(),
+                        )
+                    })?;
+                self.observed = Some(last.clone());
+                expect.check(&last).map_err(BleError::AssertionFailed)?;
+                Ok((None, Some(last)))
+            }
+            SequenceStep::Delay { ms } => {
+                tokio::time::sleep(Duration::from_millis(*ms)).await;
+                Ok((None, None))
+            }
+        }
+    }
+
+    /// 记录最新的值并执行断言
+    fn observe(
+        &mut self,
+        value: CharacteristicValue,
+        expect: Option<&Expectation>,
+    ) -> Result<StepOutcome, BleError> {
+        self.last = Some(value.clone());
+        self.observed = Some(value.clone());
+        if let Some(expect) = expect {
+            expect.check(&value).map_err(BleError::AssertionFailed)?;
+        }
+        Ok((None, Some(value)))
+    }
+
+    async fn scan_for(
+        &self,
+        filter: &ScanFilterSpec,
+        id: Option<&str>,
+        timeout_ms: u64,
+    ) -> Result<String, BleError> {
+        let (scan_filter, device_filter) = filter.compile()?;
+        let backend = self.state.backend();
+        backend.start_scan(scan_filter).await?;
+
+        let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+        let found = loop {
+            let devices = self.state.discovered_devices(&device_filter).await?;
+            let found = devices
+                .into_iter()
+                .filter(|d| id.is_none_or(|id| d.id == id))
+                .max_by_key(|d| d.rssi.unwrap_or(i16::MIN));
+            if let Some(device) = found {
+                self.state.record(scan_entry(&device));
+                break Ok(device.id);
+            }
+            if Instant::now() >= deadline {
+                break Err(BleError::Timeout(format!(
+                    "{} ms 内未发现符合条件的设备",
+                    timeout_ms
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        };
+
+        if let Err(e) = backend.stop_scan().await {
+            eprintln!("Failed to stop scan: {}", e);
+        }
+        found
+    }
+
+    /// 开启特征通知（已开启时直接返回），并确保持有该设备的通知流
+    async fn subscribe(
+        &mut self,
+        id: &str,
+        service: &str,
+        characteristic: &str,
+    ) -> Result<Characteristic, BleError> {
+        self.state.ensure_connected(id).await?;
+        let target = self.state.find_characteristic(id, service, characteristic).await?;
+        if self.subscribed.iter().any(|(device, c)| device == id && c.uuid == target.uuid) {
+            return Ok(target);
+        }
+        if !target
+            .properties
+            .intersects(CharPropFlags::NOTIFY | CharPropFlags::INDICATE)
+        {
+            return Err(BleError::unsupported(target.uuid, "notify"));
+        }
+
+        // 先打开通知流再订阅，避免错过订阅后立即到达的通知
+        if !self.streams.contains_key(id) {
+            let stream = self.state.backend().notifications(id).await?;
+            self.streams.insert(id.to_string(), stream);
+        }
+        let result = self.state.backend().subscribe(id, &target).await;
+        self.state.record(
+            TrafficEntry::new(TrafficKind::Subscribe, Direction::Outgoing, id)
+                .characteristic(&target)
+                .result(&result),
+        );
+        result?;
+        self.subscribed.push((id.to_string(), target.clone()));
+        Ok(target)
+    }
+
+    /// 等待指定特征的下一条通知，期间收到的其他特征通知暂存起来
+    async fn next_notification(
+        &mut self,
+        id: &str,
+        target: &Characteristic,
+        timeout_ms: u64,
+    ) -> Result<Vec<u8>, BleError> {
+        if let Some(index) = self
+            .pending
+            .iter()
+            .position(|(device, n)| device == id && n.uuid == target.uuid)
+        {
+            return Ok(self.pending.remove(index).1.value);
+        }
+
+        let stream = self
+            .streams
+            .get_mut(id)
+            .ok_or_else(|| BleError::Backend(format!("设备 {} 没有通知流", id)))?;
+        let pending = &mut self.pending;
+        let subscribed = &self.subscribed;
+        let state = self.state;
+        let wait = async {
+            while let Some(notification) = stream.next().await {
+                let entry = TrafficEntry::new(TrafficKind::Notification, Direction::Incoming, id)
+                    .data(&notification.value);
+                state.record(
+                    match subscribed
+                        .iter()
+                        .find(|(device, c)| device == id && c.uuid == notification.uuid)
+                    {
+                        Some((_, characteristic)) => entry.characteristic(characteristic),
+                        None => entry,
+                    },
+                );
+                if notification.uuid == target.uuid {
+                    return Ok(notification.value);
+                }
+                pending.push((id.to_string(), notification));
+            }
+            Err(BleError::NotConnected(id.to_string()))
+        };
+
+        tokio::time::timeout(Duration::from_millis(timeout_ms), wait)
+            .await
+            .map_err(|_| {
+                BleError::Timeout(format!("{} ms 内未收到 {} 的通知", timeout_ms, target.uuid))
+            })?
+    }
+
+    /// 取消序列自己开启、且前端没有订阅的通知
+    async fn cleanup(&mut self) {
+        for (id, target) in self.subscribed.drain(..) {
+            if self.state.has_subscription(&id, &target).await {
+                continue;
+            }
+            let result = self.state.backend().unsubscribe(&id, &target).await;
+            self.state.record(
+                TrafficEntry::new(TrafficKind::Unsubscribe, Direction::Outgoing, &id)
+                    .characteristic(&target)
+                    .result(&result),
+            );
+            if let Err(e) = result {
+                eprintln!("Failed to unsubscribe {} on {}: {}", target.uuid, id, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ble_backend::BleBackend;
+    use crate::ble_sim::{SimScenario, SimulatedBackend};
+    use std::sync::Arc;
+
+    fn sim_state() -> (SimulatedBackend, BleState) {
+        let backend = SimulatedBackend::new(&SimScenario::demo()).unwrap();
+        let state = BleState::new(Arc::new(backend.clone()));
+        (backend, state)
+    }
+
+    #[tokio::test]
+    async fn yaml_sequence_runs_to_completion() {
+        let (_, state) = sim_state();
+        let sequence: BleSequence = serde_yaml::from_str(
+            r#"
+name: hrm-smoke
+steps:
+  - action: scan_for
+    name_pattern: "^Sim Heart"
+    timeout_ms: 1000
+  - action: connect
+  - action: read
+    service: "180f"
+    characteristic: "2a19"
+    encoding: u8
+    expect: { value: "90", field: level_percent, min: 50 }
+  - action: wait_for_notification
+    service: "180d"
+    characteristic: "2a37"
+    timeout_ms: 3000
+  - action: assert
+    field: heart_rate_bpm
+    equals: 72
+  - action: delay
+    ms: 10
+  - action: disconnect
+"#,
+        )
+        .unwrap();
+
+        let mut events = Vec::new();
+        let report = run_sequence(&state, &sequence, |step| events.push(step.status)).await;
+
+        assert!(report.passed, "{:?}", report.steps);
+        assert_eq!(report.steps.len(), 7);
+        assert!(report.steps.iter().all(|s| s.status == StepStatus::Passed));
+        assert_eq!(report.steps[0].message.as_deref(), Some("发现设备 sim-hrm-01"));
+        assert_eq!(report.steps[6].device_id.as_deref(), Some("sim-hrm-01"));
+        // 每个步骤一条 running + 一条结束事件
+        assert_eq!(events.len(), 14);
+    }
+
+    #[tokio::test]
+    async fn failed_expectation_skips_to_disconnect() {
+        let (backend, state) = sim_state();
+        let sequence: BleSequence = serde_json::from_value(serde_json::json!({
+            "device": "sim-hrm-01",
+            "steps": [
+                { "action": "connect" },
+                { "action": "read", "service": "180f", "characteristic": "2a19",
+                  "expect": { "hex": "64" } },
+                { "action": "wait_for_notification", "service": "180d",
+                  "characteristic": "2a37", "timeout_ms": 50 },
+                { "action": "disconnect" }
+            ]
+        }))
+        .unwrap();
+
+        let report = run_sequence(&state, &sequence, |_| {}).await;
+        let statuses: Vec<_> = report.steps.iter().map(|s| s.status).collect();
+
+        assert!(!report.passed);
+        assert_eq!(
+            statuses,
+            [StepStatus::Passed, StepStatus::Failed, StepStatus::Skipped, StepStatus::Passed]
+        );
+        assert_eq!(report.steps[1].value.as_ref().unwrap().hex, "5a");
+        assert!(!backend.is_connected("sim-hrm-01").await.unwrap());
+    }
+}
@@ -0,0 +1,261 @@
+use btleplug::api::Characteristic;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+use crate::ble_codec::to_hex;
+use crate::ble_error::BleError;
+
+/// 单个会话最多保留的记录数，超出后丢弃最早的记录
+const MAX_ENTRIES: usize = 50_000;
+
+/// 记录的操作类型
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrafficKind {
+    ScanResult,
+    Connect,
+    Disconnect,
+    ConnectionState, // 意外断线、自动重连等
+    Read,
+    Write,
+    Subscribe,
+    Unsubscribe,
+    Notification,
+}
+
+/// 数据方向：outgoing 为主机 → 设备，incoming 为设备 → 主机，internal 为本地状态变化
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Outgoing,
+    Incoming,
+    Internal,
+}
+
+/// 会话时间线中的一条记录
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrafficEntry {
+    #[serde(default)]
+    pub seq: u64,
+    pub timestamp: String, // RFC 3339，毫秒精度
+    pub timestamp_ms: i64,
+    pub kind: TrafficKind,
+    pub direction: Direction,
+    pub device_id: Option<String>,
+    pub service: Option<String>,
+    pub characteristic: Option<String>,
+    pub hex: Option<String>,
+    pub length: Option<usize>,
+    pub rssi: Option<i16>,
+    pub detail: Option<String>, // 设备名、连接状态等补充说明
+    pub error: Option<String>,  // 操作失败时的错误信息
+}
+
+impl TrafficEntry {
+    pub fn new(kind: TrafficKind, direction: Direction, device_id: &str) -> Self {
+        let now = Utc::now();
+        TrafficEntry {
+            seq: 0,
+            timestamp: now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            timestamp_ms: now.timestamp_millis(),
+            kind,
+            direction,
+            device_id: Some(device_id.to_string()),
+            service: None,
+            characteristic: None,
+            hex: None,
+            length: None,
+            rssi: None,
+            detail: None,
+            error: None,
+        }
+    }
+
+    pub fn characteristic(mut self, characteristic: &Characteristic) -> Self {
+        self.service = Some(characteristic.service_uuid.to_string());
+        self.characteristic = Some(characteristic.uuid.to_string());
+        self
+    }
+
+    /// 按用户输入的UUID记录（特征尚未找到时）
+    pub fn target(mut self, service: &str, characteristic: &str) -> Self {
+        self.service = Some(service.to_string());
+        self.characteristic = Some(characteristic.to_string());
+        self
+    }
+
+    pub fn data(mut self, data: &[u8]) -> Self {
+        self.hex = Some(to_hex(data));
+        self.length = Some(data.len());
+        self
+    }
+
+    pub fn rssi(mut self, rssi: Option<i16>) -> Self {
+        self.rssi = rssi;
+        self
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// 操作失败时记录错误信息
+    pub fn result<T, E: std::fmt::Display>(mut self, result: &Result<T, E>) -> Self {
+        if let Err(e) = result {
+            self.error = Some(e.to_string());
+        }
+        self
+    }
+}
+
+/// 导出格式
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Ndjson,
+    Csv,
+}
+
+/// `get_ble_session` 返回的会话快照
+#[derive(Serialize, Clone, Debug)]
+pub struct SessionSnapshot {
+    pub session_id: String,
+    pub started_at: String,
+    pub dropped: u64, // 超出容量被丢弃的记录数
+    pub entries: Vec<TrafficEntry>,
+}
+
+/// 当前会话的BLE操作时间线
+pub struct SessionLog {
+    session_id: String,
+    started_at: String,
+    entries: VecDeque<TrafficEntry>,
+    next_seq: u64,
+    dropped: u64,
+}
+
+impl Default for SessionLog {
+    fn default() -> Self {
+        let now = Utc::now();
+        SessionLog {
+            session_id: now.format("%Y%m%d-%H%M%S").to_string(),
+            started_at: now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            entries: VecDeque::new(),
+            next_seq: 1,
+            dropped: 0,
+        }
+    }
+}
+
+impl SessionLog {
+    pub fn record(&mut self, mut entry: TrafficEntry) {
+        entry.seq = self.next_seq;
+        self.next_seq += 1;
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.pop_front();
+            self.dropped += 1;
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// 会话快照；`since` 为上次拿到的最大序号，只返回之后的记录
+    pub fn snapshot(&self, since: Option<u64>) -> SessionSnapshot {
+        let since = since.unwrap_or(0);
+        SessionSnapshot {
+            session_id: self.session_id.clone(),
+            started_at: self.started_at.clone(),
+            dropped: self.dropped,
+            entries: self
+                .entries
+                .iter()
+                .filter(|e| e.seq > since)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// 导出全部记录，返回写入的条数
+    pub fn export(&self, path: &str, format: ExportFormat) -> Result<usize, BleError> {
+        let file = File::create(path)
+            .map_err(|e| BleError::io(path, format!("无法创建导出文件: {}", e)))?;
+        let mut writer = BufWriter::new(file);
+        let io_err = |e: std::io::Error| BleError::io(path, format!("写入导出文件失败: {}", e));
+
+        match format {
+            ExportFormat::Ndjson => {
+                for entry in &self.entries {
+                    let line = serde_json::to_string(entry)
+                        .map_err(|e| BleError::Backend(e.to_string()))?;
+                    writeln!(writer, "{}", line).map_err(io_err)?;
+                }
+            }
+            ExportFormat::Csv => {
+                writeln!(
+                    writer,
+                    "seq,timestamp,kind,direction,device_id,service,characteristic,hex,length,rssi,detail,error"
+                )
+                .map_err(io_err)?;
+                for entry in &self.entries {
+                    let row = [
+                        entry.seq.to_string(),
+                        entry.timestamp.clone(),
+                        enum_name(&entry.kind),
+                        enum_name(&entry.direction),
+                        entry.device_id.clone().unwrap_or_default(),
+                        entry.service.clone().unwrap_or_default(),
+                        entry.characteristic.clone().unwrap_or_default(),
+                        entry.hex.clone().unwrap_or_default(),
+                        entry.length.map(|l| l.to_string()).unwrap_or_default(),
+                        entry.rssi.map(|r| r.to_string()).unwrap_or_default(),
+                        entry.detail.clone().unwrap_or_default(),
+                        entry.error.clone().unwrap_or_default(),
+                    ];
+                    let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+                    writeln!(writer, "{}", row.join(",")).map_err(io_err)?;
+                }
+            }
+        }
+
+        writer.flush().map_err(io_err)?;
+        Ok(self.entries.len())
+    }
+}
+
+/// 读取导出的 NDJSON 文件，用于回放
+pub fn read_ndjson(path: &str) -> Result<Vec<TrafficEntry>, BleError> {
+    let file =
+        File::open(path).map_err(|e| BleError::io(path, format!("无法打开会话文件: {}", e)))?;
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Ok(l) if l.trim().is_empty()))
+        .map(|(index, line)| {
+            let line = line.map_err(|e| BleError::io(path, format!("读取会话文件失败: {}", e)))?;
+            serde_json::from_str(&line).map_err(|e| {
+                BleError::InvalidInput(format!("第 {} 行格式错误: {}", index + 1, e))
+            })
+        })
+        .collect()
+}
+
+/// 枚举值序列化后的名称，如 `TrafficKind::ScanResult` → "scan_result"
+fn enum_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+/// 含逗号、引号或换行的字段用双引号包裹
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
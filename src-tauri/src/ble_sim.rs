@@ -0,0 +1,638 @@
+use async_trait::async_trait;
+use btleplug::api::{
+    CharPropFlags, Characteristic, Descriptor, ScanFilter, Service, ValueNotification, WriteType,
+};
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::ble::{parse_uuid, CHAR_PROPERTY_NAMES};
+use crate::ble_backend::{
+    Advertisement, BackendEvent, BleBackend, EventStream, LinkParameters, NotificationStream,
+};
+use crate::ble_codec::from_hex;
+use crate::ble_error::BleError;
+
+/// 模拟场景，可从JSON文件加载（见 `BLE_SIM_SCENARIO`）
+///
+/// 二进制值一律用十六进制字符串表示；`manufacturer_data` 的键为十进制厂商ID。
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct SimScenario {
+    #[serde(default)]
+    pub peripherals: Vec<SimPeripheral>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct SimPeripheral {
+    pub id: String,
+    pub name: Option<String>,
+    pub rssi: Option<i16>,
+    pub tx_power: Option<i16>,
+    #[serde(default)]
+    pub advertised_services: Vec<String>,
+    #[serde(default)]
+    pub manufacturer_data: HashMap<u16, String>,
+    #[serde(default)]
+    pub service_data: HashMap<String, String>,
+    #[serde(default)]
+    pub services: Vec<SimService>,
+    pub disconnect_after_ms: Option<u64>, // 连接后经过该时间模拟掉线
+    #[serde(default)]
+    pub link: LinkParameters, // 连接后上报的链路参数
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct SimService {
+    pub uuid: String,
+    #[serde(default = "default_primary")]
+    pub primary: bool,
+    #[serde(default)]
+    pub characteristics: Vec<SimCharacteristic>,
+}
+
+fn default_primary() -> bool {
+    true
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct SimCharacteristic {
+    pub uuid: String,
+    #[serde(default)]
+    pub properties: Vec<String>, // 与 get_gatt_services 返回的属性名一致
+    #[serde(default)]
+    pub value: String,
+    #[serde(default)]
+    pub descriptors: Vec<String>,
+    pub notify_interval_ms: Option<u64>, // 订阅后按周期推送当前值
+    #[serde(default)]
+    pub on_write: Vec<SimResponse>,
+}
+
+/// 写入特征后自动推送的通知，`when` 为空时任何写入都会触发
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct SimResponse {
+    pub when: Option<String>,
+    pub notify: String,
+    pub value: String,
+}
+
+impl SimScenario {
+    pub fn from_file(path: &str) -> Result<Self, BleError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| BleError::io(path, format!("无法读取模拟场景: {}", e)))?;
+        serde_json::from_str(&text)
+            .map_err(|e| BleError::InvalidInput(format!("模拟场景格式错误: {}", e)))
+    }
+
+    /// 内置演示场景：一个心率带和一个只广播的设备
+    pub fn demo() -> Self {
+        let characteristic = |uuid: &str, properties: &[&str], value: &str| SimCharacteristic {
+            uuid: uuid.to_string(),
+            properties: properties.iter().map(|p| p.to_string()).collect(),
+            value: value.to_string(),
+            ..Default::default()
+        };
+
+        SimScenario {
+            peripherals: vec![
+                SimPeripheral {
+                    id: "sim-hrm-01".to_string(),
+                    name: Some("Sim Heart Rate".to_string()),
+                    rssi: Some(-52),
+                    tx_power: Some(-4),
+                    advertised_services: vec!["180d".to_string()],
+                    link: LinkParameters {
+                        mtu: Some(247),
+                        phy: Some("2m".to_string()),
+                        connection_interval_ms: Some(30.0),
+                        peripheral_latency: Some(0),
+                        supervision_timeout_ms: Some(4000),
+                    },
+                    services: vec![
+                        SimService {
+                            uuid: "180d".to_string(),
+                            primary: true,
+                            characteristics: vec![SimCharacteristic {
+                                descriptors: vec!["2902".to_string()],
+                                notify_interval_ms: Some(1000),
+                                ..characteristic("2a37", &["notify"], "0648")
+                            }],
+                        },
+                        SimService {
+                            uuid: "180f".to_string(),
+                            primary: true,
+                            characteristics: vec![characteristic("2a19", &["read", "notify"], "5a")],
+                        },
+                        SimService {
+                            uuid: "180a".to_string(),
+                            primary: true,
+                            characteristics: vec![
+                                characteristic("2a29", &["read"], "41636d65"),
+                                characteristic("2a24", &["read"], "48524d2d31"),
+                            ],
+                        },
+                    ],
+                    ..Default::default()
+                },
+                SimPeripheral {
+                    id: "sim-beacon-01".to_string(),
+                    name: None,
+                    rssi: Some(-71),
+                    manufacturer_data: HashMap::from([(
+                        0x004C,
+                        "0215f7826da64fa24e988024bc5b71e0893e00010002c5".to_string(),
+                    )]),
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+}
+
+/// 特征按 (服务UUID, 特征UUID) 区分，不同服务下可以有同一UUID的特征
+type CharKey = (Uuid, Uuid);
+
+fn char_key(characteristic: &Characteristic) -> CharKey {
+    (characteristic.service_uuid, characteristic.uuid)
+}
+
+/// 写入触发的通知：(匹配的写入值, 推送的特征, 推送的值)，推送的特征与写入的特征在同一服务下
+type WriteResponse = (Option<Vec<u8>>, CharKey, Vec<u8>);
+
+/// 模拟外设的运行时状态
+struct SimDevice {
+    advertisement: Advertisement,
+    services: Vec<Service>,
+    values: HashMap<CharKey, Vec<u8>>,
+    notify_intervals: HashMap<CharKey, u64>,
+    on_write: HashMap<CharKey, Vec<WriteResponse>>,
+    disconnect_after_ms: Option<u64>,
+    link: LinkParameters,
+    connected: bool,
+    subscribed: HashSet<CharKey>,
+    writes: Vec<(Uuid, Vec<u8>)>,
+    failing_subscribes: u32, // 接下来这么多次订阅返回错误
+    notify_tx: broadcast::Sender<ValueNotification>,
+    tasks: Vec<JoinHandle<()>>, // 定时掉线等后台任务，断开时取消
+    notifiers: HashMap<CharKey, JoinHandle<()>>, // 每个特征至多一个周期通知任务
+}
+
+impl SimDevice {
+    fn from_config(config: &SimPeripheral) -> Result<Self, BleError> {
+        let mut services = Vec::new();
+        let mut values = HashMap::new();
+        let mut notify_intervals = HashMap::new();
+        let mut on_write = HashMap::new();
+
+        for service in &config.services {
+            let service_uuid = parse_uuid(&service.uuid)?;
+            let mut characteristics = BTreeSet::new();
+
+            for c in &service.characteristics {
+                let uuid = parse_uuid(&c.uuid)?;
+                let mut properties = CharPropFlags::empty();
+                for name in &c.properties {
+                    let (flag, _) = CHAR_PROPERTY_NAMES
+                        .iter()
+                        .find(|(_, n)| n == name)
+                        .ok_or_else(|| BleError::InvalidInput(format!("未知的特征属性: {}", name)))?;
+                    properties |= *flag;
+                }
+                let descriptors = c
+                    .descriptors
+                    .iter()
+                    .map(|d| {
+                        Ok(Descriptor {
+                            uuid: parse_uuid(d)?,
+                            service_uuid,
+                            characteristic_uuid: uuid,
+                        })
+                    })
+                    .collect::<Result<BTreeSet<_>, BleError>>()?;
+
+                characteristics.insert(Characteristic {
+                    uuid,
+                    service_uuid,
+                    properties,
+                    descriptors,
+                });
+                let key = (service_uuid, uuid);
+                values.insert(key, hex(&c.value)?);
+                if let Some(interval) = c.notify_interval_ms {
+                    notify_intervals.insert(key, interval);
+                }
+
+                let responses = c
+                    .on_write
+                    .iter()
+                    .map(|r| {
+                        let when = r.when.as_deref().map(hex).transpose()?;
+                        Ok((when, (service_uuid, parse_uuid(&r.notify)?), hex(&r.value)?))
+                    })
+                    .collect::<Result<Vec<WriteResponse>, BleError>>()?;
+                if !responses.is_empty() {
+                    on_write.insert(key, responses);
+                }
+            }
+
+            services.push(Service {
+                uuid: service_uuid,
+                primary: service.primary,
+                characteristics,
+            });
+        }
+
+        let advertisement = Advertisement {
+            id: config.id.clone(),
+            name: config.name.clone(),
+            rssi: config.rssi,
+            tx_power: config.tx_power,
+            services: config
+                .advertised_services
+                .iter()
+                .map(|s| parse_uuid(s))
+                .collect::<Result<_, _>>()?,
+            manufacturer_data: config
+                .manufacturer_data
+                .iter()
+                .map(|(id, data)| Ok((*id, hex(data)?)))
+                .collect::<Result<_, BleError>>()?,
+            service_data: config
+                .service_data
+                .iter()
+                .map(|(uuid, data)| Ok((parse_uuid(uuid)?, hex(data)?)))
+                .collect::<Result<_, BleError>>()?,
+        };
+
+        Ok(SimDevice {
+            advertisement,
+            services,
+            values,
+            notify_intervals,
+            on_write,
+            disconnect_after_ms: config.disconnect_after_ms,
+            link: config.link.clone(),
+            connected: false,
+            subscribed: HashSet::new(),
+            writes: Vec::new(),
+            failing_subscribes: 0,
+            notify_tx: broadcast::channel(256).0,
+            tasks: Vec::new(),
+            notifiers: HashMap::new(),
+        })
+    }
+
+    fn has_characteristic(&self, key: &CharKey) -> bool {
+        self.services
+            .iter()
+            .filter(|s| s.uuid == key.0)
+            .flat_map(|s| s.characteristics.iter())
+            .any(|c| c.uuid == key.1)
+    }
+
+    /// 向已订阅的特征推送通知，返回是否实际发送
+    ///
+    /// 与 btleplug 一致，通知中只带特征UUID，不带服务UUID。
+    fn push(&self, key: CharKey, value: Vec<u8>) -> bool {
+        if !self.connected || !self.subscribed.contains(&key) {
+            return false;
+        }
+        self.notify_tx
+            .send(ValueNotification { uuid: key.1, value })
+            .is_ok()
+    }
+
+    fn reset_link(&mut self) {
+        self.connected = false;
+        self.subscribed.clear();
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        for (_, notifier) in self.notifiers.drain() {
+            notifier.abort();
+        }
+    }
+}
+
+struct SimInner {
+    devices: Mutex<HashMap<String, SimDevice>>,
+    scan_filter: Mutex<Option<ScanFilter>>,
+    events: broadcast::Sender<BackendEvent>,
+}
+
+impl SimInner {
+    fn with_device<T>(
+        &self,
+        id: &str,
+        f: impl FnOnce(&mut SimDevice) -> Result<T, BleError>,
+    ) -> Result<T, BleError> {
+        let mut devices = self.devices.lock().unwrap();
+        let device = devices
+            .get_mut(id)
+            .ok_or_else(|| BleError::DeviceNotFound(id.to_string()))?;
+        f(device)
+    }
+
+    fn with_connected<T>(
+        &self,
+        id: &str,
+        f: impl FnOnce(&mut SimDevice) -> Result<T, BleError>,
+    ) -> Result<T, BleError> {
+        self.with_device(id, |device| {
+            if !device.connected {
+                return Err(BleError::NotConnected(id.to_string()));
+            }
+            f(device)
+        })
+    }
+
+    fn drop_connection(&self, id: &str) -> Result<(), BleError> {
+        self.with_device(id, |device| {
+            device.reset_link();
+            Ok(())
+        })?;
+        let _ = self.events.send(BackendEvent::Disconnected(id.to_string()));
+        Ok(())
+    }
+}
+
+/// 进程内模拟后端：广播、GATT表、通知、掉线均由场景或测试代码控制
+#[derive(Clone)]
+pub struct SimulatedBackend {
+    inner: Arc<SimInner>,
+}
+
+impl SimulatedBackend {
+    pub fn new(scenario: &SimScenario) -> Result<Self, BleError> {
+        let backend = SimulatedBackend {
+            inner: Arc::new(SimInner {
+                devices: Mutex::new(HashMap::new()),
+                scan_filter: Mutex::new(None),
+                events: broadcast::channel(64).0,
+            }),
+        };
+        for peripheral in &scenario.peripherals {
+            backend.add_peripheral(peripheral)?;
+        }
+        Ok(backend)
+    }
+
+    pub fn add_peripheral(&self, config: &SimPeripheral) -> Result<(), BleError> {
+        let device = SimDevice::from_config(config)?;
+        self.inner
+            .devices
+            .lock()
+            .unwrap()
+            .insert(config.id.clone(), device);
+        Ok(())
+    }
+
+    /// 修改特征的当前值（下次读取或周期通知生效）
+    pub fn set_value(
+        &self,
+        id: &str,
+        characteristic: &Characteristic,
+        value: Vec<u8>,
+    ) -> Result<(), BleError> {
+        self.inner.with_device(id, |device| {
+            device.values.insert(char_key(characteristic), value);
+            Ok(())
+        })
+    }
+
+    /// 模拟外设推送一条通知，特征未被订阅时不发送并返回 false
+    pub fn notify(
+        &self,
+        id: &str,
+        characteristic: &Characteristic,
+        value: Vec<u8>,
+    ) -> Result<bool, BleError> {
+        self.inner.with_device(
+            id,
+            |device| Ok(device.push(char_key(characteristic), value)),
+        )
+    }
+
+    /// 模拟链路丢失
+    pub fn drop_connection(&self, id: &str) -> Result<(), BleError> {
+        self.inner.drop_connection(id)
+    }
+
+    /// 让接下来的 `count` 次订阅失败，用于模拟重连时订阅出错
+    pub fn fail_subscribes(&self, id: &str, count: u32) -> Result<(), BleError> {
+        self.inner.with_device(id, |device| {
+            device.failing_subscribes = count;
+            Ok(())
+        })
+    }
+
+    /// 设备收到的全部写入，按时间顺序
+    pub fn writes(&self, id: &str) -> Result<Vec<(Uuid, Vec<u8>)>, BleError> {
+        self.inner.with_device(id, |device| Ok(device.writes.clone()))
+    }
+}
+
+#[async_trait]
+impl BleBackend for SimulatedBackend {
+    fn name(&self) -> &'static str {
+        "simulated"
+    }
+
+    async fn start_scan(&self, filter: ScanFilter) -> Result<(), BleError> {
+        *self.inner.scan_filter.lock().unwrap() = Some(filter);
+        Ok(())
+    }
+
+    async fn stop_scan(&self) -> Result<(), BleError> {
+        // 与 btleplug 一致，停止扫描后已发现的设备仍然可见
+        Ok(())
+    }
+
+    async fn advertisements(&self) -> Result<Vec<Advertisement>, BleError> {
+        // 与真实适配器一致：从未扫描时看不到任何设备，服务过滤由“系统”完成
+        let Some(filter) = self.inner.scan_filter.lock().unwrap().clone() else {
+            return Ok(Vec::new());
+        };
+        let devices = self.inner.devices.lock().unwrap();
+        Ok(devices
+            .values()
+            .map(|d| d.advertisement.clone())
+            .filter(|a| {
+                filter.services.is_empty()
+                    || filter.services.iter().any(|s| a.services.contains(s))
+            })
+            .collect())
+    }
+
+    async fn events(&self) -> Result<EventStream, BleError> {
+        let rx = self.inner.events.subscribe();
+        Ok(broadcast_stream(rx))
+    }
+
+    async fn connect(&self, id: &str) -> Result<(), BleError> {
+        let disconnect_after = self.inner.with_device(id, |device| {
+            device.connected = true;
+            Ok(device.disconnect_after_ms)
+        })?;
+
+        if let Some(ms) = disconnect_after {
+            let inner = self.inner.clone();
+            let id_owned = id.to_string();
+            let task = tokio::spawn(async move {
+                tokio::time::sleep(std::time::Duration::from_millis(ms)).await;
+                let _ = inner.drop_connection(&id_owned);
+            });
+            self.inner.with_device(id, |device| {
+                device.tasks.push(task);
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    async fn disconnect(&self, id: &str) -> Result<(), BleError> {
+        self.inner.with_device(id, |device| {
+            device.reset_link();
+            Ok(())
+        })
+    }
+
+    async fn is_connected(&self, id: &str) -> Result<bool, BleError> {
+        self.inner.with_device(id, |device| Ok(device.connected))
+    }
+
+    async fn link_parameters(&self, id: &str) -> Result<LinkParameters, BleError> {
+        self.inner.with_connected(id, |device| Ok(device.link.clone()))
+    }
+
+    async fn services(&self, id: &str) -> Result<Vec<Service>, BleError> {
+        self.inner
+            .with_connected(id, |device| Ok(device.services.clone()))
+    }
+
+    async fn read(&self, id: &str, characteristic: &Characteristic) -> Result<Vec<u8>, BleError> {
+        self.inner.with_connected(id, |device| {
+            device
+                .values
+                .get(&char_key(characteristic))
+                .cloned()
+                .ok_or_else(|| not_found(characteristic))
+        })
+    }
+
+    async fn write(
+        &self,
+        id: &str,
+        characteristic: &Characteristic,
+        data: &[u8],
+        _write_type: WriteType,
+    ) -> Result<(), BleError> {
+        self.inner.with_connected(id, |device| {
+            let key = char_key(characteristic);
+            if !device.has_characteristic(&key) {
+                return Err(not_found(characteristic));
+            }
+            device.values.insert(key, data.to_vec());
+            device.writes.push((characteristic.uuid, data.to_vec()));
+
+            let responses = device.on_write.get(&key).cloned().unwrap_or_default();
+            for (when, target, value) in responses {
+                if when.as_deref().is_none_or(|w| w == data) {
+                    device.push(target, value);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    async fn subscribe(&self, id: &str, characteristic: &Characteristic) -> Result<(), BleError> {
+        let key = char_key(characteristic);
+        let inner = self.inner.clone();
+        let id_owned = id.to_string();
+        self.inner.with_connected(id, |device| {
+            if device.failing_subscribes > 0 {
+                device.failing_subscribes -= 1;
+                return Err(BleError::Backend(format!("订阅 {} 失败（模拟）", key.1)));
+            }
+            device.subscribed.insert(key);
+
+            // 重复订阅时沿用已在运行的周期任务，推送频率不变
+            let Some(&ms) = device.notify_intervals.get(&key) else {
+                return Ok(());
+            };
+            if device.notifiers.get(&key).is_some_and(|t| !t.is_finished()) {
+                return Ok(());
+            }
+            let task = tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(std::time::Duration::from_millis(ms));
+                loop {
+                    ticker.tick().await;
+                    // 暂时没有接收端时只是丢弃这一次推送，取消订阅或断开后才停止
+                    let active = inner.with_device(&id_owned, |device| {
+                        if !device.connected || !device.subscribed.contains(&key) {
+                            return Ok(false);
+                        }
+                        let value = device.values.get(&key).cloned().unwrap_or_default();
+                        device.push(key, value);
+                        Ok(true)
+                    });
+                    if !matches!(active, Ok(true)) {
+                        break;
+                    }
+                }
+            });
+            device.notifiers.insert(key, task);
+            Ok(())
+        })
+    }
+
+    async fn unsubscribe(&self, id: &str, characteristic: &Characteristic) -> Result<(), BleError> {
+        self.inner.with_connected(id, |device| {
+            let key = char_key(characteristic);
+            device.subscribed.remove(&key);
+            if let Some(notifier) = device.notifiers.remove(&key) {
+                notifier.abort();
+            }
+            Ok(())
+        })
+    }
+
+    async fn notifications(&self, id: &str) -> Result<NotificationStream, BleError> {
+        let rx = self
+            .inner
+            .with_device(id, |device| Ok(device.notify_tx.subscribe()))?;
+        Ok(broadcast_stream(rx))
+    }
+}
+
+/// 场景文件中的十六进制字符串
+fn hex(text: &str) -> Result<Vec<u8>, BleError> {
+    from_hex(text).map_err(BleError::InvalidInput)
+}
+
+fn not_found(characteristic: &Characteristic) -> BleError {
+    BleError::CharacteristicNotFound {
+        service: characteristic.service_uuid.to_string(),
+        characteristic: characteristic.uuid.to_string(),
+    }
+}
+
+/// 将 broadcast 接收端包装为 Stream，落后的消息直接跳过
+fn broadcast_stream<T: Clone + Send + 'static>(
+    rx: broadcast::Receiver<T>,
+) -> futures::stream::BoxStream<'static, T> {
+    stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(item) => return Some((item, rx)),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+    .boxed()
+}
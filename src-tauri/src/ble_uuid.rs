@@ -0,0 +1,107 @@
+use uuid::Uuid;
+
+/// 蓝牙SIG基础UUID（0000xxxx-0000-1000-8000-00805F9B34FB）的低96位
+const SIG_BASE_SUFFIX: u128 = 0x0000_0000_0000_1000_8000_0080_5F9B_34FB;
+const SIG_BASE_MASK: u128 = 0x0000_0000_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF;
+
+/// 常用的SIG服务、特征、描述符名称（Assigned Numbers）
+const SIG_NAMES: &[(u16, &str)] = &[
+    // 服务
+    (0x1800, "Generic Access"),
+    (0x1801, "Generic Attribute"),
+    (0x1802, "Immediate Alert"),
+    (0x1803, "Link Loss"),
+    (0x1804, "Tx Power"),
+    (0x1805, "Current Time"),
+    (0x1806, "Reference Time Update"),
+    (0x1807, "Next DST Change"),
+    (0x1808, "Glucose"),
+    (0x1809, "Health Thermometer"),
+    (0x180A, "Device Information"),
+    (0x180D, "Heart Rate"),
+    (0x180E, "Phone Alert Status"),
+    (0x180F, "Battery"),
+    (0x1810, "Blood Pressure"),
+    (0x1811, "Alert Notification"),
+    (0x1812, "Human Interface Device"),
+    (0x1813, "Scan Parameters"),
+    (0x1814, "Running Speed and Cadence"),
+    (0x1816, "Cycling Speed and Cadence"),
+    (0x1818, "Cycling Power"),
+    (0x1819, "Location and Navigation"),
+    (0x181A, "Environmental Sensing"),
+    (0x181C, "User Data"),
+    (0x181D, "Weight Scale"),
+    (0x1822, "Pulse Oximeter"),
+    (0x1826, "Fitness Machine"),
+    (0xFE59, "Nordic Secure DFU"),
+    (0xFEAA, "Eddystone"),
+    // 特征
+    (0x2A00, "Device Name"),
+    (0x2A01, "Appearance"),
+    (0x2A02, "Peripheral Privacy Flag"),
+    (0x2A03, "Reconnection Address"),
+    (0x2A04, "Peripheral Preferred Connection Parameters"),
+    (0x2A05, "Service Changed"),
+    (0x2A06, "Alert Level"),
+    (0x2A07, "Tx Power Level"),
+    (0x2A08, "Date Time"),
+    (0x2A19, "Battery Level"),
+    (0x2A1C, "Temperature Measurement"),
+    (0x2A1D, "Temperature Type"),
+    (0x2A1E, "Intermediate Temperature"),
+    (0x2A23, "System ID"),
+    (0x2A24, "Model Number String"),
+    (0x2A25, "Serial Number String"),
+    (0x2A26, "Firmware Revision String"),
+    (0x2A27, "Hardware Revision String"),
+    (0x2A28, "Software Revision String"),
+    (0x2A29, "Manufacturer Name String"),
+    (0x2A2A, "IEEE 11073-20601 Regulatory Certification Data List"),
+    (0x2A2B, "Current Time"),
+    (0x2A35, "Blood Pressure Measurement"),
+    (0x2A37, "Heart Rate Measurement"),
+    (0x2A38, "Body Sensor Location"),
+    (0x2A39, "Heart Rate Control Point"),
+    (0x2A49, "Blood Pressure Feature"),
+    (0x2A4D, "Report"),
+    (0x2A50, "PnP ID"),
+    (0x2A53, "RSC Measurement"),
+    (0x2A5B, "CSC Measurement"),
+    (0x2A5C, "CSC Feature"),
+    (0x2A5D, "Sensor Location"),
+    (0x2A63, "Cycling Power Measurement"),
+    (0x2A6D, "Pressure"),
+    (0x2A6E, "Temperature"),
+    (0x2A6F, "Humidity"),
+    (0x2A9D, "Weight Measurement"),
+    (0x2AA6, "Central Address Resolution"),
+    (0x2AC9, "Resolvable Private Address Only"),
+    // 描述符
+    (0x2900, "Characteristic Extended Properties"),
+    (0x2901, "Characteristic User Description"),
+    (0x2902, "Client Characteristic Configuration"),
+    (0x2903, "Server Characteristic Configuration"),
+    (0x2904, "Characteristic Presentation Format"),
+    (0x2905, "Characteristic Aggregate Format"),
+    (0x2906, "Valid Range"),
+    (0x2908, "Report Reference"),
+];
+
+/// 若UUID基于SIG基础UUID，返回其16位短码
+pub fn short_uuid(uuid: &Uuid) -> Option<u16> {
+    let value = uuid.as_u128();
+    if value & SIG_BASE_MASK != SIG_BASE_SUFFIX {
+        return None;
+    }
+    u16::try_from(value >> 96).ok()
+}
+
+/// 查询SIG标准UUID对应的可读名称，未收录或自定义UUID返回 `None`
+pub fn sig_name(uuid: &Uuid) -> Option<&'static str> {
+    let short = short_uuid(uuid)?;
+    SIG_NAMES
+        .iter()
+        .find(|(value, _)| *value == short)
+        .map(|(_, name)| *name)
+}
@@ -0,0 +1,27 @@
+use serde::ser::{SerializeStruct, Serializer};
+use serde_json::Value;
+
+/// 命令错误返回给前端的统一结构：`{ code, message, details }`
+///
+/// `code` 是稳定的错误码，前端据此区分错误类型；`message` 用于展示，
+/// `details` 携带设备ID、文件路径等结构化信息（没有时为 null）。
+pub trait ErrorCode: std::fmt::Display {
+    fn code(&self) -> &'static str;
+
+    fn details(&self) -> Option<Value> {
+        None
+    }
+}
+
+/// 供错误枚举的 `Serialize` 实现调用
+pub fn serialize_error<E, S>(error: &E, serializer: S) -> Result<S::Ok, S::Error>
+where
+    E: ErrorCode,
+    S: Serializer,
+{
+    let mut state = serializer.serialize_struct("Error", 3)?;
+    state.serialize_field("code", error.code())?;
+    state.serialize_field("message", &error.to_string())?;
+    state.serialize_field("details", &error.details())?;
+    state.end()
+}
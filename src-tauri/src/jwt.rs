@@ -1,75 +1,402 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::{encode, Algorithm, Header};
 use serde::{Deserialize, Serialize};
-use thiserror::Error;
-use serde_json::Value;
+use serde_json::{Map, Value};
+use tauri::{command, AppHandle};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::jwt_error::JwtError;
+use crate::jwt_inspect::{inspect_any, TokenInspection};
+use crate::jwt_jwe::{decrypt_jwe, encrypt_jwe, DecryptedJwe};
+use crate::jwt_jws::{
+    parse_jws, sign_jws, verify_jws, JwsDocument, JwsSignOptions, JwsSigner, VerifiedJws,
+};
+use crate::jwt_keygen::{generate_keypair, JwtKeyPair};
+use crate::jwt_keys::signing_key;
+use crate::jwt_keystore::{store_path, ImportSummary, KeyStore};
+use crate::jwt_verify::{verify_with_key, ClaimChecks, VerifiedToken};
+
+/// 令牌声明的宽松视图：RFC 7519 注册声明单独成字段，其余声明保留在 `extra` 中
+///
+/// 注册声明保持原始 JSON 值，不限制类型（iss 可以是数组、sub 可以是数字、exp 可以是小数），
+/// 签名和验证始终使用调用方的原始载荷，本结构只用于旧接口的返回值。
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct Claims {
-    pub sub: String,    // 主题 （用户ID）
-    pub exp: usize,     // 过期时间
-    pub iat: usize,     // 签发时间
-    #[serde(skip_serializing_if = "Option::is_none")] 
-    pub custom: Option<serde_json::Value>, // 自定义数据
-} 
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Claims {
+    /// 以当前时间为 iat、`lifetime` 后为 exp
+    pub fn new(subject: String, lifetime: Duration) -> Self {
+        let now = Utc::now();
+        Claims {
+            sub: Some(Value::from(subject)),
+            iat: Some(Value::from(now.timestamp())),
+            exp: Some(Value::from((now + lifetime).timestamp())),
+            ..Claims::default()
+        }
+    }
+
+    /// 载荷必须是 JSON 对象，各声明的类型不做检查
+    pub fn from_value(value: Value) -> Result<Self, JwtError> {
+        if !value.is_object() {
+            return Err(JwtError::Malformed("载荷不是JSON对象".to_string()));
+        }
+        serde_json::from_value(value)
+            .map_err(|e| JwtError::Malformed(format!("声明格式错误: {}", e)))
+    }
 
-#[derive(Debug, Error)]
-pub enum JwtError {
-    #[error("Token generation failed: {0}")]
-    GenerationFailed(String),
-    #[error("Token validation failed: {0}")]
-    ValidationFailed(String),
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
 }
 
+fn parse_payload(payload: &str) -> Result<Value, JwtError> {
+    serde_json::from_str(payload)
+        .map_err(|e| JwtError::Malformed(format!("载荷JSON格式错误: {}", e)))
+}
 
-pub fn generate_jwt(
-    secret: &str,
-    subject: &str,
-    expires_in_hours: i64,
-    custom_data: Option<serde_json::Value>,
+/// 按标头中的 alg 解析签名密钥并签名，密钥格式见 [`signing_key`]；载荷按原样序列化
+pub fn sign_token<T: Serialize>(
+    header: &Header,
+    claims: &T,
+    key: &str,
 ) -> Result<String, JwtError> {
-    let now = Utc::now();
-    let iat = now.timestamp() as usize;
-    let exp = (now + Duration::hours(expires_in_hours)).timestamp() as usize;
-
-    let claims = Claims {
-        sub: subject.to_string(),
-        exp,
-        iat,
-        custom: custom_data,
-    };
+    let key = signing_key(key, header.alg)?;
+    Ok(encode(header, claims, &key)?)
+}
 
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )
-    .map_err(|e| JwtError::GenerationFailed(e.to_string()))
+/// 只解码不验证，与 `inspect_jwt_token` 相同；需要验证时使用 `verify_jwt_token`
+#[command]
+pub fn decode_jwt_token(token: String) -> Result<TokenInspection, JwtError> {
+    inspect_any(&token, None)
+}
+
+/// 解码并分析令牌，不校验签名；提供 `secret` 时额外检查 HMAC 密钥强度
+///
+/// JWE 令牌只解析标头和各段长度，解密使用 `decrypt_jwe_token`。
+#[command]
+pub fn inspect_jwt_token(
+    token: String,
+    secret: Option<String>,
+) -> Result<TokenInspection, JwtError> {
+    inspect_any(&token, secret.as_deref())
 }
 
+/// 解密 JWE 令牌；明文是签名令牌时一并给出未验证的分析结果
+///
+/// dir、A128KW、A256KW 的 `key` 为对称密钥，RSA-OAEP 和 ECDH-ES 为私钥。
+#[command]
+pub fn decrypt_jwe_token(token: String, key: String) -> Result<DecryptedJwe, JwtError> {
+    decrypt_jwe(&token, &key)
+}
 
-pub fn validate_jwt(secret: &str, token: &str) -> Result<Claims, JwtError> {
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::new(Algorithm::HS256),
+/// 按标头中的 alg 和 enc 加密载荷，`payload` 原样作为明文（可以是 JSON 或已签名的令牌）
+#[command]
+pub fn encrypt_jwe_token(header: String, payload: String, key: String) -> Result<String, JwtError> {
+    let header: Value = serde_json::from_str(&header)
+        .map_err(|e| JwtError::Malformed(format!("标头JSON格式错误: {}", e)))?;
+    encrypt_jwe(&header, payload.as_bytes(), &key)
+}
+
+/// 解析 compact、Flattened 或 General JSON 格式的 JWS，不验证签名
+///
+/// 分离载荷的 JWS 可通过 `payload` 提供原始载荷。
+#[command]
+pub fn inspect_jws_token(input: String, payload: Option<String>) -> Result<JwsDocument, JwtError> {
+    parse_jws(&input, payload.as_deref())
+}
+
+/// 验证 JWS 的签名，至少一个签名通过即成功，每个签名的结果在 `signatures` 中
+///
+/// 分离载荷（包括 RFC 7797 未编码载荷）需通过 `payload` 提供原始内容。
+#[command]
+pub fn verify_jws_token(
+    input: String,
+    key: String,
+    payload: Option<String>,
+    algorithms: Option<Vec<Algorithm>>,
+) -> Result<VerifiedJws, JwtError> {
+    verify_jws(&input, payload.as_deref(), &key, algorithms.as_deref())
+}
+
+/// 用一个或多个签名者签名，输出格式、分离载荷和 b64 由 `options` 决定（默认 compact）
+#[command]
+pub fn sign_jws_token(
+    payload: String,
+    signers: Vec<JwsSigner>,
+    options: Option<JwsSignOptions>,
+) -> Result<String, JwtError> {
+    sign_jws(&payload, &signers, &options.unwrap_or_default())
+}
+
+/// 用调用方提供的密钥验证令牌，失败时错误的 `code` 指明未通过的检查
+#[command]
+pub fn verify_jwt_token(
+    token: String,
+    key: String,
+    algorithms: Option<Vec<Algorithm>>,
+    checks: Option<ClaimChecks>,
+) -> Result<VerifiedToken, JwtError> {
+    verify_with_key(
+        &token,
+        &key,
+        algorithms.as_deref(),
+        &checks.unwrap_or_default(),
     )
-    .map(|data| data.claims)
-    .map_err(|e| JwtError::ValidationFailed(e.to_string()))
 }
 
-#[tauri::command]
+/// 按标头中的算法签名，支持 HS*、RS*、PS*、ES256/ES384 和 EdDSA
+///
+/// HMAC 算法的 `secret` 为共享密钥，其余算法为 PEM 或 Base64 DER 格式的私钥。
+#[command]
+pub fn encode_jwt_token(
+    header: String,
+    payload: String,
+    secret: String,
+) -> Result<String, JwtError> {
+    let header: Header = serde_json::from_str(&header)
+        .map_err(|e| JwtError::Malformed(format!("标头JSON格式错误: {}", e)))?;
+    sign_token(&header, &parse_payload(&payload)?, &secret)
+}
+
+/// 验证令牌签名和注册声明
+///
+/// `secret` 可以是 HMAC 共享密钥，也可以是 PEM/DER 公钥、X.509 证书、JWK 或 JWKS；
+/// 为空时按令牌的 kid 和 iss 从本地密钥库中选择密钥。
+/// 算法取自令牌标头，并需在 `algorithms` 白名单内（未提供时按密钥类型决定）。
+/// `issuer`、`audience` 会并入 `checks` 的允许列表。
+#[command]
+pub fn validate_jwt_token(
+    app: AppHandle,
+    token: String,
+    secret: String,
+    issuer: Option<String>,
+    audience: Option<String>,
+    algorithms: Option<Vec<Algorithm>>,
+    checks: Option<ClaimChecks>,
+) -> Result<VerifiedToken, JwtError> {
+    let mut checks = checks.unwrap_or_default();
+    checks.issuer.extend(issuer);
+    checks.audience.extend(audience);
+
+    let secret = match secret.trim() {
+        "" => KeyStore::load(&store_path(&app)?)?.select(&token)?,
+        _ => secret,
+    };
+    verify_with_key(&token, &secret, algorithms.as_deref(), &checks)
+}
+
+/// 从文件导入 JWKS、单个 JWK 或 OpenID 发现文档到本地密钥库
+///
+/// `issuer` 指定密钥所属的签发者，发现文档中的 issuer 会自动使用。
+#[command]
+pub fn import_jwt_keys(
+    app: AppHandle,
+    path: String,
+    issuer: Option<String>,
+) -> Result<ImportSummary, JwtError> {
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| JwtError::KeyStore(format!("读取 {} 失败: {}", path, e)))?;
+    let store_path = store_path(&app)?;
+    let mut store = KeyStore::load(&store_path)?;
+    let summary = store.import(&text, &path, issuer.as_deref())?;
+    store.save(&store_path)?;
+    println!(
+        "Imported {} JWT keys from {} ({} replaced, {} skipped)",
+        summary.imported, path, summary.replaced, summary.skipped
+    );
+    Ok(summary)
+}
+
+/// 读取本地密钥库，包括验证密钥和通过发现文档登记的签发者
+#[command]
+pub fn list_jwt_keys(app: AppHandle) -> Result<KeyStore, JwtError> {
+    KeyStore::load(&store_path(&app)?)
+}
+
+/// 从本地密钥库删除密钥，返回是否有密钥被删除
+#[command]
+pub fn remove_jwt_key(
+    app: AppHandle,
+    kid: String,
+    issuer: Option<String>,
+) -> Result<bool, JwtError> {
+    let store_path = store_path(&app)?;
+    let mut store = KeyStore::load(&store_path)?;
+    let removed = store.remove(&kid, issuer.as_deref());
+    if removed {
+        store.save(&store_path)?;
+    }
+    Ok(removed)
+}
+
+/// 生成签名用的密钥对，RSA 可指定 2048、3072 或 4096 位（默认 2048）
+#[command]
+pub async fn generate_jwt_keypair(
+    alg: Algorithm,
+    bits: Option<usize>,
+) -> Result<JwtKeyPair, JwtError> {
+    // RSA 密钥生成较慢，放到阻塞线程池中执行
+    tokio::task::spawn_blocking(move || generate_keypair(alg, bits))
+        .await
+        .map_err(|e| JwtError::KeyGeneration(e.to_string()))?
+}
+
+/// 与 `inspect_jwt_token` 相同，不检查密钥强度
+#[command]
+pub fn decode_jwt_token_generic(token: String) -> Result<TokenInspection, JwtError> {
+    inspect_any(&token, None)
+}
+
+/// 生成演示用的令牌，签名段只是对输入的编码，不能通过验证
+#[command]
+pub fn create_demo_jwt_token(
+    header: String,
+    payload: String,
+    signature: String,
+) -> Result<String, JwtError> {
+    let header: Value = serde_json::from_str(&header)
+        .map_err(|e| JwtError::Malformed(format!("标头JSON格式错误: {}", e)))?;
+    let payload = parse_payload(&payload)?;
+
+    let encoded_header = URL_SAFE_NO_PAD.encode(header.to_string());
+    let encoded_payload = URL_SAFE_NO_PAD.encode(payload.to_string());
+    let signing_input = format!("{}.{}", encoded_header, encoded_payload);
+    let demo_signature = URL_SAFE_NO_PAD.encode(format!("{}{}", signature, signing_input));
+    Ok(format!("{}.{}", signing_input, demo_signature))
+}
+
+/// 固定使用 HS256 签名；标头无效时使用默认标头，其中的 alg 总是被替换为 HS256
+#[command]
+pub fn encode_hs256_token(
+    header: String,
+    payload: String,
+    secret: String,
+) -> Result<String, JwtError> {
+    let mut header: Header = serde_json::from_str(&header).unwrap_or_default();
+    header.alg = Algorithm::HS256;
+    sign_token(&header, &parse_payload(&payload)?, &secret)
+}
+
+/// 旧接口：用 HMAC 密钥签发包含 sub、iat、exp 的令牌，`custom_data` 放在 custom 声明中
+#[command]
 pub async fn encode_jwt(
     secret: String,
     subject: String,
     expires_in_hours: i64,
     custom_data: Option<Value>,
-) -> Result<String, String> {
-    generate_jwt(&secret, &subject, expires_in_hours, custom_data)
-        .map_err(|e| e.to_string())
+) -> Result<String, JwtError> {
+    let mut claims = Claims::new(subject, Duration::hours(expires_in_hours));
+    claims
+        .extra
+        .extend(custom_data.map(|custom| ("custom".to_string(), custom)));
+    sign_token(&Header::default(), &claims, &secret)
+}
+
+/// 旧接口：用 HMAC 密钥验证 HS256 令牌，要求包含 sub、iat、exp
+#[command]
+pub async fn decode_jwt(secret: String, token: String) -> Result<Claims, JwtError> {
+    let checks = ClaimChecks {
+        required: ["sub", "iat", "exp"].map(str::to_string).to_vec(),
+        ..ClaimChecks::default()
+    };
+    let verified = verify_with_key(&token, &secret, Some(&[Algorithm::HS256]), &checks)?;
+    Claims::from_value(verified.claims)
 }
 
-#[tauri::command]
-pub async fn decode_jwt(secret: String, token: String) -> Result<Claims, String> {
-    validate_jwt(&secret, &token).map_err(|e| e.to_string())
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn claims_keep_registered_and_custom_members() {
+        let claims = Claims::from_value(
+            json!({ "sub": "u", "aud": ["a", "b"], "exp": 1700000000.5, "roles": ["admin"] }),
+        )
+        .unwrap();
+        assert_eq!(claims.sub, Some(json!("u")));
+        assert_eq!(claims.exp, Some(json!(1700000000.5)));
+        assert_eq!(claims.extra["roles"], json!(["admin"]));
+        assert_eq!(claims.to_value()["aud"], json!(["a", "b"]));
+        assert!(Claims::from_value(json!([1])).is_err());
+    }
+
+    #[test]
+    fn encoders_sign_the_payload_as_given() {
+        // 多个签发者、数字 sub 和小数时间都按原样签名，不做类型检查或截断
+        let payload = json!({ "iss": ["a", "b"], "sub": 123, "exp": 4102444800.75 });
+        let header = r#"{"alg":"HS256","typ":"JWT"}"#;
+        let tokens = [
+            encode_jwt_token(header.into(), payload.to_string(), "secret".into()).unwrap(),
+            encode_hs256_token(header.into(), payload.to_string(), "secret".into()).unwrap(),
+            create_demo_jwt_token(header.into(), payload.to_string(), "sig".into()).unwrap(),
+        ];
+        for token in &tokens {
+            let inspection = crate::jwt_inspect::inspect_token(token, None).unwrap();
+            assert_eq!(inspection.payload, payload);
+        }
+
+        // 小数时间在验证时才被拒绝，其余声明验证后同样保留原始类型
+        let checks = ClaimChecks::default();
+        assert!(matches!(
+            verify_with_key(&tokens[0], "secret", None, &checks),
+            Err(JwtError::Malformed(_))
+        ));
+        let payload = json!({ "iss": ["a", "b"], "sub": 123 });
+        let token = encode_jwt_token(header.into(), payload.to_string(), "secret".into()).unwrap();
+        let verified = verify_with_key(&token, "secret", None, &checks).unwrap();
+        assert_eq!(verified.claims, payload);
+        assert!(encode_jwt_token(header.into(), "{".into(), "secret".into()).is_err());
+    }
+
+    #[tokio::test]
+    async fn legacy_commands_round_trip() {
+        let token = encode_jwt(
+            "secret".to_string(),
+            "user-1".to_string(),
+            1,
+            Some(json!({ "role": "admin" })),
+        )
+        .await
+        .unwrap();
+        let claims = decode_jwt("secret".to_string(), token.clone())
+            .await
+            .unwrap();
+        assert_eq!(claims.sub, Some(json!("user-1")));
+        assert_eq!(claims.extra["custom"]["role"], "admin");
+        assert_eq!(
+            decode_jwt("other".to_string(), token).await.unwrap_err(),
+            JwtError::InvalidSignature
+        );
+
+        // 旧接口要求 iat 和 exp；encode_hs256_token 保留标头的其他字段，只替换 alg
+        let header = r#"{"alg":"HS512","typ":"JWT","kid":"k"}"#.to_string();
+        let token =
+            encode_hs256_token(header, r#"{"sub":"x"}"#.to_string(), "secret".to_string()).unwrap();
+        let inspection = crate::jwt_inspect::inspect_token(&token, None).unwrap();
+        assert_eq!(inspection.header["alg"], "HS256");
+        assert_eq!(inspection.header["kid"], "k");
+        assert_eq!(
+            decode_jwt("secret".to_string(), token).await.unwrap_err(),
+            JwtError::MissingClaim("iat".to_string())
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use jsonwebtoken::errors::ErrorKind;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::error::{serialize_error, ErrorCode};
+
+/// JWT 子系统（签名、验证、加解密、密钥管理）共用的错误类型，`code` 指明具体原因
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum JwtError {
+    #[error("JWT格式错误: {0}")]
+    Malformed(String),
+    #[error("签名无效")]
+    InvalidSignature,
+    #[error("密钥无效: {0}")]
+    InvalidKey(String),
+    #[error("算法 {0} 不在允许列表中")]
+    AlgorithmNotAllowed(String),
+    /// 标头中的算法与密钥类型不符，例如用 RSA 公钥验证 HS256 令牌
+    #[error("{key} 密钥不能用于 {alg} 算法")]
+    KeyMismatch { alg: String, key: String },
+    #[error("找不到 kid 为 {0} 的密钥")]
+    KeyNotFound(String),
+    #[error("生成密钥失败: {0}")]
+    KeyGeneration(String),
+    #[error("不支持的算法: {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("解密失败: {0}")]
+    DecryptionFailed(String),
+    #[error("密钥库错误: {0}")]
+    KeyStore(String),
+    #[error("令牌已过期")]
+    Expired,
+    #[error("令牌尚未生效")]
+    NotYetValid,
+    #[error("签发时间晚于当前时间")]
+    IssuedInFuture,
+    #[error("签发者不匹配")]
+    InvalidIssuer,
+    #[error("受众不匹配")]
+    InvalidAudience,
+    #[error("主题不匹配")]
+    InvalidSubject,
+    #[error("缺少声明: {0}")]
+    MissingClaim(String),
+}
+
+impl ErrorCode for JwtError {
+    fn code(&self) -> &'static str {
+        match self {
+            JwtError::Malformed(_) => "malformed_token",
+            JwtError::InvalidSignature => "invalid_signature",
+            JwtError::InvalidKey(_) => "invalid_key",
+            JwtError::AlgorithmNotAllowed(_) => "algorithm_not_allowed",
+            JwtError::KeyMismatch { .. } => "key_mismatch",
+            JwtError::KeyNotFound(_) => "key_not_found",
+            JwtError::KeyGeneration(_) => "key_generation_failed",
+            JwtError::UnsupportedAlgorithm(_) => "unsupported_algorithm",
+            JwtError::DecryptionFailed(_) => "decryption_failed",
+            JwtError::KeyStore(_) => "key_store_error",
+            JwtError::Expired => "expired",
+            JwtError::NotYetValid => "not_yet_valid",
+            JwtError::IssuedInFuture => "issued_in_future",
+            JwtError::InvalidIssuer => "invalid_issuer",
+            JwtError::InvalidAudience => "invalid_audience",
+            JwtError::InvalidSubject => "invalid_subject",
+            JwtError::MissingClaim(_) => "missing_claim",
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        let claim = match self {
+            JwtError::Expired => "exp",
+            JwtError::NotYetValid => "nbf",
+            JwtError::IssuedInFuture => "iat",
+            JwtError::InvalidIssuer => "iss",
+            JwtError::InvalidAudience => "aud",
+            JwtError::InvalidSubject => "sub",
+            JwtError::MissingClaim(name) => name,
+            JwtError::AlgorithmNotAllowed(alg) | JwtError::UnsupportedAlgorithm(alg) => {
+                return Some(json!({ "alg": alg }))
+            }
+            JwtError::KeyMismatch { alg, key } => return Some(json!({ "alg": alg, "key": key })),
+            JwtError::KeyNotFound(kid) => return Some(json!({ "kid": kid })),
+            _ => return None,
+        };
+        Some(json!({ "claim": claim }))
+    }
+}
+
+impl Serialize for JwtError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_error(self, serializer)
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for JwtError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        match e.kind() {
+            ErrorKind::InvalidSignature | ErrorKind::Crypto(_) => JwtError::InvalidSignature,
+            ErrorKind::InvalidEcdsaKey
+            | ErrorKind::InvalidRsaKey(_)
+            | ErrorKind::InvalidKeyFormat => JwtError::InvalidKey(e.to_string()),
+            ErrorKind::InvalidAlgorithm | ErrorKind::MissingAlgorithm => {
+                JwtError::AlgorithmNotAllowed(e.to_string())
+            }
+            ErrorKind::ExpiredSignature => JwtError::Expired,
+            ErrorKind::ImmatureSignature => JwtError::NotYetValid,
+            ErrorKind::InvalidIssuer => JwtError::InvalidIssuer,
+            ErrorKind::InvalidAudience => JwtError::InvalidAudience,
+            ErrorKind::InvalidSubject => JwtError::InvalidSubject,
+            ErrorKind::MissingRequiredClaim(claim) => JwtError::MissingClaim(claim.clone()),
+            _ => JwtError::Malformed(e.to_string()),
+        }
+    }
+}
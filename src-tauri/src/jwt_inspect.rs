@@ -0,0 +1,398 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::jwt_error::JwtError;
+use crate::jwt_jwe::{inspect_jwe, JweInspection};
+
+/// 有效期超过该值（秒）时提示有效期过长
+const LONG_LIFETIME_SECS: i64 = 30 * 24 * 3600;
+
+/// 常见声明的说明，来自 RFC 7519 和 OpenID Connect Core
+const KNOWN_CLAIMS: [(&str, &str); 18] = [
+    ("iss", "签发者"),
+    ("sub", "主题（通常是用户ID）"),
+    ("aud", "受众，令牌的预期接收方"),
+    ("exp", "过期时间"),
+    ("nbf", "生效时间，在此之前令牌无效"),
+    ("iat", "签发时间"),
+    ("jti", "令牌唯一ID，可用于防重放"),
+    ("azp", "授权方，令牌签发给的客户端"),
+    ("nonce", "客户端提供的随机值，用于防重放"),
+    ("auth_time", "用户完成认证的时间"),
+    ("sid", "会话ID"),
+    ("scope", "授权范围"),
+    ("client_id", "客户端ID"),
+    ("name", "用户全名"),
+    ("preferred_username", "用户名"),
+    ("email", "邮箱"),
+    ("email_verified", "邮箱是否已验证"),
+    ("roles", "角色"),
+];
+
+/// 令牌的原始三段
+#[derive(Serialize, Clone, Debug)]
+pub struct RawSegments {
+    pub header: String,
+    pub payload: String,
+    pub signature: String,
+}
+
+/// 时间类声明的可读形式
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ClaimTime {
+    pub timestamp: i64,
+    pub utc: String,
+    pub local: String,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// 检查到的问题，`code` 供前端识别
+#[derive(Serialize, Clone, Debug)]
+pub struct InspectionIssue {
+    pub code: &'static str,
+    pub severity: IssueSeverity,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ClaimInfo {
+    pub name: String,
+    pub value: Value,
+    pub description: Option<&'static str>, // 不是常见声明时为空
+}
+
+/// 未经验证的令牌分析结果，仅用于查看，不能作为可信数据使用
+#[derive(Serialize, Clone, Debug)]
+pub struct JwtInspection {
+    pub header: Value,
+    pub payload: Value, // 载荷不是 JSON 时为原始文本
+    pub segments: RawSegments,
+    pub signature_len: usize, // 签名字节数
+    pub algorithm: Option<String>,
+    pub issued_at: Option<ClaimTime>,
+    pub expires_at: Option<ClaimTime>,
+    pub not_before: Option<ClaimTime>,
+    pub expires_in: Option<i64>, // 距过期的秒数，已过期时为负数
+    pub expires_in_text: Option<String>,
+    pub issues: Vec<InspectionIssue>,
+    pub claims: Vec<ClaimInfo>,
+    pub verified: bool, // 始终为 false，提醒调用方内容未验证
+}
+
+/// 签名令牌（JWS，三段）或加密令牌（JWE，五段）的分析结果，`kind` 区分两者
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TokenInspection {
+    Jws(Box<JwtInspection>),
+    Jwe(JweInspection),
+}
+
+/// 按段数分派：五段为 JWE，只解析标头；其余按 JWS 分析
+pub fn inspect_any(token: &str, secret: Option<&str>) -> Result<TokenInspection, JwtError> {
+    match token.trim().split('.').count() {
+        5 => inspect_jwe(token).map(TokenInspection::Jwe),
+        _ => inspect_token(token, secret)
+            .map(|inspection| TokenInspection::Jws(Box::new(inspection))),
+    }
+}
+
+/// 解码令牌的三个段并分析声明，不校验签名
+///
+/// 提供 `secret` 时检查 HMAC 密钥长度是否达到算法的要求（RFC 7518 第 3.2 节）。
+pub fn inspect_token(token: &str, secret: Option<&str>) -> Result<JwtInspection, JwtError> {
+    let parts: Vec<&str> = token.trim().split('.').collect();
+    let [header_segment, payload_segment, signature_segment] = parts.as_slice() else {
+        return Err(JwtError::Malformed(format!(
+            "JWT应由3段组成，实际为{}段",
+            parts.len()
+        )));
+    };
+
+    let header = decode_segment("标头", header_segment)?;
+    if !header.is_object() {
+        return Err(JwtError::Malformed("标头不是JSON对象".to_string()));
+    }
+    let payload = decode_segment("载荷", payload_segment)?;
+    let signature_len = URL_SAFE_NO_PAD
+        .decode(signature_segment)
+        .map_err(|e| JwtError::Malformed(format!("签名Base64解码失败: {}", e)))?
+        .len();
+
+    let now = Utc::now().timestamp();
+    let algorithm = header
+        .get("alg")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    // 时间声明可能是任意数值，超出 i64 的部分饱和处理，下面的差值都用饱和运算
+    let timestamp = |name: &str| payload.get(name).and_then(Value::as_f64).map(|t| t as i64);
+    let (iat, exp, nbf) = (timestamp("iat"), timestamp("exp"), timestamp("nbf"));
+
+    let mut issues = Vec::new();
+    let mut issue = |code, severity, message: String| {
+        issues.push(InspectionIssue {
+            code,
+            severity,
+            message,
+        })
+    };
+    match algorithm.as_deref() {
+        Some(alg) if alg.eq_ignore_ascii_case("none") => issue(
+            "alg_none",
+            IssueSeverity::Error,
+            "算法为 none，令牌没有签名，任何人都可以伪造".to_string(),
+        ),
+        None => issue(
+            "missing_alg",
+            IssueSeverity::Error,
+            "标头缺少 alg".to_string(),
+        ),
+        _ => {}
+    }
+    match exp {
+        None => issue(
+            "missing_exp",
+            IssueSeverity::Warning,
+            "缺少 exp，令牌永不过期".to_string(),
+        ),
+        Some(exp) if exp <= now => issue(
+            "expired",
+            IssueSeverity::Warning,
+            format!("令牌已于{}前过期", format_duration(now.saturating_sub(exp))),
+        ),
+        Some(_) => {}
+    }
+    if let Some(exp) = exp {
+        let lifetime = exp.saturating_sub(iat.unwrap_or(now));
+        if lifetime > LONG_LIFETIME_SECS {
+            issue(
+                "long_lifetime",
+                IssueSeverity::Warning,
+                format!("有效期长达{}", format_duration(lifetime)),
+            );
+        }
+    }
+    if let Some(nbf) = nbf.filter(|nbf| *nbf > now) {
+        issue(
+            "not_yet_valid",
+            IssueSeverity::Info,
+            format!("令牌将在{}后生效", format_duration(nbf.saturating_sub(now))),
+        );
+    }
+    if let (Some(secret), Some(required)) = (secret, algorithm.as_deref().and_then(hmac_key_len)) {
+        if secret.len() < required {
+            issue(
+                "weak_secret",
+                IssueSeverity::Warning,
+                format!(
+                    "HMAC 密钥只有 {} 字节，{} 要求至少 {} 字节",
+                    secret.len(),
+                    algorithm.as_deref().unwrap_or_default(),
+                    required
+                ),
+            );
+        }
+    }
+
+    let claims = payload
+        .as_object()
+        .map(|object| {
+            object
+                .iter()
+                .map(|(name, value)| ClaimInfo {
+                    name: name.clone(),
+                    value: value.clone(),
+                    description: KNOWN_CLAIMS
+                        .iter()
+                        .find(|(known, _)| known == name)
+                        .map(|(_, description)| *description),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(JwtInspection {
+        segments: RawSegments {
+            header: header_segment.to_string(),
+            payload: payload_segment.to_string(),
+            signature: signature_segment.to_string(),
+        },
+        header,
+        payload,
+        signature_len,
+        algorithm,
+        issued_at: iat.and_then(claim_time),
+        expires_at: exp.and_then(claim_time),
+        not_before: nbf.and_then(claim_time),
+        expires_in: exp.map(|exp| exp.saturating_sub(now)),
+        expires_in_text: exp.map(|exp| match exp.saturating_sub(now) {
+            left if left > 0 => format!("{}后过期", format_duration(left)),
+            left => format!("已过期{}", format_duration(left.saturating_neg())),
+        }),
+        issues,
+        claims,
+        verified: false,
+    })
+}
+
+/// HMAC 密钥的最小长度等于哈希输出长度
+fn hmac_key_len(alg: &str) -> Option<usize> {
+    match alg {
+        "HS256" => Some(32),
+        "HS384" => Some(48),
+        "HS512" => Some(64),
+        _ => None,
+    }
+}
+
+fn claim_time(timestamp: i64) -> Option<ClaimTime> {
+    let utc = DateTime::<Utc>::from_timestamp(timestamp, 0)?;
+    Some(ClaimTime {
+        timestamp,
+        utc: utc.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        local: utc
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S %:z")
+            .to_string(),
+    })
+}
+
+/// 秒数转为“1天2小时3分钟”的形式，只保留最大的两个单位
+fn format_duration(secs: i64) -> String {
+    let units = [(86_400, "天"), (3_600, "小时"), (60, "分钟"), (1, "秒")];
+    let mut rest = secs.max(0);
+    let mut parts = Vec::new();
+    for (size, name) in units {
+        if rest >= size {
+            parts.push(format!("{}{}", rest / size, name));
+            rest %= size;
+        }
+    }
+    if parts.is_empty() {
+        return "0秒".to_string();
+    }
+    parts.truncate(2);
+    parts.concat()
+}
+
+/// base64url 解码一段；是 JSON 时解析为对象，否则按 UTF-8 文本返回
+fn decode_segment(name: &str, segment: &str) -> Result<Value, JwtError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(segment)
+        .map_err(|e| JwtError::Malformed(format!("{}Base64解码失败: {}", name, e)))?;
+    match serde_json::from_slice(&bytes) {
+        Ok(value) => Ok(value),
+        Err(_) => String::from_utf8(bytes)
+            .map(Value::String)
+            .map_err(|_| JwtError::Malformed(format!("{}不是有效的UTF-8文本", name))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn token(header: Value, payload: Value) -> String {
+        format!(
+            "{}.{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(payload.to_string()),
+            URL_SAFE_NO_PAD.encode([1u8; 32])
+        )
+    }
+
+    fn codes(inspection: &JwtInspection) -> Vec<&'static str> {
+        inspection.issues.iter().map(|issue| issue.code).collect()
+    }
+
+    #[test]
+    fn decodes_without_verifying() {
+        let now = Utc::now().timestamp();
+        let jwt = token(
+            json!({ "alg": "HS256", "typ": "JWT" }),
+            json!({ "sub": "x", "iat": now, "exp": now + 3600, "tenant": "t" }),
+        );
+
+        let inspection = inspect_token(&jwt, None).unwrap();
+        assert_eq!(inspection.algorithm.as_deref(), Some("HS256"));
+        assert_eq!(inspection.payload["tenant"], "t");
+        assert_eq!(inspection.signature_len, 32);
+        assert_eq!(
+            inspection.expires_at.as_ref().unwrap().timestamp,
+            now + 3600
+        );
+        assert!(inspection.expires_in.unwrap() > 3500);
+        assert!(inspection.issues.is_empty());
+        assert!(!inspection.verified);
+
+        let sub = inspection.claims.iter().find(|c| c.name == "sub").unwrap();
+        assert!(sub.description.is_some());
+        let tenant = inspection
+            .claims
+            .iter()
+            .find(|c| c.name == "tenant")
+            .unwrap();
+        assert!(tenant.description.is_none());
+
+        assert!(inspect_token("a.b", None).is_err());
+        assert!(matches!(
+            inspect_any(&jwt, None),
+            Ok(TokenInspection::Jws(_))
+        ));
+    }
+
+    #[test]
+    fn detects_issues() {
+        let now = Utc::now().timestamp();
+        let unsigned = token(json!({ "alg": "none" }), json!({ "sub": "x" }));
+        assert_eq!(
+            codes(&inspect_token(&unsigned, None).unwrap()),
+            vec!["alg_none", "missing_exp"]
+        );
+
+        let expired = token(
+            json!({ "alg": "HS256" }),
+            json!({ "iat": now - 400 * 86_400, "exp": now - 86_400 }),
+        );
+        let inspection = inspect_token(&expired, Some("short")).unwrap();
+        assert_eq!(
+            codes(&inspection),
+            vec!["expired", "long_lifetime", "weak_secret"]
+        );
+        assert!(inspection
+            .expires_in_text
+            .is_some_and(|text| text.starts_with("已过期1天")));
+    }
+
+    #[test]
+    fn extreme_timestamps_do_not_overflow() {
+        for payload in [
+            json!({ "exp": -1e300 }),
+            json!({ "iat": -1e300, "exp": 1e300 }),
+            json!({ "exp": i64::MIN, "nbf": 1e300 }),
+        ] {
+            let jwt = token(json!({ "alg": "HS256" }), payload.clone());
+            let inspection = inspect_token(&jwt, None).unwrap();
+            assert!(inspection.expires_in.is_some(), "{}", payload);
+            assert!(inspection.expires_at.is_none(), "{}", payload);
+        }
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(0), "0秒");
+        assert_eq!(format_duration(59), "59秒");
+        assert_eq!(format_duration(3 * 86_400 + 5 * 3_600 + 7), "3天5小时");
+        assert_eq!(format_duration(3_660), "1小时1分钟");
+    }
+}
@@ -0,0 +1,821 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes128Gcm, Aes256Gcm, Nonce};
+use aes_kw::{KekAes128, KekAes256};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use cbc::cipher::block_padding::Pkcs7;
+use cbc::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::{BigUint, Oaep, RsaPrivateKey, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::jwt_error::JwtError;
+use crate::jwt_inspect::{inspect_token, JwtInspection};
+use crate::jwt_keys::KeyText;
+
+/// 认证标签的字节数：GCM 为完整标签，A128CBC-HS256 为 HMAC-SHA256 的前半部分
+const TAG_LEN: usize = 16;
+
+/// 密钥管理算法（标头 alg）
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyManagement {
+    #[serde(rename = "dir")]
+    Dir,
+    A128KW,
+    A256KW,
+    #[serde(rename = "RSA-OAEP")]
+    RsaOaep,
+    #[serde(rename = "RSA-OAEP-256")]
+    RsaOaep256,
+    #[serde(rename = "ECDH-ES")]
+    EcdhEs,
+}
+
+/// 内容加密算法（标头 enc）
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentEncryption {
+    A128GCM,
+    A256GCM,
+    #[serde(rename = "A128CBC-HS256")]
+    A128CbcHs256,
+}
+
+impl ContentEncryption {
+    fn name(self) -> &'static str {
+        match self {
+            ContentEncryption::A128GCM => "A128GCM",
+            ContentEncryption::A256GCM => "A256GCM",
+            ContentEncryption::A128CbcHs256 => "A128CBC-HS256",
+        }
+    }
+
+    /// 内容加密密钥（CEK）的字节数；A128CBC-HS256 为 16 字节 MAC 密钥 + 16 字节 AES 密钥
+    fn key_len(self) -> usize {
+        match self {
+            ContentEncryption::A128GCM => 16,
+            ContentEncryption::A256GCM | ContentEncryption::A128CbcHs256 => 32,
+        }
+    }
+
+    fn iv_len(self) -> usize {
+        match self {
+            ContentEncryption::A128GCM | ContentEncryption::A256GCM => 12,
+            ContentEncryption::A128CbcHs256 => 16,
+        }
+    }
+
+    /// 返回 (密文, 认证标签)
+    fn encrypt(
+        self,
+        cek: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Result<(Vec<u8>, Vec<u8>), JwtError> {
+        let failed = |e: &dyn std::fmt::Display| JwtError::InvalidKey(e.to_string());
+        match self {
+            ContentEncryption::A128GCM | ContentEncryption::A256GCM => {
+                let payload = Payload {
+                    msg: plaintext,
+                    aad,
+                };
+                let nonce = Nonce::from_slice(iv);
+                let sealed = match self {
+                    ContentEncryption::A128GCM => Aes128Gcm::new_from_slice(cek)
+                        .map_err(|e| failed(&e))?
+                        .encrypt(nonce, payload),
+                    _ => Aes256Gcm::new_from_slice(cek)
+                        .map_err(|e| failed(&e))?
+                        .encrypt(nonce, payload),
+                };
+                let mut sealed = sealed.map_err(|e| failed(&e))?;
+                let tag = sealed.split_off(sealed.len() - TAG_LEN);
+                Ok((sealed, tag))
+            }
+            ContentEncryption::A128CbcHs256 => {
+                let (mac_key, enc_key) = cek.split_at(16);
+                let ciphertext = cbc::Encryptor::<aes::Aes128>::new_from_slices(enc_key, iv)
+                    .map_err(|e| failed(&e))?
+                    .encrypt_padded_vec_mut::<Pkcs7>(plaintext);
+                let tag = cbc_hmac(mac_key, aad, iv, &ciphertext)
+                    .finalize()
+                    .into_bytes();
+                Ok((ciphertext, tag[..TAG_LEN].to_vec()))
+            }
+        }
+    }
+
+    fn decrypt(
+        self,
+        cek: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+        ciphertext: &[u8],
+        tag: &[u8],
+    ) -> Result<Vec<u8>, JwtError> {
+        let failed =
+            || JwtError::DecryptionFailed("认证标签校验失败，密钥错误或令牌被篡改".to_string());
+        if cek.len() != self.key_len() || iv.len() != self.iv_len() {
+            return Err(JwtError::DecryptionFailed(format!(
+                "{} 需要 {} 字节密钥和 {} 字节 IV",
+                self.name(),
+                self.key_len(),
+                self.iv_len()
+            )));
+        }
+        // 三种算法的认证标签都是 16 字节；截短的标签会降低伪造难度，必须拒绝
+        if tag.len() != TAG_LEN {
+            return Err(JwtError::DecryptionFailed(format!(
+                "认证标签应为 {} 字节，实际为 {} 字节",
+                TAG_LEN,
+                tag.len()
+            )));
+        }
+        match self {
+            ContentEncryption::A128GCM | ContentEncryption::A256GCM => {
+                let sealed = [ciphertext, tag].concat();
+                let payload = Payload { msg: &sealed, aad };
+                let nonce = Nonce::from_slice(iv);
+                let opened = match self {
+                    ContentEncryption::A128GCM => Aes128Gcm::new_from_slice(cek)
+                        .map_err(|_| failed())?
+                        .decrypt(nonce, payload),
+                    _ => Aes256Gcm::new_from_slice(cek)
+                        .map_err(|_| failed())?
+                        .decrypt(nonce, payload),
+                };
+                opened.map_err(|_| failed())
+            }
+            ContentEncryption::A128CbcHs256 => {
+                let (mac_key, enc_key) = cek.split_at(16);
+                cbc_hmac(mac_key, aad, iv, ciphertext)
+                    .verify_truncated_left(tag)
+                    .map_err(|_| failed())?;
+                cbc::Decryptor::<aes::Aes128>::new_from_slices(enc_key, iv)
+                    .map_err(|_| failed())?
+                    .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+                    .map_err(|_| failed())
+            }
+        }
+    }
+}
+
+/// RFC 7518 第 5.2.2.1 节：HMAC(MAC_KEY, AAD || IV || 密文 || AAD 位长度)
+fn cbc_hmac(mac_key: &[u8], aad: &[u8], iv: &[u8], ciphertext: &[u8]) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(mac_key).expect("HMAC 接受任意长度的密钥");
+    mac.update(aad);
+    mac.update(iv);
+    mac.update(ciphertext);
+    mac.update(&((aad.len() as u64) * 8).to_be_bytes());
+    mac
+}
+
+/// 只解析标头和各段长度，不需要密钥
+#[derive(Serialize, Clone, Debug)]
+pub struct JweInspection {
+    pub header: Value,
+    pub alg: Option<String>,
+    pub enc: Option<String>,
+    pub kid: Option<String>,
+    pub nested: bool,    // cty 为 JWT，解密后是另一个令牌
+    pub supported: bool, // alg 和 enc 是否都能解密
+    pub encrypted_key_len: usize,
+    pub iv_len: usize,
+    pub ciphertext_len: usize,
+    pub tag_len: usize,
+}
+
+/// 解密结果
+#[derive(Serialize, Clone, Debug)]
+pub struct DecryptedJwe {
+    pub header: Value,
+    pub plaintext: String,
+    pub payload: Option<Value>,        // 明文是 JSON 时的解析结果
+    pub nested: Option<JwtInspection>, // 明文是签名令牌时的分析结果（未验证签名）
+}
+
+/// JWE compact 格式的五个段
+struct JweParts {
+    protected: String,
+    header: Value,
+    encrypted_key: Vec<u8>,
+    iv: Vec<u8>,
+    ciphertext: Vec<u8>,
+    tag: Vec<u8>,
+}
+
+fn parse_parts(token: &str) -> Result<JweParts, JwtError> {
+    let parts: Vec<&str> = token.trim().split('.').collect();
+    let [protected, encrypted_key, iv, ciphertext, tag] = parts.as_slice() else {
+        return Err(JwtError::Malformed(format!(
+            "JWE应由5段组成，实际为{}段",
+            parts.len()
+        )));
+    };
+    let decode = |name: &str, segment: &str| {
+        URL_SAFE_NO_PAD
+            .decode(segment)
+            .map_err(|e| JwtError::Malformed(format!("{}Base64解码失败: {}", name, e)))
+    };
+    let header: Value = serde_json::from_slice(&decode("标头", protected)?)
+        .map_err(|e| JwtError::Malformed(format!("标头JSON格式错误: {}", e)))?;
+    if !header.is_object() {
+        return Err(JwtError::Malformed("标头不是JSON对象".to_string()));
+    }
+
+    Ok(JweParts {
+        protected: protected.to_string(),
+        header,
+        encrypted_key: decode("加密密钥", encrypted_key)?,
+        iv: decode("IV", iv)?,
+        ciphertext: decode("密文", ciphertext)?,
+        tag: decode("认证标签", tag)?,
+    })
+}
+
+fn header_str(header: &Value, name: &str) -> Option<String> {
+    header.get(name).and_then(Value::as_str).map(str::to_string)
+}
+
+/// 从标头读取 alg 和 enc，不支持的算法返回 `UnsupportedAlgorithm`
+fn algorithms(header: &Value) -> Result<(KeyManagement, ContentEncryption), JwtError> {
+    let field = |name: &str| {
+        let value = header.get(name).cloned().unwrap_or(Value::Null);
+        (value.to_string(), value)
+    };
+    let (alg_text, alg) = field("alg");
+    let (enc_text, enc) = field("enc");
+    let alg = serde_json::from_value(alg).map_err(|_| JwtError::UnsupportedAlgorithm(alg_text))?;
+    let enc = serde_json::from_value(enc).map_err(|_| JwtError::UnsupportedAlgorithm(enc_text))?;
+    if header.get("zip").is_some() {
+        return Err(JwtError::UnsupportedAlgorithm("zip".to_string()));
+    }
+    Ok((alg, enc))
+}
+
+pub fn inspect_jwe(token: &str) -> Result<JweInspection, JwtError> {
+    let parts = parse_parts(token)?;
+    let header = parts.header;
+    Ok(JweInspection {
+        alg: header_str(&header, "alg"),
+        enc: header_str(&header, "enc"),
+        kid: header_str(&header, "kid"),
+        nested: header_str(&header, "cty").is_some_and(|cty| cty.eq_ignore_ascii_case("JWT")),
+        supported: algorithms(&header).is_ok(),
+        encrypted_key_len: parts.encrypted_key.len(),
+        iv_len: parts.iv.len(),
+        ciphertext_len: parts.ciphertext.len(),
+        tag_len: parts.tag.len(),
+        header,
+    })
+}
+
+/// 解密 JWE compact 令牌
+///
+/// `key` 的格式取决于 alg：dir 和 AES 密钥包装为对称密钥（oct JWK、base64 或原始文本），
+/// RSA-OAEP 为 RSA 私钥，ECDH-ES 为 P-256/P-384 私钥（PEM、base64 DER 或 JWK）。
+pub fn decrypt_jwe(token: &str, key: &str) -> Result<DecryptedJwe, JwtError> {
+    let parts = parse_parts(token)?;
+    let (alg, enc) = algorithms(&parts.header)?;
+
+    let cek = match alg {
+        KeyManagement::Dir => symmetric_key(key, enc.key_len())?,
+        KeyManagement::A128KW | KeyManagement::A256KW => {
+            let unwrapped = match alg {
+                KeyManagement::A128KW => KekAes128::try_from(symmetric_key(key, 16)?.as_slice())
+                    .map_err(|e| JwtError::InvalidKey(e.to_string()))?
+                    .unwrap_vec(&parts.encrypted_key),
+                _ => KekAes256::try_from(symmetric_key(key, 32)?.as_slice())
+                    .map_err(|e| JwtError::InvalidKey(e.to_string()))?
+                    .unwrap_vec(&parts.encrypted_key),
+            };
+            unwrapped.map_err(|_| JwtError::DecryptionFailed("密钥解包失败".to_string()))?
+        }
+        KeyManagement::RsaOaep | KeyManagement::RsaOaep256 => {
+            let AsymmetricKey::RsaPrivate(private) = asymmetric_key(key)? else {
+                return Err(JwtError::InvalidKey(
+                    "RSA-OAEP 解密需要 RSA 私钥".to_string(),
+                ));
+            };
+            private
+                .decrypt(oaep(alg), &parts.encrypted_key)
+                .map_err(|_| JwtError::DecryptionFailed("RSA-OAEP 解密内容密钥失败".to_string()))?
+        }
+        KeyManagement::EcdhEs => {
+            let AsymmetricKey::EcPrivate(private) = asymmetric_key(key)? else {
+                return Err(JwtError::InvalidKey("ECDH-ES 解密需要 EC 私钥".to_string()));
+            };
+            let epk = parts
+                .header
+                .get("epk")
+                .ok_or_else(|| JwtError::Malformed("ECDH-ES 标头缺少 epk".to_string()))?;
+            let shared = private.agree(epk)?;
+            derive_ecdh_key(&shared, &parts.header, enc)?
+        }
+    };
+
+    let plaintext = enc.decrypt(
+        &cek,
+        &parts.iv,
+        parts.protected.as_bytes(),
+        &parts.ciphertext,
+        &parts.tag,
+    )?;
+    let plaintext = String::from_utf8(plaintext)
+        .map_err(|_| JwtError::DecryptionFailed("明文不是有效的UTF-8文本".to_string()))?;
+
+    Ok(DecryptedJwe {
+        payload: serde_json::from_str(&plaintext).ok(),
+        nested: match plaintext.split('.').count() {
+            3 => inspect_token(&plaintext, None).ok(),
+            _ => None,
+        },
+        header: parts.header,
+        plaintext,
+    })
+}
+
+/// 按标头中的 alg 和 enc 加密为 JWE compact 格式
+///
+/// RSA-OAEP 和 ECDH-ES 使用接收方的公钥（也可以传私钥）；ECDH-ES 会在标头中加入 epk。
+pub fn encrypt_jwe(header: &Value, plaintext: &[u8], key: &str) -> Result<String, JwtError> {
+    if !header.is_object() {
+        return Err(JwtError::Malformed("标头不是JSON对象".to_string()));
+    }
+    let (alg, enc) = algorithms(header)?;
+    let mut header = header.clone();
+
+    let (cek, encrypted_key) = match alg {
+        KeyManagement::Dir => (symmetric_key(key, enc.key_len())?, Vec::new()),
+        KeyManagement::A128KW | KeyManagement::A256KW => {
+            let cek = random_bytes(enc.key_len());
+            let wrapped = match alg {
+                KeyManagement::A128KW => KekAes128::try_from(symmetric_key(key, 16)?.as_slice())
+                    .map_err(|e| JwtError::InvalidKey(e.to_string()))?
+                    .wrap_vec(&cek),
+                _ => KekAes256::try_from(symmetric_key(key, 32)?.as_slice())
+                    .map_err(|e| JwtError::InvalidKey(e.to_string()))?
+                    .wrap_vec(&cek),
+            };
+            let wrapped = wrapped.map_err(|e| JwtError::InvalidKey(e.to_string()))?;
+            (cek, wrapped)
+        }
+        KeyManagement::RsaOaep | KeyManagement::RsaOaep256 => {
+            let public = match asymmetric_key(key)? {
+                AsymmetricKey::RsaPublic(public) => public,
+                AsymmetricKey::RsaPrivate(private) => private.to_public_key(),
+                _ => return Err(JwtError::InvalidKey("RSA-OAEP 需要 RSA 公钥".to_string())),
+            };
+            let cek = random_bytes(enc.key_len());
+            let encrypted = public
+                .encrypt(&mut OsRng, oaep(alg), &cek)
+                .map_err(|e| JwtError::InvalidKey(e.to_string()))?;
+            (cek, encrypted)
+        }
+        KeyManagement::EcdhEs => {
+            let public = match asymmetric_key(key)? {
+                AsymmetricKey::EcPublic(public) => public,
+                AsymmetricKey::EcPrivate(private) => private.public(),
+                _ => return Err(JwtError::InvalidKey("ECDH-ES 需要 EC 公钥".to_string())),
+            };
+            let (shared, epk) = public.ephemeral_agree();
+            header["epk"] = epk;
+            (derive_ecdh_key(&shared, &header, enc)?, Vec::new())
+        }
+    };
+
+    let protected = URL_SAFE_NO_PAD.encode(header.to_string());
+    let iv = random_bytes(enc.iv_len());
+    let (ciphertext, tag) = enc.encrypt(&cek, &iv, protected.as_bytes(), plaintext)?;
+    Ok([
+        protected,
+        URL_SAFE_NO_PAD.encode(encrypted_key),
+        URL_SAFE_NO_PAD.encode(iv),
+        URL_SAFE_NO_PAD.encode(ciphertext),
+        URL_SAFE_NO_PAD.encode(tag),
+    ]
+    .join("."))
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+fn oaep(alg: KeyManagement) -> Oaep {
+    match alg {
+        KeyManagement::RsaOaep256 => Oaep::new::<Sha256>(),
+        _ => Oaep::new::<sha1::Sha1>(),
+    }
+}
+
+/// 对称密钥：oct JWK，或按 base64url、base64、原始文本的顺序取长度符合要求的一种
+fn symmetric_key(material: &str, len: usize) -> Result<Vec<u8>, JwtError> {
+    let text = material.trim();
+    if text.starts_with('{') {
+        let jwk: Value = serde_json::from_str(text)
+            .map_err(|e| JwtError::InvalidKey(format!("JWK JSON格式错误: {}", e)))?;
+        let key = jwk
+            .get("k")
+            .and_then(Value::as_str)
+            .and_then(|k| URL_SAFE_NO_PAD.decode(k).ok())
+            .ok_or_else(|| JwtError::InvalidKey("对称密钥JWK缺少有效的 k".to_string()))?;
+        if key.len() != len {
+            return Err(JwtError::InvalidKey(format!(
+                "需要 {} 字节的对称密钥，JWK 中为 {} 字节",
+                len,
+                key.len()
+            )));
+        }
+        return Ok(key);
+    }
+
+    [
+        URL_SAFE_NO_PAD.decode(text).ok(),
+        STANDARD.decode(text).ok(),
+        Some(text.as_bytes().to_vec()),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|key| key.len() == len)
+    .ok_or_else(|| {
+        JwtError::InvalidKey(format!(
+            "需要 {} 字节的对称密钥（oct JWK、base64 或原始文本）",
+            len
+        ))
+    })
+}
+
+/// RFC 7518 第 4.6.2 节：ECDH-ES 直接密钥协商，AlgorithmID 为 enc
+fn derive_ecdh_key(
+    shared: &[u8],
+    header: &Value,
+    enc: ContentEncryption,
+) -> Result<Vec<u8>, JwtError> {
+    let party = |name: &str| match header.get(name).and_then(Value::as_str) {
+        Some(value) => URL_SAFE_NO_PAD
+            .decode(value)
+            .map_err(|e| JwtError::Malformed(format!("{} Base64解码失败: {}", name, e))),
+        None => Ok(Vec::new()),
+    };
+    Ok(concat_kdf(
+        shared,
+        enc.name().as_bytes(),
+        &party("apu")?,
+        &party("apv")?,
+        enc.key_len(),
+    ))
+}
+
+/// NIST SP 800-56A Concat KDF（SHA-256）
+fn concat_kdf(shared: &[u8], algorithm_id: &[u8], apu: &[u8], apv: &[u8], len: usize) -> Vec<u8> {
+    let mut other_info = Vec::new();
+    for field in [algorithm_id, apu, apv] {
+        other_info.extend((field.len() as u32).to_be_bytes());
+        other_info.extend(field);
+    }
+    other_info.extend(((len * 8) as u32).to_be_bytes());
+
+    let mut output = Vec::with_capacity(len + 32);
+    let mut counter = 1u32;
+    while output.len() < len {
+        let mut hasher = Sha256::new();
+        hasher.update(counter.to_be_bytes());
+        hasher.update(shared);
+        hasher.update(&other_info);
+        output.extend(hasher.finalize());
+        counter += 1;
+    }
+    output.truncate(len);
+    output
+}
+
+/// JWE 使用的非对称密钥
+enum AsymmetricKey {
+    RsaPrivate(Box<RsaPrivateKey>),
+    RsaPublic(RsaPublicKey),
+    EcPrivate(EcPrivate),
+    EcPublic(EcPublic),
+}
+
+enum EcPrivate {
+    P256(p256::SecretKey),
+    P384(p384::SecretKey),
+}
+
+enum EcPublic {
+    P256(p256::PublicKey),
+    P384(p384::PublicKey),
+}
+
+impl EcPrivate {
+    fn public(&self) -> EcPublic {
+        match self {
+            EcPrivate::P256(key) => EcPublic::P256(key.public_key()),
+            EcPrivate::P384(key) => EcPublic::P384(key.public_key()),
+        }
+    }
+
+    /// 与标头中的临时公钥 epk 协商出共享密钥
+    fn agree(&self, epk: &Value) -> Result<Vec<u8>, JwtError> {
+        let invalid = |e: p256::elliptic_curve::Error| {
+            JwtError::Malformed(format!("epk 不是有效的EC公钥: {}", e))
+        };
+        let epk = ec_jwk_text(epk);
+        Ok(match self {
+            EcPrivate::P256(key) => {
+                let epk = p256::PublicKey::from_jwk_str(&epk).map_err(invalid)?;
+                p256::ecdh::diffie_hellman(key.to_nonzero_scalar(), epk.as_affine())
+                    .raw_secret_bytes()
+                    .to_vec()
+            }
+            EcPrivate::P384(key) => {
+                let epk = p384::PublicKey::from_jwk_str(&epk).map_err(invalid)?;
+                p384::ecdh::diffie_hellman(key.to_nonzero_scalar(), epk.as_affine())
+                    .raw_secret_bytes()
+                    .to_vec()
+            }
+        })
+    }
+}
+
+impl EcPublic {
+    /// 生成临时密钥对并协商，返回 (共享密钥, epk JWK)
+    fn ephemeral_agree(&self) -> (Vec<u8>, Value) {
+        let (shared, epk) = match self {
+            EcPublic::P256(public) => {
+                let ephemeral = p256::ecdh::EphemeralSecret::random(&mut OsRng);
+                let shared = ephemeral.diffie_hellman(public).raw_secret_bytes().to_vec();
+                (shared, ephemeral.public_key().to_jwk_string())
+            }
+            EcPublic::P384(public) => {
+                let ephemeral = p384::ecdh::EphemeralSecret::random(&mut OsRng);
+                let shared = ephemeral.diffie_hellman(public).raw_secret_bytes().to_vec();
+                (shared, ephemeral.public_key().to_jwk_string())
+            }
+        };
+        (shared, serde_json::from_str(&epk).unwrap_or_default())
+    }
+}
+
+/// 解析 RSA 或 EC 密钥：PEM（PKCS#8、PKCS#1、SEC1、SPKI）、base64 DER 或 JWK
+fn asymmetric_key(material: &str) -> Result<AsymmetricKey, JwtError> {
+    let key = match KeyText::parse(material)? {
+        KeyText::Pem(pem) => from_pem(pem),
+        KeyText::Der(der) => from_der(&der),
+        KeyText::Json(jwk) => from_jwk(&jwk),
+        KeyText::Secret(_) => None,
+    };
+    key.ok_or_else(|| JwtError::InvalidKey("无法识别的RSA或EC密钥".to_string()))
+}
+
+fn from_pem(pem: &str) -> Option<AsymmetricKey> {
+    use AsymmetricKey::*;
+    RsaPrivateKey::from_pkcs8_pem(pem)
+        .ok()
+        .or_else(|| RsaPrivateKey::from_pkcs1_pem(pem).ok())
+        .map(|key| RsaPrivate(Box::new(key)))
+        .or_else(|| {
+            RsaPublicKey::from_public_key_pem(pem)
+                .ok()
+                .or_else(|| RsaPublicKey::from_pkcs1_pem(pem).ok())
+                .map(RsaPublic)
+        })
+        .or_else(|| {
+            p256::SecretKey::from_pkcs8_pem(pem)
+                .ok()
+                .or_else(|| p256::SecretKey::from_sec1_pem(pem).ok())
+                .map(|key| EcPrivate(self::EcPrivate::P256(key)))
+        })
+        .or_else(|| {
+            p384::SecretKey::from_pkcs8_pem(pem)
+                .ok()
+                .or_else(|| p384::SecretKey::from_sec1_pem(pem).ok())
+                .map(|key| EcPrivate(self::EcPrivate::P384(key)))
+        })
+        .or_else(|| {
+            p256::PublicKey::from_public_key_pem(pem)
+                .ok()
+                .map(|key| EcPublic(self::EcPublic::P256(key)))
+        })
+        .or_else(|| {
+            p384::PublicKey::from_public_key_pem(pem)
+                .ok()
+                .map(|key| EcPublic(self::EcPublic::P384(key)))
+        })
+}
+
+fn from_der(der: &[u8]) -> Option<AsymmetricKey> {
+    use AsymmetricKey::*;
+    RsaPrivateKey::from_pkcs8_der(der)
+        .ok()
+        .or_else(|| RsaPrivateKey::from_pkcs1_der(der).ok())
+        .map(|key| RsaPrivate(Box::new(key)))
+        .or_else(|| {
+            RsaPublicKey::from_public_key_der(der)
+                .ok()
+                .or_else(|| RsaPublicKey::from_pkcs1_der(der).ok())
+                .map(RsaPublic)
+        })
+        .or_else(|| {
+            p256::SecretKey::from_pkcs8_der(der)
+                .ok()
+                .or_else(|| p256::SecretKey::from_sec1_der(der).ok())
+                .map(|key| EcPrivate(self::EcPrivate::P256(key)))
+        })
+        .or_else(|| {
+            p384::SecretKey::from_pkcs8_der(der)
+                .ok()
+                .or_else(|| p384::SecretKey::from_sec1_der(der).ok())
+                .map(|key| EcPrivate(self::EcPrivate::P384(key)))
+        })
+        .or_else(|| {
+            p256::PublicKey::from_public_key_der(der)
+                .ok()
+                .map(|key| EcPublic(self::EcPublic::P256(key)))
+        })
+        .or_else(|| {
+            p384::PublicKey::from_public_key_der(der)
+                .ok()
+                .map(|key| EcPublic(self::EcPublic::P384(key)))
+        })
+}
+
+/// elliptic-curve 的 JWK 解析不接受 kid、alg 等额外成员，只保留密钥本身的成员
+fn ec_jwk_text(jwk: &Value) -> String {
+    let members: serde_json::Map<String, Value> = ["kty", "crv", "x", "y", "d"]
+        .into_iter()
+        .filter_map(|name| Some((name.to_string(), jwk.get(name)?.clone())))
+        .collect();
+    Value::Object(members).to_string()
+}
+
+fn from_jwk(jwk: &Value) -> Option<AsymmetricKey> {
+    let member = |name: &str| {
+        jwk.get(name)
+            .and_then(Value::as_str)
+            .and_then(|value| URL_SAFE_NO_PAD.decode(value).ok())
+            .map(|bytes| BigUint::from_bytes_be(&bytes))
+    };
+    let text = ec_jwk_text(jwk);
+    let private = jwk.get("d").is_some();
+    match (
+        jwk.get("kty")?.as_str()?,
+        jwk.get("crv").and_then(Value::as_str),
+    ) {
+        ("RSA", _) if private => {
+            let primes = vec![member("p")?, member("q")?];
+            RsaPrivateKey::from_components(member("n")?, member("e")?, member("d")?, primes)
+                .ok()
+                .map(|key| AsymmetricKey::RsaPrivate(Box::new(key)))
+        }
+        ("RSA", _) => RsaPublicKey::new(member("n")?, member("e")?)
+            .ok()
+            .map(AsymmetricKey::RsaPublic),
+        ("EC", Some("P-256")) if private => p256::SecretKey::from_jwk_str(&text)
+            .ok()
+            .map(|key| AsymmetricKey::EcPrivate(EcPrivate::P256(key))),
+        ("EC", Some("P-256")) => p256::PublicKey::from_jwk_str(&text)
+            .ok()
+            .map(|key| AsymmetricKey::EcPublic(EcPublic::P256(key))),
+        ("EC", Some("P-384")) if private => p384::SecretKey::from_jwk_str(&text)
+            .ok()
+            .map(|key| AsymmetricKey::EcPrivate(EcPrivate::P384(key))),
+        ("EC", Some("P-384")) => p384::PublicKey::from_jwk_str(&text)
+            .ok()
+            .map(|key| AsymmetricKey::EcPublic(EcPublic::P384(key))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jwt_keygen::generate_keypair;
+    use jsonwebtoken::Algorithm;
+    use serde_json::json;
+
+    #[test]
+    fn decrypts_rfc7516_a128kw_example() {
+        // RFC 7516 附录 A.3
+        let token = "eyJhbGciOiJBMTI4S1ciLCJlbmMiOiJBMTI4Q0JDLUhTMjU2In0.\
+            6KB707dM9YTIgHtLvtgWQ8mKwboJW3of9locizkDTHzBC2IlrT1oOQ.\
+            AxY8DCtDaGlsbGljb3RoZQ.\
+            KDlTtXchhZTGufMYmOYGS4HffxPSUrfmqCHXaI9wOGY.\
+            U0m_YmjN04DJvceFICbCVQ";
+        let key = r#"{"kty":"oct","k":"GawgguFyGrWKav7AX4VKUg"}"#;
+        let decrypted = decrypt_jwe(token, key).unwrap();
+        assert_eq!(decrypted.plaintext, "Live long and prosper.");
+
+        let tampered = token.replace("U0m_", "U1m_");
+        assert!(matches!(
+            decrypt_jwe(&tampered, key),
+            Err(JwtError::DecryptionFailed(_))
+        ));
+
+        // 截短到 1 字节或加长的标签都直接拒绝，不进入 MAC 比较
+        let (body, tag) = token.rsplit_once('.').unwrap();
+        let full = URL_SAFE_NO_PAD.decode(tag).unwrap();
+        for bad in [
+            &full[..1],
+            &full[..15],
+            &[full.as_slice(), &[0]].concat()[..],
+        ] {
+            let truncated = format!("{}.{}", body, URL_SAFE_NO_PAD.encode(bad));
+            assert!(matches!(
+                decrypt_jwe(&truncated, key),
+                Err(JwtError::DecryptionFailed(message)) if message.contains("认证标签应为")
+            ));
+        }
+    }
+
+    #[test]
+    fn concat_kdf_matches_rfc7518_example() {
+        // RFC 7518 附录 C
+        let z = [
+            158, 86, 217, 29, 129, 113, 53, 211, 114, 131, 66, 131, 191, 132, 38, 156, 251, 49,
+            110, 163, 218, 128, 106, 72, 246, 218, 167, 121, 140, 254, 144, 196,
+        ];
+        let key = concat_kdf(&z, b"A128GCM", b"Alice", b"Bob", 16);
+        assert_eq!(URL_SAFE_NO_PAD.encode(key), "VqqN6vgjbSBcIijNcacQGg");
+    }
+
+    #[test]
+    fn round_trips_all_algorithms() {
+        let rsa = generate_keypair(Algorithm::RS256, None).unwrap();
+        let p256 = generate_keypair(Algorithm::ES256, None).unwrap();
+        let p384 = generate_keypair(Algorithm::ES384, None).unwrap();
+        let cases = [
+            (
+                "dir",
+                "0123456789abcdef0123456789abcdef",
+                "0123456789abcdef0123456789abcdef",
+            ),
+            ("A128KW", "0123456789abcdef", "0123456789abcdef"),
+            (
+                "A256KW",
+                "0123456789abcdef0123456789abcdef",
+                "0123456789abcdef0123456789abcdef",
+            ),
+            (
+                "RSA-OAEP",
+                rsa.public_pem.as_str(),
+                rsa.private_pem.as_str(),
+            ),
+            (
+                "RSA-OAEP-256",
+                rsa.public_der.as_str(),
+                rsa.private_der.as_str(),
+            ),
+            (
+                "ECDH-ES",
+                p256.public_pem.as_str(),
+                p256.private_pem.as_str(),
+            ),
+            (
+                "ECDH-ES",
+                &p384.public_jwk.to_string(),
+                &p384.private_jwk.to_string(),
+            ),
+        ];
+
+        for (alg, public, private) in cases {
+            for enc in ["A128GCM", "A256GCM", "A128CBC-HS256"] {
+                if alg == "dir" && enc == "A128GCM" {
+                    continue;
+                }
+                let header = json!({ "alg": alg, "enc": enc, "cty": "JWT" });
+                let token = encrypt_jwe(&header, b"a.b.c", public).unwrap();
+                let inspection = inspect_jwe(&token).unwrap();
+                assert!(inspection.supported && inspection.nested);
+
+                let decrypted = decrypt_jwe(&token, private).unwrap();
+                assert_eq!(decrypted.plaintext, "a.b.c", "{} {}", alg, enc);
+
+                // 截短的 GCM 标签同样被拒绝
+                let truncated = &token[..token.len() - 2];
+                assert!(decrypt_jwe(truncated, private).is_err(), "{} {}", alg, enc);
+            }
+        }
+    }
+
+    #[test]
+    fn inspects_unsupported_algorithms() {
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"RSA1_5","enc":"A128GCM"}"#);
+        let token = format!("{}.AA.AA.AA.AA", header);
+        let inspection = inspect_jwe(&token).unwrap();
+        assert_eq!(inspection.alg.as_deref(), Some("RSA1_5"));
+        assert!(!inspection.supported);
+        assert!(matches!(
+            decrypt_jwe(&token, "key"),
+            Err(JwtError::UnsupportedAlgorithm(_))
+        ));
+    }
+}
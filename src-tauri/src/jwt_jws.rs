@@ -0,0 +1,602 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::{crypto, Algorithm};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::jwt_error::JwtError;
+use crate::jwt_keys::{signing_key, verification_key};
+
+/// JWS 的三种序列化格式（RFC 7515 第 7 节）
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JwsSerialization {
+    Compact,
+    Flattened,
+    General,
+}
+
+/// JSON 序列化中的一个签名，`protected` 和 `signature` 为 base64url 文本
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct RawSignature {
+    #[serde(default)]
+    protected: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    header: Option<Value>,
+    signature: String,
+}
+
+/// 解析后的 JWS，`payload` 为序列化中出现的载荷部分，分离载荷时为空
+struct RawJws {
+    serialization: JwsSerialization,
+    payload: Option<String>,
+    signatures: Vec<RawSignature>,
+}
+
+/// 单个签名的标头信息，未验证
+#[derive(Serialize, Clone, Debug)]
+pub struct JwsSignatureInfo {
+    pub protected: Value,
+    pub header: Option<Value>, // 未受保护的标头，仅 JSON 序列化有
+    pub alg: Option<String>,
+    pub kid: Option<String>,
+}
+
+/// 未经验证的 JWS 解析结果
+#[derive(Serialize, Clone, Debug)]
+pub struct JwsDocument {
+    pub serialization: JwsSerialization,
+    pub b64: bool,               // false 表示载荷未经 base64url 编码（RFC 7797）
+    pub detached: bool,          // 载荷不在序列化中，需要调用方另外提供
+    pub payload: Option<String>, // 分离载荷且未提供时为空
+    pub payload_json: Option<Value>,
+    pub signatures: Vec<JwsSignatureInfo>,
+}
+
+/// 单个签名的验证结果
+#[derive(Serialize, Clone, Debug)]
+pub struct SignatureCheck {
+    pub index: usize,
+    pub alg: Option<String>,
+    pub kid: Option<String>,
+    pub valid: bool,
+    pub error: Option<JwtError>,
+}
+
+/// 至少一个签名验证通过的 JWS
+#[derive(Serialize, Clone, Debug)]
+pub struct VerifiedJws {
+    pub serialization: JwsSerialization,
+    pub payload: String,
+    pub payload_json: Option<Value>,
+    pub signatures: Vec<SignatureCheck>,
+}
+
+/// 一个签名者：受保护标头（必须包含 alg）、可选的未受保护标头和签名私钥
+#[derive(Deserialize, Clone, Debug)]
+pub struct JwsSigner {
+    pub protected: Value,
+    #[serde(default)]
+    pub header: Option<Value>,
+    pub key: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct JwsSignOptions {
+    pub serialization: JwsSerialization,
+    pub detached: bool, // 输出中不包含载荷
+    pub b64: bool,      // 为 false 时按 RFC 7797 不编码载荷，并在标头中加入 b64 和 crit
+}
+
+impl Default for JwsSignOptions {
+    fn default() -> Self {
+        JwsSignOptions {
+            serialization: JwsSerialization::Compact,
+            detached: false,
+            b64: true,
+        }
+    }
+}
+
+/// 一个签名的 JOSE 标头，受保护标头已解码
+struct SignatureHeader {
+    protected: Value,
+    header: Option<Value>,
+}
+
+impl SignatureHeader {
+    fn decode(signature: &RawSignature) -> Result<Self, JwtError> {
+        let protected = match signature.protected.as_str() {
+            "" => json!({}),
+            segment => URL_SAFE_NO_PAD
+                .decode(segment)
+                .ok()
+                .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+                .filter(Value::is_object)
+                .ok_or_else(|| JwtError::Malformed("受保护标头不是有效的JSON对象".to_string()))?,
+        };
+        if let Some(header) = &signature.header {
+            let duplicated = header
+                .as_object()
+                .ok_or_else(|| JwtError::Malformed("未受保护标头不是JSON对象".to_string()))?
+                .keys()
+                .find(|name| protected.get(name.as_str()).is_some());
+            if let Some(name) = duplicated {
+                return Err(JwtError::Malformed(format!(
+                    "标头参数 {} 同时出现在受保护和未受保护标头中",
+                    name
+                )));
+            }
+        }
+        Ok(SignatureHeader {
+            protected,
+            header: signature.header.clone(),
+        })
+    }
+
+    /// 受保护标头优先，其次是未受保护标头
+    fn get(&self, name: &str) -> Option<&Value> {
+        self.protected
+            .get(name)
+            .or_else(|| self.header.as_ref()?.get(name))
+    }
+
+    fn text(&self, name: &str) -> Option<String> {
+        self.get(name).and_then(Value::as_str).map(str::to_string)
+    }
+
+    /// RFC 7797：b64 必须在受保护标头中并列入 crit；crit 中不能有其他不认识的参数
+    fn b64(&self) -> Result<bool, JwtError> {
+        let crit: Vec<&str> = match self.protected.get("crit") {
+            Some(crit) => crit
+                .as_array()
+                .map(|items| items.iter().filter_map(Value::as_str).collect())
+                .ok_or_else(|| JwtError::Malformed("crit 必须是数组".to_string()))?,
+            None => Vec::new(),
+        };
+        if let Some(name) = crit.iter().find(|name| **name != "b64") {
+            return Err(JwtError::Malformed(format!(
+                "不支持 crit 中的参数 {}",
+                name
+            )));
+        }
+        match self.protected.get("b64") {
+            None => Ok(true),
+            Some(Value::Bool(b64)) if crit.contains(&"b64") => Ok(*b64),
+            Some(Value::Bool(_)) => Err(JwtError::Malformed(
+                "使用 b64 时必须把它列入 crit".to_string(),
+            )),
+            Some(_) => Err(JwtError::Malformed("b64 必须是布尔值".to_string())),
+        }
+    }
+
+    fn algorithm(&self) -> Result<Algorithm, JwtError> {
+        let alg = self
+            .get("alg")
+            .ok_or_else(|| JwtError::Malformed("标头缺少 alg".to_string()))?;
+        serde_json::from_value(alg.clone())
+            .map_err(|_| JwtError::UnsupportedAlgorithm(alg.to_string()))
+    }
+}
+
+fn parse_raw(input: &str) -> Result<RawJws, JwtError> {
+    let text = input.trim();
+    if !text.starts_with('{') {
+        let parts: Vec<&str> = text.split('.').collect();
+        let [protected, payload, signature] = parts.as_slice() else {
+            return Err(JwtError::Malformed(format!(
+                "JWS compact 格式应由3段组成，实际为{}段",
+                parts.len()
+            )));
+        };
+        return Ok(RawJws {
+            serialization: JwsSerialization::Compact,
+            payload: Some(payload.to_string()).filter(|payload| !payload.is_empty()),
+            signatures: vec![RawSignature {
+                protected: protected.to_string(),
+                header: None,
+                signature: signature.to_string(),
+            }],
+        });
+    }
+
+    let mut value: Value = serde_json::from_str(text)
+        .map_err(|e| JwtError::Malformed(format!("JWS JSON格式错误: {}", e)))?;
+    let payload = match value.get("payload") {
+        None => None,
+        Some(Value::String(payload)) => Some(payload.clone()),
+        Some(_) => return Err(JwtError::Malformed("payload 必须是字符串".to_string())),
+    };
+    let (serialization, signatures) = match value.get_mut("signatures") {
+        Some(signatures) => (
+            JwsSerialization::General,
+            serde_json::from_value(signatures.take()),
+        ),
+        None => (
+            JwsSerialization::Flattened,
+            serde_json::from_value(value).map(|signature| vec![signature]),
+        ),
+    };
+    let signatures: Vec<RawSignature> =
+        signatures.map_err(|e| JwtError::Malformed(format!("签名格式错误: {}", e)))?;
+    if signatures.is_empty() {
+        return Err(JwtError::Malformed("JWS 中没有签名".to_string()));
+    }
+    Ok(RawJws {
+        serialization,
+        payload,
+        signatures,
+    })
+}
+
+/// 解码所有签名的标头，并确认各签名的 b64 一致
+fn decode_headers(raw: &RawJws) -> Result<(Vec<SignatureHeader>, bool), JwtError> {
+    let headers = raw
+        .signatures
+        .iter()
+        .map(SignatureHeader::decode)
+        .collect::<Result<Vec<_>, _>>()?;
+    let b64 = headers
+        .iter()
+        .map(SignatureHeader::b64)
+        .collect::<Result<Vec<_>, _>>()?;
+    if b64.windows(2).any(|pair| pair[0] != pair[1]) {
+        return Err(JwtError::Malformed("所有签名的 b64 必须一致".to_string()));
+    }
+    Ok((headers, b64.first().copied().unwrap_or(true)))
+}
+
+/// 确定载荷：序列化中有载荷时使用它，否则使用调用方提供的分离载荷
+///
+/// 返回 (签名输入中的载荷部分, 载荷原文)，都没有时为空。
+fn resolve_payload(
+    raw: &RawJws,
+    b64: bool,
+    detached: Option<&str>,
+) -> Result<Option<(String, String)>, JwtError> {
+    match (&raw.payload, detached) {
+        (Some(part), _) if b64 => {
+            let bytes = URL_SAFE_NO_PAD
+                .decode(part)
+                .map_err(|e| JwtError::Malformed(format!("载荷Base64解码失败: {}", e)))?;
+            let text = String::from_utf8(bytes)
+                .map_err(|_| JwtError::Malformed("载荷不是有效的UTF-8文本".to_string()))?;
+            Ok(Some((part.clone(), text)))
+        }
+        (Some(part), _) => Ok(Some((part.clone(), part.clone()))),
+        (None, Some(payload)) if b64 => {
+            Ok(Some((URL_SAFE_NO_PAD.encode(payload), payload.to_string())))
+        }
+        (None, Some(payload)) => Ok(Some((payload.to_string(), payload.to_string()))),
+        (None, None) => Ok(None),
+    }
+}
+
+/// 解析 compact、Flattened 或 General JSON 格式的 JWS，不验证签名
+///
+/// 载荷不在序列化中（分离载荷）时，可通过 `detached` 提供原始载荷。
+pub fn parse_jws(input: &str, detached: Option<&str>) -> Result<JwsDocument, JwtError> {
+    let raw = parse_raw(input)?;
+    let (headers, b64) = decode_headers(&raw)?;
+    let payload = resolve_payload(&raw, b64, detached)?.map(|(_, text)| text);
+
+    Ok(JwsDocument {
+        serialization: raw.serialization,
+        b64,
+        detached: raw.payload.is_none(),
+        payload_json: payload
+            .as_deref()
+            .and_then(|payload| serde_json::from_str(payload).ok()),
+        payload,
+        signatures: headers
+            .into_iter()
+            .map(|header| JwsSignatureInfo {
+                alg: header.text("alg"),
+                kid: header.text("kid"),
+                protected: header.protected,
+                header: header.header,
+            })
+            .collect(),
+    })
+}
+
+/// 验证 JWS 的每个签名，至少一个通过时返回成功
+///
+/// `key` 的格式与 `verify_with_key` 相同，JWKS 按各签名标头中的 kid 选择密钥。
+/// 只有一个签名时直接返回它的错误，多个签名都失败时返回 `InvalidSignature`。
+pub fn verify_jws(
+    input: &str,
+    detached: Option<&str>,
+    key: &str,
+    allowed: Option<&[Algorithm]>,
+) -> Result<VerifiedJws, JwtError> {
+    if key.trim().is_empty() {
+        return Err(JwtError::InvalidKey("需要提供验证密钥".to_string()));
+    }
+    let raw = parse_raw(input)?;
+    let (headers, b64) = decode_headers(&raw)?;
+    let (payload_part, payload) = resolve_payload(&raw, b64, detached)?
+        .ok_or_else(|| JwtError::Malformed("载荷是分离的，需要提供原始载荷才能验证".to_string()))?;
+
+    let signatures: Vec<SignatureCheck> = raw
+        .signatures
+        .iter()
+        .zip(&headers)
+        .enumerate()
+        .map(|(index, (signature, header))| {
+            let message = format!("{}.{}", signature.protected, payload_part);
+            let result = verify_signature(header, &signature.signature, &message, key, allowed);
+            SignatureCheck {
+                index,
+                alg: header.text("alg"),
+                kid: header.text("kid"),
+                valid: result.is_ok(),
+                error: result.err(),
+            }
+        })
+        .collect();
+
+    if !signatures.iter().any(|check| check.valid) {
+        return Err(match signatures.as_slice() {
+            [only] => only.error.clone().unwrap_or(JwtError::InvalidSignature),
+            _ => JwtError::InvalidSignature,
+        });
+    }
+    Ok(VerifiedJws {
+        serialization: raw.serialization,
+        payload_json: serde_json::from_str(&payload).ok(),
+        payload,
+        signatures,
+    })
+}
+
+fn verify_signature(
+    header: &SignatureHeader,
+    signature: &str,
+    message: &str,
+    key: &str,
+    allowed: Option<&[Algorithm]>,
+) -> Result<(), JwtError> {
+    let alg = header.algorithm()?;
+    if allowed.is_some_and(|allowed| !allowed.contains(&alg)) {
+        return Err(JwtError::AlgorithmNotAllowed(format!("{:?}", alg)));
+    }
+    let key = verification_key(key, header.text("kid").as_deref())?;
+    if !key.algorithms.contains(&alg) {
+        return Err(JwtError::KeyMismatch {
+            alg: format!("{:?}", alg),
+            key: key.family.name().to_string(),
+        });
+    }
+    match crypto::verify(signature, message.as_bytes(), &key.key, alg)? {
+        true => Ok(()),
+        false => Err(JwtError::InvalidSignature),
+    }
+}
+
+/// 用一个或多个签名者对载荷签名
+///
+/// compact 和 Flattened 格式只能有一个签名者，compact 格式不能带未受保护标头。
+pub fn sign_jws(
+    payload: &str,
+    signers: &[JwsSigner],
+    options: &JwsSignOptions,
+) -> Result<String, JwtError> {
+    match (options.serialization, signers) {
+        (_, []) => return Err(JwtError::InvalidKey("至少需要一个签名者".to_string())),
+        (JwsSerialization::General, _) | (_, [_]) => {}
+        _ => {
+            return Err(JwtError::Malformed(
+                "只有 General JSON 格式支持多个签名".to_string(),
+            ))
+        }
+    }
+    let compact = options.serialization == JwsSerialization::Compact;
+    if compact && signers[0].header.is_some() {
+        return Err(JwtError::Malformed(
+            "compact 格式不支持未受保护标头".to_string(),
+        ));
+    }
+    if compact && !options.b64 && !options.detached && payload.contains('.') {
+        return Err(JwtError::Malformed(
+            "未编码的载荷包含“.”，compact 格式只能使用分离载荷".to_string(),
+        ));
+    }
+
+    let payload_part = match options.b64 {
+        true => URL_SAFE_NO_PAD.encode(payload),
+        false => payload.to_string(),
+    };
+    let signatures = signers
+        .iter()
+        .map(|signer| sign_one(signer, &payload_part, options.b64))
+        .collect::<Result<Vec<_>, _>>()?;
+    let payload_part = Some(payload_part).filter(|_| !options.detached);
+
+    let serialized = match options.serialization {
+        JwsSerialization::Compact => {
+            let signature = &signatures[0];
+            return Ok(format!(
+                "{}.{}.{}",
+                signature.protected,
+                payload_part.unwrap_or_default(),
+                signature.signature
+            ));
+        }
+        JwsSerialization::Flattened => {
+            let mut value = serde_json::to_value(&signatures[0]).unwrap_or_default();
+            if let Some(payload) = payload_part {
+                value["payload"] = Value::String(payload);
+            }
+            value
+        }
+        JwsSerialization::General => {
+            let mut value = json!({ "signatures": signatures });
+            if let Some(payload) = payload_part {
+                value["payload"] = Value::String(payload);
+            }
+            value
+        }
+    };
+    Ok(serialized.to_string())
+}
+
+fn sign_one(signer: &JwsSigner, payload_part: &str, b64: bool) -> Result<RawSignature, JwtError> {
+    let mut protected = signer.protected.clone();
+    let object = protected
+        .as_object_mut()
+        .ok_or_else(|| JwtError::Malformed("受保护标头不是JSON对象".to_string()))?;
+    if !b64 {
+        object.insert("b64".to_string(), Value::Bool(false));
+        let crit = object.entry("crit").or_insert_with(|| json!([]));
+        match crit.as_array_mut() {
+            Some(items) if !items.contains(&json!("b64")) => items.push(json!("b64")),
+            Some(_) => {}
+            None => return Err(JwtError::Malformed("crit 必须是数组".to_string())),
+        }
+    }
+
+    let raw = RawSignature {
+        protected: URL_SAFE_NO_PAD.encode(protected.to_string()),
+        header: signer.header.clone(),
+        signature: String::new(),
+    };
+    let header = SignatureHeader::decode(&raw)?;
+    header.b64()?;
+    let alg = header.algorithm()?;
+    let key = signing_key(&signer.key, alg)?;
+    let message = format!("{}.{}", raw.protected, payload_part);
+    Ok(RawSignature {
+        signature: crypto::sign(message.as_bytes(), &key, alg)?,
+        ..raw
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jwt_keygen::generate_keypair;
+
+    /// RFC 7515 附录 A.1 的 HMAC 密钥
+    const RFC_KEY: &str = r#"{"kty":"oct","k":"AyM1SysPpbyDfgZld3umj1qzKObwVMkoqQ-EstJQLr_T-1qS0gZH75aKtMN3Yj0iPS4hcgUuTwjAzZr1Z9CAow"}"#;
+
+    #[test]
+    fn verifies_rfc7797_unencoded_detached_payload() {
+        // RFC 7797 第 4.2 节
+        let jws = "eyJhbGciOiJIUzI1NiIsImI2NCI6ZmFsc2UsImNyaXQiOlsiYjY0Il19..A5dxf2s96_n5FLueVuW1Z_vh161FwXZC4YLPff6dmDY";
+        let document = parse_jws(jws, None).unwrap();
+        assert!(document.detached && !document.b64);
+        assert!(document.payload.is_none());
+
+        let verified = verify_jws(jws, Some("$.02"), RFC_KEY, None).unwrap();
+        assert_eq!(verified.payload, "$.02");
+        assert!(matches!(
+            verify_jws(jws, Some("$.03"), RFC_KEY, None),
+            Err(JwtError::InvalidSignature)
+        ));
+        assert!(matches!(
+            verify_jws(jws, None, RFC_KEY, None),
+            Err(JwtError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_b64_without_crit() {
+        let protected = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","b64":false}"#);
+        let jws = format!("{}.payload.AA", protected);
+        assert!(matches!(parse_jws(&jws, None), Err(JwtError::Malformed(_))));
+    }
+
+    #[test]
+    fn signs_and_verifies_general_json_with_multiple_signatures() {
+        let rsa = generate_keypair(Algorithm::RS256, None).unwrap();
+        let ec = generate_keypair(Algorithm::ES256, None).unwrap();
+        let signers = [
+            JwsSigner {
+                protected: json!({ "alg": "RS256" }),
+                header: Some(json!({ "kid": rsa.kid })),
+                key: rsa.private_pem.clone(),
+            },
+            JwsSigner {
+                protected: json!({ "alg": "ES256", "kid": ec.kid }),
+                header: None,
+                key: ec.private_pem.clone(),
+            },
+        ];
+        let options = JwsSignOptions {
+            serialization: JwsSerialization::General,
+            ..Default::default()
+        };
+        let jws = sign_jws(r#"{"event":"paid"}"#, &signers, &options).unwrap();
+
+        let document = parse_jws(&jws, None).unwrap();
+        assert_eq!(document.serialization, JwsSerialization::General);
+        assert_eq!(document.payload_json.unwrap()["event"], "paid");
+        assert_eq!(
+            document.signatures[0].kid.as_deref(),
+            Some(rsa.kid.as_str())
+        );
+
+        let jwks = json!({ "keys": [rsa.public_jwk, ec.public_jwk] }).to_string();
+        let verified = verify_jws(&jws, None, &jwks, None).unwrap();
+        assert!(verified.signatures.iter().all(|check| check.valid));
+
+        // 只有 EC 公钥时 RSA 签名失败，但整体仍然通过
+        let verified = verify_jws(&jws, None, &ec.public_pem, None).unwrap();
+        let valid: Vec<bool> = verified.signatures.iter().map(|c| c.valid).collect();
+        assert_eq!(valid, vec![false, true]);
+    }
+
+    #[test]
+    fn signs_flattened_and_compact_detached() {
+        let signer = JwsSigner {
+            protected: json!({ "alg": "HS256" }),
+            header: Some(json!({ "kid": "webhook" })),
+            key: "webhook-secret".to_string(),
+        };
+        let flattened = JwsSignOptions {
+            serialization: JwsSerialization::Flattened,
+            detached: true,
+            b64: false,
+        };
+        let jws = sign_jws("a.b", std::slice::from_ref(&signer), &flattened).unwrap();
+        assert!(!jws.contains("payload"));
+        let verified = verify_jws(&jws, Some("a.b"), "webhook-secret", None).unwrap();
+        assert_eq!(verified.serialization, JwsSerialization::Flattened);
+
+        // compact 格式不能带未受保护标头，也不能内嵌含“.”的未编码载荷
+        let compact = JwsSignOptions {
+            b64: false,
+            ..Default::default()
+        };
+        assert!(sign_jws("a.b", std::slice::from_ref(&signer), &compact).is_err());
+        let signer = JwsSigner {
+            header: None,
+            ..signer
+        };
+        assert!(sign_jws("a.b", std::slice::from_ref(&signer), &compact).is_err());
+        let detached = JwsSignOptions {
+            detached: true,
+            ..compact
+        };
+        let jws = sign_jws("a.b", &[signer], &detached).unwrap();
+        assert!(jws.contains(".."));
+        assert!(verify_jws(
+            &jws,
+            Some("a.b"),
+            "webhook-secret",
+            Some(&[Algorithm::HS256])
+        )
+        .is_ok());
+        assert!(matches!(
+            verify_jws(
+                &jws,
+                Some("a.b"),
+                "webhook-secret",
+                Some(&[Algorithm::RS256])
+            ),
+            Err(JwtError::AlgorithmNotAllowed(_))
+        ));
+    }
+}
@@ -0,0 +1,300 @@
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use ed25519_dalek::SigningKey;
+use jsonwebtoken::Algorithm;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use rand::rngs::OsRng;
+use rsa::traits::{PrivateKeyParts, PublicKeyParts};
+use rsa::RsaPrivateKey;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use crate::jwt_error::JwtError;
+use crate::jwt_keys::{to_pem, KeyFamily};
+
+/// 支持的 RSA 密钥长度
+const RSA_BITS: [usize; 3] = [2048, 3072, 4096];
+
+/// 生成的密钥对，私钥为 PKCS#8、公钥为 SPKI，可直接用于签名和验证命令
+#[derive(Serialize, Clone, Debug)]
+pub struct JwtKeyPair {
+    pub alg: String,
+    pub kid: String, // RFC 7638 JWK 指纹
+    pub private_pem: String,
+    pub public_pem: String,
+    pub private_der: String, // base64
+    pub public_der: String,  // base64
+    pub private_jwk: Value,
+    pub public_jwk: Value,
+}
+
+/// 生成过程中得到的原始材料，JWK 成员均为 base64url 编码
+struct KeyMaterial {
+    private_der: Vec<u8>,
+    public_der: Vec<u8>,
+    public_members: BTreeMap<&'static str, String>, // 计算指纹所需的成员
+    private_members: Vec<(&'static str, String)>,
+}
+
+/// 按签名算法生成密钥对；`bits` 只对 RSA 有效，默认 2048
+pub fn generate_keypair(alg: Algorithm, bits: Option<usize>) -> Result<JwtKeyPair, JwtError> {
+    let material = match (KeyFamily::of(alg), alg) {
+        (KeyFamily::Rsa, _) => rsa_keypair(bits.unwrap_or(2048))?,
+        (KeyFamily::Ec, Algorithm::ES256) => p256_keypair()?,
+        (KeyFamily::Ec, _) => p384_keypair()?,
+        (KeyFamily::Ed, _) => ed25519_keypair()?,
+        (KeyFamily::Hmac, _) => {
+            return Err(JwtError::KeyGeneration(format!(
+                "{:?} 使用共享密钥，不需要生成密钥对",
+                alg
+            )))
+        }
+    };
+
+    let kid = thumbprint(&material.public_members);
+    let alg = format!("{:?}", alg);
+    let mut public_jwk: Map<String, Value> = material
+        .public_members
+        .iter()
+        .map(|(name, value)| (name.to_string(), Value::from(value.as_str())))
+        .collect();
+    public_jwk.insert("kid".to_string(), Value::from(kid.as_str()));
+    public_jwk.insert("alg".to_string(), Value::from(alg.as_str()));
+    public_jwk.insert("use".to_string(), Value::from("sig"));
+    let mut private_jwk = public_jwk.clone();
+    for (name, value) in material.private_members {
+        private_jwk.insert(name.to_string(), Value::from(value));
+    }
+
+    Ok(JwtKeyPair {
+        alg,
+        kid,
+        private_pem: to_pem("PRIVATE KEY", &material.private_der),
+        public_pem: to_pem("PUBLIC KEY", &material.public_der),
+        private_der: STANDARD.encode(&material.private_der),
+        public_der: STANDARD.encode(&material.public_der),
+        private_jwk: Value::Object(private_jwk),
+        public_jwk: Value::Object(public_jwk),
+    })
+}
+
+/// RFC 7638：必需成员按字典序、无空白地序列化后取 SHA-256
+fn thumbprint(members: &BTreeMap<&'static str, String>) -> String {
+    let body: Vec<String> = members
+        .iter()
+        .map(|(name, value)| format!("\"{}\":\"{}\"", name, value))
+        .collect();
+    let canonical = format!("{{{}}}", body.join(","));
+    URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+}
+
+/// 计算任意公钥 JWK 的 RFC 7638 指纹，缺少必需成员时返回 None
+pub fn jwk_thumbprint(jwk: &Value) -> Option<String> {
+    let required: &[&'static str] = match jwk.get("kty")?.as_str()? {
+        "RSA" => &["e", "kty", "n"],
+        "EC" => &["crv", "kty", "x", "y"],
+        "OKP" => &["crv", "kty", "x"],
+        "oct" => &["k", "kty"],
+        _ => return None,
+    };
+    let members = required
+        .iter()
+        .map(|name| Some((*name, jwk.get(*name)?.as_str()?.to_string())))
+        .collect::<Option<BTreeMap<_, _>>>()?;
+    Some(thumbprint(&members))
+}
+
+fn b64(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn generation_error(e: impl std::fmt::Display) -> JwtError {
+    JwtError::KeyGeneration(e.to_string())
+}
+
+fn rsa_keypair(bits: usize) -> Result<KeyMaterial, JwtError> {
+    use rsa::pkcs8::{EncodePrivateKey, EncodePublicKey};
+
+    if !RSA_BITS.contains(&bits) {
+        return Err(JwtError::KeyGeneration(format!(
+            "RSA 密钥长度只支持 {:?}",
+            RSA_BITS
+        )));
+    }
+    let key = RsaPrivateKey::new(&mut OsRng, bits).map_err(generation_error)?;
+    let public = key.to_public_key();
+    let uint = |n: &rsa::BigUint| b64(&n.to_bytes_be());
+
+    let mut private_members = vec![("d", uint(key.d()))];
+    if let [p, q] = key.primes() {
+        private_members.push(("p", uint(p)));
+        private_members.push(("q", uint(q)));
+    }
+    if let (Some(dp), Some(dq), Some(qi)) = (key.dp(), key.dq(), key.crt_coefficient()) {
+        private_members.push(("dp", uint(dp)));
+        private_members.push(("dq", uint(dq)));
+        private_members.push(("qi", uint(&qi)));
+    }
+
+    Ok(KeyMaterial {
+        private_der: key
+            .to_pkcs8_der()
+            .map_err(generation_error)?
+            .as_bytes()
+            .to_vec(),
+        public_der: public
+            .to_public_key_der()
+            .map_err(generation_error)?
+            .into_vec(),
+        public_members: BTreeMap::from([
+            ("kty", "RSA".to_string()),
+            ("n", uint(public.n())),
+            ("e", uint(public.e())),
+        ]),
+        private_members,
+    })
+}
+
+fn p256_keypair() -> Result<KeyMaterial, JwtError> {
+    use p256::pkcs8::{EncodePrivateKey, EncodePublicKey};
+
+    let key = p256::SecretKey::random(&mut OsRng);
+    let public = key.public_key();
+    let point = public.to_encoded_point(false);
+    Ok(KeyMaterial {
+        private_der: key
+            .to_pkcs8_der()
+            .map_err(generation_error)?
+            .as_bytes()
+            .to_vec(),
+        public_der: public
+            .to_public_key_der()
+            .map_err(generation_error)?
+            .into_vec(),
+        public_members: ec_members(
+            "P-256",
+            point.x().map(|x| x.as_slice()),
+            point.y().map(|y| y.as_slice()),
+        ),
+        private_members: vec![("d", b64(&key.to_bytes()))],
+    })
+}
+
+fn p384_keypair() -> Result<KeyMaterial, JwtError> {
+    use p384::pkcs8::{EncodePrivateKey, EncodePublicKey};
+
+    let key = p384::SecretKey::random(&mut OsRng);
+    let public = key.public_key();
+    let point = public.to_encoded_point(false);
+    Ok(KeyMaterial {
+        private_der: key
+            .to_pkcs8_der()
+            .map_err(generation_error)?
+            .as_bytes()
+            .to_vec(),
+        public_der: public
+            .to_public_key_der()
+            .map_err(generation_error)?
+            .into_vec(),
+        public_members: ec_members(
+            "P-384",
+            point.x().map(|x| x.as_slice()),
+            point.y().map(|y| y.as_slice()),
+        ),
+        private_members: vec![("d", b64(&key.to_bytes()))],
+    })
+}
+
+/// 非压缩点一定带有 x、y 坐标
+fn ec_members(curve: &str, x: Option<&[u8]>, y: Option<&[u8]>) -> BTreeMap<&'static str, String> {
+    BTreeMap::from([
+        ("kty", "EC".to_string()),
+        ("crv", curve.to_string()),
+        ("x", b64(x.unwrap_or_default())),
+        ("y", b64(y.unwrap_or_default())),
+    ])
+}
+
+fn ed25519_keypair() -> Result<KeyMaterial, JwtError> {
+    use ed25519_dalek::pkcs8::{EncodePrivateKey, EncodePublicKey};
+
+    let key = SigningKey::generate(&mut OsRng);
+    let public = key.verifying_key();
+    Ok(KeyMaterial {
+        private_der: key
+            .to_pkcs8_der()
+            .map_err(generation_error)?
+            .as_bytes()
+            .to_vec(),
+        public_der: public
+            .to_public_key_der()
+            .map_err(generation_error)?
+            .into_vec(),
+        public_members: BTreeMap::from([
+            ("kty", "OKP".to_string()),
+            ("crv", "Ed25519".to_string()),
+            ("x", b64(public.as_bytes())),
+        ]),
+        private_members: vec![("d", b64(key.as_bytes()))],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jwt_keys::signing_key;
+    use crate::jwt_verify::{verify_with_key, ClaimChecks};
+    use jsonwebtoken::{encode, Header};
+
+    #[test]
+    fn rfc7638_thumbprint() {
+        // RFC 7638 第 3.1 节的示例
+        let members = BTreeMap::from([
+            ("kty", "RSA".to_string()),
+            ("e", "AQAB".to_string()),
+            ("n", "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw".to_string()),
+        ]);
+        assert_eq!(
+            thumbprint(&members),
+            "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"
+        );
+    }
+
+    #[test]
+    fn generated_keys_sign_and_verify() {
+        let claims = serde_json::json!({ "sub": "x" });
+        for alg in [
+            Algorithm::RS256,
+            Algorithm::ES256,
+            Algorithm::ES384,
+            Algorithm::EdDSA,
+        ] {
+            let pair = generate_keypair(alg, None).unwrap();
+            assert_eq!(pair.public_jwk["kid"], pair.kid);
+            assert_eq!(jwk_thumbprint(&pair.public_jwk), Some(pair.kid.clone()));
+
+            let mut header = Header::new(alg);
+            header.kid = Some(pair.kid.clone());
+            let token = encode(
+                &header,
+                &claims,
+                &signing_key(&pair.private_pem, alg).unwrap(),
+            );
+            let token = token.unwrap();
+            let checks = ClaimChecks::default();
+            let jwks = serde_json::json!({ "keys": [pair.public_jwk] }).to_string();
+            for key in [&pair.public_pem, &pair.public_der, &jwks] {
+                assert!(
+                    verify_with_key(&token, key, None, &checks).is_ok(),
+                    "{:?}",
+                    alg
+                );
+            }
+            assert!(signing_key(&pair.private_der, alg).is_ok());
+        }
+        assert!(generate_keypair(Algorithm::HS256, None).is_err());
+        assert!(generate_keypair(Algorithm::RS256, Some(1024)).is_err());
+    }
+}
@@ -0,0 +1,338 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::jwt_error::JwtError;
+use crate::jwt_inspect::inspect_token;
+use crate::jwt_keygen::jwk_thumbprint;
+
+/// 密钥库文件名，保存在应用数据目录中
+const STORE_FILE: &str = "jwt_keys.json";
+
+/// JWK 中的私有成员，导入时去掉，密钥库只保存公钥
+const PRIVATE_MEMBERS: [&str; 7] = ["d", "p", "q", "dp", "dq", "qi", "oth"];
+
+/// 密钥库中的一个验证密钥
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoredKey {
+    pub kid: String, // JWK 没有 kid 时使用 RFC 7638 指纹
+    pub issuer: Option<String>,
+    pub kty: String,
+    pub alg: Option<String>,
+    pub jwk: Value,
+    pub source: String, // 导入的文件路径
+    pub imported_at: i64,
+}
+
+/// 通过 OpenID 发现文档登记的签发者
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoredIssuer {
+    pub issuer: String,
+    pub jwks_uri: Option<String>,
+    pub source: String,
+}
+
+/// 持久化的密钥库，按 (issuer, kid) 唯一
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct KeyStore {
+    pub issuers: Vec<StoredIssuer>,
+    pub keys: Vec<StoredKey>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportedKind {
+    Jwks,
+    Jwk,
+    Discovery,
+}
+
+/// 一次导入的结果
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportSummary {
+    pub kind: ImportedKind,
+    pub issuer: Option<String>,
+    pub imported: usize,
+    pub replaced: usize,          // 替换了已有的同 issuer、同 kid 的密钥
+    pub skipped: usize,           // 对称密钥（oct）、用于加密（use=enc）或无法识别的密钥
+    pub jwks_uri: Option<String>, // 发现文档没有内嵌 jwks 时，需要另外导入该地址的 JWKS 文件
+}
+
+impl KeyStore {
+    /// 读取密钥库文件，不存在时返回空库
+    pub fn load(path: &Path) -> Result<Self, JwtError> {
+        if !path.exists() {
+            return Ok(KeyStore::default());
+        }
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| JwtError::KeyStore(format!("读取 {} 失败: {}", path.display(), e)))?;
+        serde_json::from_str(&text)
+            .map_err(|e| JwtError::KeyStore(format!("{} 格式错误: {}", path.display(), e)))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), JwtError> {
+        let write = || -> std::io::Result<()> {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let text = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+            std::fs::write(path, text)
+        };
+        write().map_err(|e| JwtError::KeyStore(format!("写入 {} 失败: {}", path.display(), e)))
+    }
+
+    /// 导入 JWKS、单个 JWK 或 OpenID 发现文档的 JSON 文本
+    ///
+    /// 发现文档提供 issuer，内嵌的 `jwks` 会一并导入；`issuer` 参数优先于文档中的值。
+    pub fn import(
+        &mut self,
+        text: &str,
+        source: &str,
+        issuer: Option<&str>,
+    ) -> Result<ImportSummary, JwtError> {
+        let document: Value = serde_json::from_str(text)
+            .map_err(|e| JwtError::InvalidKey(format!("JSON格式错误: {}", e)))?;
+
+        let (kind, keys) = if let Some(keys) = document.get("keys") {
+            (ImportedKind::Jwks, keys.as_array().cloned())
+        } else if document.get("kty").is_some() {
+            (ImportedKind::Jwk, Some(vec![document.clone()]))
+        } else if document.get("issuer").is_some() || document.get("jwks_uri").is_some() {
+            let keys = document
+                .pointer("/jwks/keys")
+                .and_then(Value::as_array)
+                .cloned();
+            (ImportedKind::Discovery, keys.or_else(|| Some(Vec::new())))
+        } else {
+            (ImportedKind::Jwks, None)
+        };
+        let keys = keys
+            .ok_or_else(|| JwtError::InvalidKey("不是 JWKS、JWK 或 OpenID 发现文档".to_string()))?;
+
+        let issuer = issuer
+            .map(str::to_string)
+            .or_else(|| text_member(&document, "issuer"));
+        let jwks_uri = text_member(&document, "jwks_uri");
+        if kind == ImportedKind::Discovery {
+            let issuer = issuer
+                .clone()
+                .ok_or_else(|| JwtError::InvalidKey("发现文档缺少 issuer".to_string()))?;
+            self.issuers.retain(|known| known.issuer != issuer);
+            self.issuers.push(StoredIssuer {
+                issuer,
+                jwks_uri: jwks_uri.clone(),
+                source: source.to_string(),
+            });
+        }
+
+        let mut summary = ImportSummary {
+            kind,
+            issuer: issuer.clone(),
+            imported: 0,
+            replaced: 0,
+            skipped: 0,
+            jwks_uri: jwks_uri.filter(|_| keys.is_empty()),
+        };
+        let imported_at = Utc::now().timestamp();
+        for jwk in keys {
+            let Some(key) = stored_key(jwk, issuer.clone(), source, imported_at) else {
+                summary.skipped += 1;
+                continue;
+            };
+            let before = self.keys.len();
+            self.keys
+                .retain(|known| !(known.issuer == key.issuer && known.kid == key.kid));
+            if self.keys.len() < before {
+                summary.replaced += 1;
+            }
+            self.keys.push(key);
+            summary.imported += 1;
+        }
+        Ok(summary)
+    }
+
+    /// 删除指定的密钥，返回是否有密钥被删除；`issuer` 为空时删除所有签发者下该 kid 的密钥
+    pub fn remove(&mut self, kid: &str, issuer: Option<&str>) -> bool {
+        let before = self.keys.len();
+        self.keys.retain(|key| {
+            key.kid != kid || issuer.is_some_and(|issuer| key.issuer.as_deref() != Some(issuer))
+        });
+        self.keys.len() < before
+    }
+
+    /// 按令牌标头的 kid 和载荷的 iss 挑选候选密钥，组成 JWKS 文本交给验证流程
+    ///
+    /// 登记了 iss 的密钥只用于该签发者的令牌，没有 issuer 的密钥可用于任何令牌。
+    pub fn select(&self, token: &str) -> Result<String, JwtError> {
+        let inspection = inspect_token(token, None)?;
+        let kid = inspection.header.get("kid").and_then(Value::as_str);
+        let iss = inspection.payload.get("iss").and_then(Value::as_str);
+
+        let candidates: Vec<&Value> = self
+            .keys
+            .iter()
+            .filter(|key| key.issuer.is_none() || key.issuer.as_deref() == iss)
+            .filter(|key| kid.is_none_or(|kid| key.kid == kid))
+            .map(|key| &key.jwk)
+            .collect();
+        match (candidates.is_empty(), kid) {
+            (true, Some(kid)) => Err(JwtError::KeyNotFound(kid.to_string())),
+            (true, None) => Err(JwtError::InvalidKey(match iss {
+                Some(iss) => format!("密钥库中没有签发者 {} 的密钥", iss),
+                None => "密钥库中没有可用的密钥".to_string(),
+            })),
+            (false, _) => Ok(json!({ "keys": candidates }).to_string()),
+        }
+    }
+}
+
+fn text_member(value: &Value, name: &str) -> Option<String> {
+    value.get(name).and_then(Value::as_str).map(str::to_string)
+}
+
+/// 只保留可用于验证签名的公钥成员；加密密钥和无法计算指纹的密钥返回 None
+///
+/// 对称密钥（oct）的 `k` 就是共享密钥本身，不能明文写入密钥库，也返回 None。
+fn stored_key(
+    mut jwk: Value,
+    issuer: Option<String>,
+    source: &str,
+    imported_at: i64,
+) -> Option<StoredKey> {
+    if jwk.get("use").and_then(Value::as_str) == Some("enc")
+        || jwk.get("kty").and_then(Value::as_str) == Some("oct")
+    {
+        return None;
+    }
+    let thumbprint = jwk_thumbprint(&jwk)?;
+    let object = jwk.as_object_mut()?;
+    for name in PRIVATE_MEMBERS {
+        object.remove(name);
+    }
+    let kid = match text_member(&jwk, "kid") {
+        Some(kid) => kid,
+        None => {
+            jwk["kid"] = Value::from(thumbprint.as_str());
+            thumbprint
+        }
+    };
+    Some(StoredKey {
+        kid,
+        issuer,
+        kty: text_member(&jwk, "kty")?,
+        alg: text_member(&jwk, "alg"),
+        jwk,
+        source: source.to_string(),
+        imported_at,
+    })
+}
+
+/// 密钥库文件的位置；应用数据目录不可用时返回错误
+pub fn store_path(app: &AppHandle) -> Result<PathBuf, JwtError> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(STORE_FILE))
+        .map_err(|e| JwtError::KeyStore(format!("无法确定应用数据目录: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jwt_keygen::generate_keypair;
+    use crate::jwt_verify::{verify_with_key, ClaimChecks};
+    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+
+    fn token(kid: &str, iss: &str, private_pem: &str) -> String {
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(kid.to_string());
+        let key = EncodingKey::from_ec_pem(private_pem.as_bytes()).unwrap();
+        encode(&header, &json!({ "iss": iss, "sub": "x" }), &key).unwrap()
+    }
+
+    #[test]
+    fn imports_discovery_and_selects_by_issuer_and_kid() {
+        let pair = generate_keypair(Algorithm::ES256, None).unwrap();
+        let other = generate_keypair(Algorithm::ES256, None).unwrap();
+        let mut store = KeyStore::default();
+
+        let discovery = json!({
+            "issuer": "https://idp.example.com",
+            "jwks_uri": "https://idp.example.com/jwks",
+        });
+        let summary = store
+            .import(&discovery.to_string(), "discovery.json", None)
+            .unwrap();
+        assert_eq!(summary.kind, ImportedKind::Discovery);
+        assert_eq!(
+            summary.jwks_uri.as_deref(),
+            Some("https://idp.example.com/jwks")
+        );
+
+        // 私钥 JWK 导入后只保留公钥成员，对称密钥和加密密钥不导入
+        let jwks = json!({ "keys": [
+            pair.private_jwk,
+            { "kty": "RSA", "use": "enc" },
+            { "kty": "oct", "kid": "hmac", "k": "c2VjcmV0" },
+        ] });
+        let summary = store
+            .import(
+                &jwks.to_string(),
+                "jwks.json",
+                Some("https://idp.example.com"),
+            )
+            .unwrap();
+        assert_eq!((summary.imported, summary.skipped), (1, 2));
+        assert!(store.keys[0].jwk.get("d").is_none());
+        assert!(!serde_json::to_string(&store).unwrap().contains("c2VjcmV0"));
+        let summary = store
+            .import(
+                &jwks.to_string(),
+                "jwks.json",
+                Some("https://idp.example.com"),
+            )
+            .unwrap();
+        assert_eq!(summary.replaced, 1);
+        store
+            .import(
+                &other.public_jwk.to_string(),
+                "other.json",
+                Some("https://other.example.com"),
+            )
+            .unwrap();
+
+        let jwt = token(&pair.kid, "https://idp.example.com", &pair.private_pem);
+        let key = store.select(&jwt).unwrap();
+        assert!(verify_with_key(&jwt, &key, None, &ClaimChecks::default()).is_ok());
+
+        // 同一 kid 但签发者不符时找不到密钥
+        let forged = token(&pair.kid, "https://other.example.com", &pair.private_pem);
+        assert!(matches!(
+            store.select(&forged),
+            Err(JwtError::KeyNotFound(_))
+        ));
+
+        assert!(store.remove(&pair.kid, None));
+        assert!(store.select(&jwt).is_err());
+    }
+
+    #[test]
+    fn persists_to_file() {
+        let pair = generate_keypair(Algorithm::ES256, None).unwrap();
+        let path = std::env::temp_dir()
+            .join(format!("jwt-keystore-{}", std::process::id()))
+            .join(STORE_FILE);
+        assert!(KeyStore::load(&path).unwrap().keys.is_empty());
+
+        let mut store = KeyStore::default();
+        store
+            .import(&pair.public_jwk.to_string(), "key.json", None)
+            .unwrap();
+        store.save(&path).unwrap();
+        let loaded = KeyStore::load(&path).unwrap();
+        assert_eq!(loaded.keys[0].kid, pair.kid);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
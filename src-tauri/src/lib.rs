@@ -1,10 +1,30 @@
 mod base64;
 mod ble;
+mod ble_backend;
+mod ble_beacon;
+mod ble_codec;
+mod ble_decoders;
+mod ble_dfu;
+mod ble_error;
+mod ble_link;
+mod ble_schema;
+mod ble_sequence;
+mod ble_session;
+mod ble_sim;
+mod ble_uuid;
+mod error;
 mod jwt;
-mod jwt_encoder;
+mod jwt_error;
+mod jwt_inspect;
+mod jwt_jwe;
+mod jwt_jws;
+mod jwt_keygen;
+mod jwt_keys;
+mod jwt_keystore;
+mod jwt_verify;
 mod logcat;
+mod startup;
 
-use tauri::Manager;
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -16,24 +36,12 @@ fn greet(name: &str) -> String {
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .manage(ble::BleState::from_env())
+        .manage(startup::StartupState::default())
         .setup(|app| {
-            let splash_window = app.get_webview_window("splashscreen").unwrap();
-            let main_window = app.get_webview_window("main").unwrap();
-
-            // 监听应用准备就绪事件
+            // 启动页显示期间执行初始化任务，完成后关闭启动页并显示主窗口
             let app_handle = app.handle().clone();
-            tauri::async_runtime::spawn(async move {
-                // 模拟应用初始化时间
-                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-
-                // 关闭启动页面并显示主窗口
-                if let Some(splash) = app_handle.get_webview_window("splashscreen") {
-                    splash.close().unwrap();
-                }
-                if let Some(main) = app_handle.get_webview_window("main") {
-                    main.show().unwrap();
-                }
-            });
+            tauri::async_runtime::spawn(startup::Startup::with_default_tasks().run(app_handle));
 
             Ok(())
         })
@@ -43,19 +51,50 @@ pub fn run() {
             base64::decode_base64_text,
             base64::validate_base64_image,
             base64::get_image_info,
-            jwt_encoder::decode_jwt_token,
-            jwt_encoder::encode_jwt_token,
-            jwt_encoder::validate_jwt_token,
-            jwt_encoder::decode_jwt_token_generic,
-            jwt_encoder::create_demo_jwt_token,
-            jwt_encoder::encode_hs256_token,
+            jwt::decode_jwt_token,
+            jwt::encode_jwt_token,
+            jwt::validate_jwt_token,
+            jwt::inspect_jwt_token,
+            jwt::verify_jwt_token,
+            jwt::decrypt_jwe_token,
+            jwt::encrypt_jwe_token,
+            jwt::inspect_jws_token,
+            jwt::verify_jws_token,
+            jwt::sign_jws_token,
+            jwt::generate_jwt_keypair,
+            jwt::import_jwt_keys,
+            jwt::list_jwt_keys,
+            jwt::remove_jwt_key,
+            jwt::decode_jwt_token_generic,
+            jwt::create_demo_jwt_token,
+            jwt::encode_hs256_token,
             jwt::encode_jwt,
             jwt::decode_jwt,
             logcat::list_devices,
             logcat::start_logcat,
+            startup::get_startup_report,
+            startup::get_startup_progress,
             ble::scan_devices,
             ble::connect_device,
-            // ble::disconnect_device,
+            ble::get_gatt_services,
+            ble::read_characteristic,
+            ble::write_characteristic,
+            ble::subscribe_characteristic,
+            ble::unsubscribe_characteristic,
+            ble::disconnect_device,
+            ble::list_connected_devices,
+            ble::get_ble_backend,
+            ble::load_ble_schema,
+            ble::clear_ble_schemas,
+            ble::run_ble_sequence,
+            ble::get_ble_session,
+            ble::clear_ble_session,
+            ble::export_ble_session,
+            ble::replay_ble_session,
+            ble::get_link_info,
+            ble::run_throughput_test,
+            ble::start_ble_transfer,
+            ble::cancel_ble_transfer,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
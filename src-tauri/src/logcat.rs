@@ -1,12 +1,89 @@
 use regex::Regex;
-use serde::Serialize;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader};
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, Command, Output, Stdio};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use tauri::Window;
 use tauri::{command, window, Emitter, Manager};
+use thiserror::Error;
+
+use crate::error::{serialize_error, ErrorCode};
+
+/// logcat 命令的错误类型，序列化为 `{ code, message, details }`
+#[derive(Debug, Clone, Error)]
+pub enum LogcatError {
+    /// PATH 中找不到 adb 可执行文件
+    #[error("未找到 adb，请安装 Android platform-tools 并加入 PATH")]
+    AdbNotFound,
+    #[error("设备 {0} 未连接")]
+    DeviceNotFound(String),
+    #[error("adb {command} 执行失败: {message}")]
+    AdbFailed { command: String, message: String },
+}
+
+impl ErrorCode for LogcatError {
+    fn code(&self) -> &'static str {
+        match self {
+            LogcatError::AdbNotFound => "adb_not_found",
+            LogcatError::DeviceNotFound(_) => "device_not_found",
+            LogcatError::AdbFailed { .. } => "adb_failed",
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            LogcatError::DeviceNotFound(serial) => Some(json!({ "serial": serial })),
+            LogcatError::AdbFailed { command, .. } => Some(json!({ "command": command })),
+            LogcatError::AdbNotFound => None,
+        }
+    }
+}
+
+impl Serialize for LogcatError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_error(self, serializer)
+    }
+}
+
+/// 将启动 adb 失败转换为错误，找不到可执行文件时单独区分
+fn spawn_error(command: &str, e: std::io::Error) -> LogcatError {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        LogcatError::AdbNotFound
+    } else {
+        LogcatError::AdbFailed {
+            command: command.to_string(),
+            message: e.to_string(),
+        }
+    }
+}
+
+/// 执行 adb 命令并等待结束，非零退出码视为失败
+fn run_adb(serial: Option<&str>, args: &[&str]) -> Result<Output, LogcatError> {
+    let command = args.join(" ");
+    let mut adb = Command::new("adb");
+    if let Some(serial) = serial {
+        adb.args(["-s", serial]);
+    }
+    let output = adb.args(args).output().map_err(|e| spawn_error(&command, e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(match serial {
+            // adb: device 'xxx' not found；只匹配这一句，避免误判 "more than one device" 等其他错误
+            Some(serial) if stderr.contains(&format!("device '{}' not found", serial)) => {
+                LogcatError::DeviceNotFound(serial.to_string())
+            }
+            _ => LogcatError::AdbFailed {
+                command,
+                message: stderr,
+            },
+        });
+    }
+    Ok(output)
+}
 
 #[derive(Serialize)]
 pub struct DeviceInfo {
@@ -18,11 +95,19 @@ pub struct DeviceInfo {
 
 // remember to call `.manage(MyState::default())`
 #[tauri::command]
-pub async fn list_devices() -> Result<Vec<DeviceInfo>, String> {
-    let output = Command::new("adb")
-        .arg("devices")
-        .output()
-        .map_err(|e| format!("adb devices fail: {}", e))?;
+pub async fn list_devices() -> Result<Vec<DeviceInfo>, LogcatError> {
+    // 每台设备要执行多次 adb shell，全部放到阻塞线程池中执行
+    tokio::task::spawn_blocking(connected_devices)
+        .await
+        .map_err(|e| LogcatError::AdbFailed {
+            command: "devices".to_string(),
+            message: e.to_string(),
+        })?
+}
+
+/// 已连接设备及其型号、系统版本
+fn connected_devices() -> Result<Vec<DeviceInfo>, LogcatError> {
+    let output = run_adb(None, &["devices"])?;
 
     let stdout = String::from_utf8_lossy(&output.stdout);
     let mut devices = vec![];
@@ -51,90 +136,103 @@ pub async fn list_devices() -> Result<Vec<DeviceInfo>, String> {
     Ok(devices)
 }
 
+/// adb 版本号（`adb version` 输出的第一行），用于启动时检测 adb 是否可用
+pub fn adb_version() -> Result<String, LogcatError> {
+    let output = run_adb(None, &["version"])?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string())
+}
+
 fn get_prop(serial: &str, key: &str) -> Option<String> {
-    let output = Command::new("adb")
-        .arg("-s")
-        .arg(serial)
-        .arg("shell")
-        .arg("getprop")
-        .arg(key)
-        .output()
-        .ok()?;
+    let output = run_adb(Some(serial), &["shell", "getprop", key]).ok()?;
     Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
 #[tauri::command]
-pub async fn start_logcat(window: tauri::Window, serial: String) -> Result<(), String> {
+pub async fn start_logcat(window: tauri::Window, serial: String) -> Result<(), LogcatError> {
+    // 先查询一次进程表，adb 缺失或设备未连接时直接返回错误；adb 调用会阻塞，放到阻塞线程池中执行
+    let initial = {
+        let serial = serial.clone();
+        tokio::task::spawn_blocking(move || get_pid_package_map(&serial))
+            .await
+            .map_err(|e| LogcatError::AdbFailed {
+                command: "shell ps".to_string(),
+                message: e.to_string(),
+            })??
+    };
+    let pid_map = Arc::new(Mutex::new(initial));
+    let mut child = Command::new("adb")
+        .args(["-s", &serial, "logcat", "-v", "threadtime"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| spawn_error("logcat", e))?;
+
     thread::spawn(move || {
-        let pid_map = Arc::new(Mutex::new(get_pid_package_map(&serial)));
-
-        let child = Command::new("adb")
-            .args(&["-s", &serial, "logcat", "-v", "threadtime"])
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .spawn();
-
-        if let Ok(mut child) = child {
-            if let Some(stdout) = child.stdout.take() {
-                let reader = BufReader::new(stdout);
-
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        println!("{}", &line);
-
-                        // 用正则提取字段
-                        let re = regex::Regex::new(
-                            r"(?x)
-                                ^(\d{2}-\d{2}\s+\d{2}:\d{2}:\d{2}\.\d{3}) # timestamp
-                                \s+(\d+)\s+(\d+)                          # pid, tid
-                                \s+([VDIWEF])\s+                          # level
-                                (\S+):\s+(.*)$                            # tag: message
-                            ",
-                        )
-                        .unwrap();
-
-                        if let Some(caps) = re.captures(&line) {
-                            let timestamp = caps.get(1).unwrap().as_str();
-                            let pid = caps.get(2).unwrap().as_str().to_string();
-                            let tid = caps.get(3).unwrap().as_str();
-                            let level = caps.get(4).unwrap().as_str();
-                            let tag = caps.get(5).unwrap().as_str();
-                            let message = caps.get(6).unwrap().as_str();
-
-                            let pkg = {
-                                let map = pid_map.lock().unwrap();
-                                map.get(&pid).cloned()
-                            };
-
-                            let package_name = if let Some(pkg) = pkg {
-                                pkg
-                            } else {
-                                // 如果没找到，刷新一次 map
-                                let new_map = get_pid_package_map(&serial);
-                                let mut map = pid_map.lock().unwrap();
-                                *map = new_map;
-                                map.get(&pid).cloned().unwrap_or_else(|| "".to_string())
-                            };
-
-                            // 构建格式化行（注意字段对齐）
-                            let formatted = format!(
-                                "<span style='color: rgb(183,197,219); margin-right: 10px'>{:<23}</span>  
-                                <span style='display: inline-block; width: 80px; color: rgb(183,197,219); margin-right: 10px'>{:>5}-{:>5}</span>  
-                                <span style='width: 250px; text-overflow: ellipsis; white-space: nowrap; overflow: hidden; color: rgb(0, 183, 247)'; margin-right: 10px>{:<25}</span> 
-                                <span style='width: 450px; text-overflow: ellipsis; white-space: nowrap; overflow: hidden; color: rgb(0, 183, 247)'; margin-right: 10px>{:<35}</span> 
-                                <span style='display: inline-block; background:rgb(183,197,219); width: 20px; text-align: center; color: red'>{:<1}</span>
-                                <span style='colro: rgb(0, 107, 108)'>{}</span>",
-                                timestamp, pid, tid, tag, package_name, level, message
-                            );
-
-                            // 发给前端
-                            let _ = window.emit("logcat-line", formatted);
-                        }
+        if let Some(stdout) = child.stdout.take() {
+            let reader = BufReader::new(stdout);
+
+            for line in reader.lines() {
+                if let Ok(line) = line {
+                    println!("{}", &line);
+
+                    // 用正则提取字段
+                    let re = regex::Regex::new(
+                        r"(?x)
+                            ^(\d{2}-\d{2}\s+\d{2}:\d{2}:\d{2}\.\d{3}) # timestamp
+                            \s+(\d+)\s+(\d+)                          # pid, tid
+                            \s+([VDIWEF])\s+                          # level
+                            (\S+):\s+(.*)$                            # tag: message
+                        ",
+                    )
+                    .unwrap();
+
+                    if let Some(caps) = re.captures(&line) {
+                        let timestamp = caps.get(1).unwrap().as_str();
+                        let pid = caps.get(2).unwrap().as_str().to_string();
+                        let tid = caps.get(3).unwrap().as_str();
+                        let level = caps.get(4).unwrap().as_str();
+                        let tag = caps.get(5).unwrap().as_str();
+                        let message = caps.get(6).unwrap().as_str();
+
+                        let pkg = {
+                            let map = pid_map.lock().unwrap();
+                            map.get(&pid).cloned()
+                        };
+
+                        let package_name = if let Some(pkg) = pkg {
+                            pkg
+                        } else {
+                            // 如果没找到，刷新一次 map；先在锁外执行 adb，再替换
+                            let refreshed = get_pid_package_map(&serial);
+                            let mut map = pid_map.lock().unwrap();
+                            match refreshed {
+                                Ok(new_map) => *map = new_map,
+                                Err(e) => eprintln!("Failed to refresh pid map: {}", e),
+                            }
+                            map.get(&pid).cloned().unwrap_or_else(|| "".to_string())
+                        };
+
+                        // 构建格式化行（注意字段对齐）
+                        let formatted = format!(
+                            "<span style='color: rgb(183,197,219); margin-right: 10px'>{:<23}</span>  
+                            <span style='display: inline-block; width: 80px; color: rgb(183,197,219); margin-right: 10px'>{:>5}-{:>5}</span>  
+                            <span style='width: 250px; text-overflow: ellipsis; white-space: nowrap; overflow: hidden; color: rgb(0, 183, 247)'; margin-right: 10px>{:<25}</span> 
+                            <span style='width: 450px; text-overflow: ellipsis; white-space: nowrap; overflow: hidden; color: rgb(0, 183, 247)'; margin-right: 10px>{:<35}</span> 
+                            <span style='display: inline-block; background:rgb(183,197,219); width: 20px; text-align: center; color: red'>{:<1}</span>
+                            <span style='colro: rgb(0, 107, 108)'>{}</span>",
+                            timestamp, pid, tid, tag, package_name, level, message
+                        );
+
+                        // 发给前端
+                        let _ = window.emit("logcat-line", formatted);
                     }
                 }
             }
-        } else {
-            let _ = window.emit("logcat-error", "无法启动 adb logcat");
         }
     });
 
@@ -142,11 +240,8 @@ pub async fn start_logcat(window: tauri::Window, serial: String) -> Result<(), S
 }
 
 /// 查询设备上的进程列表，返回 PID → 包名 的映射表
-fn get_pid_package_map(serial: &str) -> HashMap<String, String> {
-    let output = Command::new("adb")
-        .args(&["-s", serial, "shell", "ps"])
-        .output()
-        .expect("Failed to run adb shell ps");
+fn get_pid_package_map(serial: &str) -> Result<HashMap<String, String>, LogcatError> {
+    let output = run_adb(Some(serial), &["shell", "ps"])?;
 
     let stdout = String::from_utf8_lossy(&output.stdout);
     let mut map = HashMap::new();
@@ -162,5 +257,5 @@ fn get_pid_package_map(serial: &str) -> HashMap<String, String> {
         }
     }
 
-    map
+    Ok(map)
 }
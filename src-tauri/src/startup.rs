@@ -0,0 +1,548 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+use thiserror::Error;
+
+use crate::ble::BleState;
+use crate::ble_error::BleError;
+use crate::error::{serialize_error, ErrorCode};
+use crate::logcat::{adb_version, LogcatError};
+
+/// 启动页窗口的标签，与 tauri.conf.json 一致
+const SPLASH_WINDOW: &str = "splashscreen";
+const MAIN_WINDOW: &str = "main";
+/// 单个启动任务的最长执行时间，超时视为失败并继续后续任务
+const TASK_TIMEOUT: Duration = Duration::from_secs(5);
+/// 整个启动流程的最长时间，到期后跳过剩余任务并显示主窗口
+const STARTUP_DEADLINE: Duration = Duration::from_secs(10);
+
+/// 启动任务的错误类型，序列化为 `{ code, message, details }`
+#[derive(Debug, Clone, Error)]
+pub enum StartupError {
+    #[error("设置文件无效 {path}: {message}")]
+    InvalidSettings { path: String, message: String },
+    #[error(transparent)]
+    Adb(#[from] LogcatError),
+    #[error(transparent)]
+    Ble(#[from] BleError),
+    #[error("{0} 超时")]
+    Timeout(String),
+    #[error("{task} 异常终止: {message}")]
+    Panicked { task: String, message: String },
+    #[error("启动时间已用完，未执行{0}")]
+    DeadlineExceeded(String),
+}
+
+impl ErrorCode for StartupError {
+    fn code(&self) -> &'static str {
+        match self {
+            StartupError::InvalidSettings { .. } => "invalid_settings",
+            StartupError::Adb(e) => e.code(),
+            StartupError::Ble(e) => e.code(),
+            StartupError::Timeout(_) => "timeout",
+            StartupError::Panicked { .. } => "task_panicked",
+            StartupError::DeadlineExceeded(_) => "deadline_exceeded",
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            StartupError::InvalidSettings { path, .. } => Some(serde_json::json!({ "path": path })),
+            StartupError::Adb(e) => e.details(),
+            StartupError::Ble(e) => e.details(),
+            StartupError::Timeout(_)
+            | StartupError::Panicked { .. }
+            | StartupError::DeadlineExceeded(_) => None,
+        }
+    }
+}
+
+impl Serialize for StartupError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_error(self, serializer)
+    }
+}
+
+/// 应用设置，保存在配置目录的 settings.json 中，缺失的字段使用默认值
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct AppSettings {
+    pub ble_schemas: Vec<String>, // 启动时预加载的特征解析规则文件
+}
+
+/// 启动时执行的初始化任务
+///
+/// 任务按注册顺序依次执行；失败或超时只记录在报告中，不会阻止主窗口显示。
+/// 整个流程超过 `STARTUP_DEADLINE` 后，尚未执行的任务直接记为失败。
+#[async_trait]
+pub trait StartupTask: Send + Sync {
+    /// 稳定的任务名，前端据此识别任务
+    fn name(&self) -> &'static str;
+
+    /// 显示在启动页上的说明
+    fn label(&self) -> &'static str;
+
+    /// 成功时返回一句结果说明
+    async fn run(&self, app: &AppHandle) -> Result<String, StartupError>;
+}
+
+/// 任务状态
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Running,
+    Done,
+    Failed,
+}
+
+/// `startup-progress` 事件的负载，每个任务开始和结束时各发送一次
+#[derive(Serialize, Clone, Debug)]
+pub struct StartupProgress {
+    pub task: String,
+    pub label: String,
+    pub index: usize,
+    pub total: usize,
+    pub status: TaskStatus,
+    pub message: Option<String>,
+    pub error: Option<StartupError>, // 失败时的结构化错误
+    pub duration_ms: u64,
+}
+
+/// 启动结果，同时作为 `startup-finished` 事件的负载
+#[derive(Serialize, Clone, Debug)]
+pub struct StartupReport {
+    pub ok: bool, // 所有任务都成功
+    pub tasks: Vec<StartupProgress>,
+    pub duration_ms: u64,
+}
+
+impl StartupReport {
+    fn new(tasks: Vec<StartupProgress>, duration: Duration) -> Self {
+        StartupReport {
+            ok: tasks.iter().all(|t| t.status == TaskStatus::Done),
+            tasks,
+            duration_ms: duration.as_millis() as u64,
+        }
+    }
+}
+
+/// 启动进度和最近一次启动的结果
+///
+/// 启动页在注册事件监听之前可能已错过部分 `startup-progress`，
+/// 因此先通过 `get_startup_progress` 取当前进度；主窗口通过 `get_startup_report` 查询结果。
+#[derive(Default)]
+pub struct StartupState {
+    progress: Mutex<Vec<StartupProgress>>, // 每个任务的最新进度，按任务顺序
+    report: Mutex<Option<StartupReport>>,
+}
+
+/// 启动流程：依次执行注册的任务，向启动页推送进度，完成后切换到主窗口
+#[derive(Default)]
+pub struct Startup {
+    tasks: Vec<Arc<dyn StartupTask>>,
+}
+
+impl Startup {
+    /// 内置任务：读取设置 → 检测 adb → 探测蓝牙适配器 → 预加载缓存
+    pub fn with_default_tasks() -> Self {
+        Startup::default()
+            .register(LoadSettings)
+            .register(DetectAdb)
+            .register(ProbeBleAdapter)
+            .register(WarmCaches)
+    }
+
+    pub fn register(mut self, task: impl StartupTask + 'static) -> Self {
+        self.tasks.push(Arc::new(task));
+        self
+    }
+
+    pub async fn run(self, app: AppHandle) {
+        let started = Instant::now();
+        let deadline = started + STARTUP_DEADLINE;
+        let total = self.tasks.len();
+        let mut reports = Vec::with_capacity(total);
+
+        for (index, task) in self.tasks.iter().enumerate() {
+            let mut progress = StartupProgress {
+                task: task.name().to_string(),
+                label: task.label().to_string(),
+                index,
+                total,
+                status: TaskStatus::Running,
+                message: None,
+                error: None,
+                duration_ms: 0,
+            };
+            emit_progress(&app, &progress);
+
+            let task_started = Instant::now();
+            let result = match task_budget(deadline.saturating_duration_since(task_started)) {
+                Some(timeout) => {
+                    let run = {
+                        let (task, app) = (task.clone(), app.clone());
+                        async move { task.run(&app).await }
+                    };
+                    run_guarded(task.label(), timeout, run).await
+                }
+                None => Err(StartupError::DeadlineExceeded(task.label().to_string())),
+            };
+            match result {
+                Ok(message) => {
+                    println!("Startup task {}: {}", task.name(), message);
+                    progress.status = TaskStatus::Done;
+                    progress.message = Some(message);
+                }
+                Err(e) => {
+                    eprintln!("Startup task {} failed: {}", task.name(), e);
+                    progress.status = TaskStatus::Failed;
+                    progress.message = Some(e.to_string());
+                    progress.error = Some(e);
+                }
+            }
+            progress.duration_ms = task_started.elapsed().as_millis() as u64;
+            emit_progress(&app, &progress);
+            reports.push(progress);
+        }
+
+        let report = StartupReport::new(reports, started.elapsed());
+        println!(
+            "Startup finished in {} ms ({})",
+            report.duration_ms,
+            if report.ok { "ok" } else { "with failures" }
+        );
+        if let Err(e) = app.emit("startup-finished", &report) {
+            eprintln!("Failed to emit startup result: {}", e);
+        }
+        if let Some(state) = app.try_state::<StartupState>() {
+            *state.report.lock().unwrap() = Some(report);
+        }
+
+        show_main_window(&app);
+    }
+}
+
+/// 任务可用的执行时间：单个任务的上限与启动剩余时间中较小者，剩余时间用完时返回 None
+fn task_budget(remaining: Duration) -> Option<Duration> {
+    Some(remaining.min(TASK_TIMEOUT)).filter(|budget| !budget.is_zero())
+}
+
+/// 在独立的任务中执行，超时和 panic 都转换为错误，不会中断后续任务和主窗口的显示
+async fn run_guarded<F>(label: &str, timeout: Duration, run: F) -> Result<String, StartupError>
+where
+    F: Future<Output = Result<String, StartupError>> + Send + 'static,
+{
+    let mut handle = tokio::spawn(run);
+    match tokio::time::timeout(timeout, &mut handle).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => Err(StartupError::Panicked {
+            task: label.to_string(),
+            message: e.to_string(),
+        }),
+        Err(_) => {
+            handle.abort();
+            Err(StartupError::Timeout(label.to_string()))
+        }
+    }
+}
+
+fn emit_progress(app: &AppHandle, progress: &StartupProgress) {
+    if let Some(state) = app.try_state::<StartupState>() {
+        state.record(progress.clone());
+    }
+    if let Err(e) = app.emit_to(SPLASH_WINDOW, "startup-progress", progress) {
+        eprintln!("Failed to emit startup progress: {}", e);
+    }
+}
+
+/// 显示主窗口并关闭启动页；窗口不存在或操作失败时只记录日志
+fn show_main_window(app: &AppHandle) {
+    match app.get_webview_window(MAIN_WINDOW) {
+        Some(main) => {
+            if let Err(e) = main.show().and_then(|_| main.set_focus()) {
+                eprintln!("Failed to show main window: {}", e);
+            }
+        }
+        None => eprintln!("Main window not found"),
+    }
+    if let Some(splash) = app.get_webview_window(SPLASH_WINDOW) {
+        if let Err(e) = splash.close() {
+            eprintln!("Failed to close splashscreen: {}", e);
+        }
+    }
+}
+
+impl StartupState {
+    fn record(&self, progress: StartupProgress) {
+        let mut tasks = self.progress.lock().unwrap();
+        match tasks.iter_mut().find(|t| t.index == progress.index) {
+            Some(existing) => *existing = progress,
+            None => tasks.push(progress),
+        }
+    }
+}
+
+/// 最近一次启动的任务结果；启动尚未完成时返回 null
+#[tauri::command]
+pub fn get_startup_report(state: State<'_, StartupState>) -> Option<StartupReport> {
+    state.report.lock().unwrap().clone()
+}
+
+/// 已开始的启动任务的最新进度，启动页打开时用来补上错过的 `startup-progress`
+#[tauri::command]
+pub fn get_startup_progress(state: State<'_, StartupState>) -> Vec<StartupProgress> {
+    state.progress.lock().unwrap().clone()
+}
+
+fn config_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_config_dir().ok()
+}
+
+/// 读取 settings.json 并交给 Tauri 管理；文件不存在时使用默认设置
+struct LoadSettings;
+
+#[async_trait]
+impl StartupTask for LoadSettings {
+    fn name(&self) -> &'static str {
+        "settings"
+    }
+
+    fn label(&self) -> &'static str {
+        "读取设置"
+    }
+
+    async fn run(&self, app: &AppHandle) -> Result<String, StartupError> {
+        let path = config_dir(app).map(|dir| dir.join("settings.json"));
+        let result = match &path {
+            Some(path) if path.exists() => read_settings(path).map(|s| (s, "已读取设置")),
+            _ => Ok((AppSettings::default(), "使用默认设置")),
+        };
+
+        // 读取失败也注册默认设置，保证后续任务和命令能拿到状态
+        let (settings, message) = match result {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                app.manage(AppSettings::default());
+                return Err(e);
+            }
+        };
+        app.manage(settings);
+        Ok(message.to_string())
+    }
+}
+
+fn read_settings(path: &Path) -> Result<AppSettings, StartupError> {
+    let invalid = |message: String| StartupError::InvalidSettings {
+        path: path.display().to_string(),
+        message,
+    };
+    let text = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    serde_json::from_str(&text).map_err(|e| invalid(e.to_string()))
+}
+
+/// 检测 adb 是否可用（logcat 功能依赖 adb）
+struct DetectAdb;
+
+#[async_trait]
+impl StartupTask for DetectAdb {
+    fn name(&self) -> &'static str {
+        "adb"
+    }
+
+    fn label(&self) -> &'static str {
+        "检测 adb"
+    }
+
+    async fn run(&self, _app: &AppHandle) -> Result<String, StartupError> {
+        let version = tokio::task::spawn_blocking(adb_version)
+            .await
+            .map_err(|e| LogcatError::AdbFailed {
+                command: "version".to_string(),
+                message: e.to_string(),
+            })??;
+        Ok(version)
+    }
+}
+
+/// 初始化当前BLE后端，确认蓝牙适配器可用
+struct ProbeBleAdapter;
+
+#[async_trait]
+impl StartupTask for ProbeBleAdapter {
+    fn name(&self) -> &'static str {
+        "ble_adapter"
+    }
+
+    fn label(&self) -> &'static str {
+        "探测蓝牙适配器"
+    }
+
+    async fn run(&self, app: &AppHandle) -> Result<String, StartupError> {
+        let state = app.state::<BleState>();
+        let backend = state.backend();
+        backend.advertisements().await?;
+        Ok(format!("{} 后端可用", backend.name()))
+    }
+}
+
+/// 预加载设置中列出的、以及配置目录 schemas/ 下的特征解析规则
+struct WarmCaches;
+
+#[async_trait]
+impl StartupTask for WarmCaches {
+    fn name(&self) -> &'static str {
+        "caches"
+    }
+
+    fn label(&self) -> &'static str {
+        "预加载缓存"
+    }
+
+    async fn run(&self, app: &AppHandle) -> Result<String, StartupError> {
+        let mut paths: Vec<String> = app
+            .try_state::<AppSettings>()
+            .map(|settings| settings.ble_schemas.clone())
+            .unwrap_or_default();
+        if let Some(dir) = config_dir(app).map(|dir| dir.join("schemas")) {
+            if let Ok(entries) = std::fs::read_dir(dir) {
+                let mut files: Vec<String> = entries
+                    .filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .filter(|path| {
+                        path.extension()
+                            .is_some_and(|ext| ext == "json" || ext == "toml")
+                    })
+                    .map(|path| path.to_string_lossy().to_string())
+                    .collect();
+                files.sort();
+                paths.extend(files);
+            }
+        }
+
+        let state = app.state::<BleState>();
+        let mut first_error = None;
+        let mut loaded = 0;
+        for path in &paths {
+            match state.load_schema(path) {
+                Ok(_) => loaded += 1,
+                Err(e) => {
+                    eprintln!("Failed to preload BLE schema {}: {}", path, e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e.into()),
+            None => Ok(format!("已加载 {} 个特征解析规则文件", loaded)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_settings(name: &str, text: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}.json", name, std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    fn progress(index: usize, status: TaskStatus) -> StartupProgress {
+        StartupProgress {
+            task: format!("task-{}", index),
+            label: format!("任务{}", index),
+            index,
+            total: 2,
+            status,
+            message: None,
+            error: None,
+            duration_ms: 0,
+        }
+    }
+
+    #[test]
+    fn reads_settings_with_defaults() {
+        let path = temp_settings("settings-ok", r#"{"ble_schemas":["a.json"],"other":1}"#);
+        assert_eq!(read_settings(&path).unwrap().ble_schemas, ["a.json"]);
+        std::fs::write(&path, "{}").unwrap();
+        assert!(read_settings(&path).unwrap().ble_schemas.is_empty());
+
+        std::fs::write(&path, r#"{"ble_schemas":"a.json"}"#).unwrap();
+        let error = read_settings(&path).unwrap_err();
+        assert_eq!(error.code(), "invalid_settings");
+        assert_eq!(
+            error.details(),
+            Some(serde_json::json!({ "path": path.display().to_string() }))
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert!(read_settings(&path).is_err());
+    }
+
+    #[tokio::test]
+    async fn guarded_task_reports_failures() {
+        let timeout = Duration::from_millis(50);
+        let ok = run_guarded("ok", timeout, async { Ok("done".to_string()) }).await;
+        assert_eq!(ok.unwrap(), "done");
+
+        let failed = run_guarded("failed", timeout, async {
+            Err(StartupError::Timeout("inner".to_string()))
+        })
+        .await;
+        assert!(matches!(failed, Err(StartupError::Timeout(label)) if label == "inner"));
+
+        let slow = run_guarded("slow", timeout, async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(String::new())
+        })
+        .await;
+        assert!(matches!(slow, Err(StartupError::Timeout(label)) if label == "slow"));
+
+        let panicked = run_guarded("panics", timeout, async {
+            panic!("boom");
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(panicked.code(), "task_panicked");
+        assert!(panicked.to_string().starts_with("panics 异常终止"));
+    }
+
+    #[test]
+    fn task_budget_is_limited_by_startup_deadline() {
+        assert_eq!(task_budget(STARTUP_DEADLINE), Some(TASK_TIMEOUT));
+        assert_eq!(
+            task_budget(Duration::from_millis(200)),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(task_budget(Duration::ZERO), None);
+        let skipped = StartupError::DeadlineExceeded("检测 adb".to_string());
+        assert_eq!(skipped.code(), "deadline_exceeded");
+        assert_eq!(skipped.to_string(), "启动时间已用完，未执行检测 adb");
+    }
+
+    #[test]
+    fn report_ok_only_when_all_tasks_done() {
+        let done = vec![progress(0, TaskStatus::Done), progress(1, TaskStatus::Done)];
+        assert!(StartupReport::new(done, Duration::from_millis(5)).ok);
+        let failed = vec![progress(0, TaskStatus::Done), progress(1, TaskStatus::Failed)];
+        let report = StartupReport::new(failed, Duration::from_millis(5));
+        assert!(!report.ok);
+        assert_eq!(report.duration_ms, 5);
+    }
+
+    #[test]
+    fn state_keeps_latest_progress_per_task() {
+        let state = StartupState::default();
+        state.record(progress(0, TaskStatus::Running));
+        state.record(progress(0, TaskStatus::Done));
+        state.record(progress(1, TaskStatus::Running));
+        let tasks = state.progress.lock().unwrap();
+        let statuses: Vec<_> = tasks.iter().map(|t| (t.index, t.status)).collect();
+        assert_eq!(statuses, [(0, TaskStatus::Done), (1, TaskStatus::Running)]);
+    }
+}
@@ -102,6 +102,7 @@ export default function JwtEncoderPage() {
     { value: "ES256", label: "ES256" },
     { value: "ES512", label: "ES512" },
     { value: "ES384", label: "ES384" },
+    { value: "EdDSA", label: "EdDSA" },
   ];
 
   useEffect(() => {